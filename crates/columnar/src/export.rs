@@ -0,0 +1,118 @@
+use std::{collections::HashMap, io, sync::Arc};
+
+use arrow_array::{ArrayRef, Float64Array, RecordBatch, TimestampNanosecondArray};
+use arrow_schema::{DataType, Field, Schema, TimeUnit};
+use chrono::SecondsFormat;
+use log_if::prelude::*;
+use parquet::{
+    arrow::ArrowWriter, basic::Compression, file::properties::WriterProperties, format::KeyValue,
+};
+
+use crate::{DESCRIPTIVE_NAME_KEY, EXPECTED_RANGE_KEY, FIRST_TIMESTAMP_KEY};
+
+/// Name of the timestamp column in exported files
+pub const TIMESTAMP_COLUMN: &str = "timestamp";
+
+/// Writes `log` as a Parquet file to `writer`.
+///
+/// The timestamp column holds the union of the timestamps of all the plots, and the value of a plot is null
+/// at the timestamps where it has no point. Plots with the same name get a counter appended to keep the
+/// column names unique.
+pub fn write_parquet<W: io::Write + Send, P: Plotable + ?Sized>(
+    writer: W,
+    log: &P,
+) -> io::Result<()> {
+    let timestamps = union_of_timestamps(log.raw_plots());
+
+    let mut fields = vec![Field::new(
+        TIMESTAMP_COLUMN,
+        DataType::Timestamp(TimeUnit::Nanosecond, Some("UTC".into())),
+        false,
+    )];
+    let mut columns: Vec<ArrayRef> = vec![Arc::new(
+        TimestampNanosecondArray::from(timestamps.clone()).with_timezone("UTC"),
+    )];
+    let mut column_names = vec![TIMESTAMP_COLUMN.to_owned()];
+    for raw_plot in log.raw_plots() {
+        let name = unique_name(&column_names, raw_plot.name());
+        fields.push(
+            Field::new(&name, DataType::Float64, true).with_metadata(HashMap::from([(
+                EXPECTED_RANGE_KEY.to_owned(),
+                format!("{:?}", raw_plot.expected_range()),
+            )])),
+        );
+        columns.push(Arc::new(align_values(&timestamps, raw_plot.points())));
+        column_names.push(name);
+    }
+
+    let metadata = schema_metadata(log);
+    let schema = Arc::new(Schema::new(fields).with_metadata(metadata.iter().cloned().collect()));
+    let batch = RecordBatch::try_new(Arc::clone(&schema), columns).map_err(io::Error::other)?;
+    let properties = WriterProperties::builder()
+        .set_compression(Compression::SNAPPY)
+        .set_key_value_metadata(Some(
+            metadata
+                .into_iter()
+                .map(|(key, value)| KeyValue::new(key, value))
+                .collect(),
+        ))
+        .build();
+    let mut writer =
+        ArrowWriter::try_new(writer, schema, Some(properties)).map_err(io::Error::other)?;
+    writer.write(&batch).map_err(io::Error::other)?;
+    writer.close().map_err(io::Error::other)?;
+    Ok(())
+}
+
+/// The descriptive name, first timestamp and metadata of the `log`, with unique keys
+fn schema_metadata<P: Plotable + ?Sized>(log: &P) -> Vec<(String, String)> {
+    let mut metadata = vec![
+        (
+            DESCRIPTIVE_NAME_KEY.to_owned(),
+            log.descriptive_name().to_owned(),
+        ),
+        (
+            FIRST_TIMESTAMP_KEY.to_owned(),
+            log.first_timestamp()
+                .to_rfc3339_opts(SecondsFormat::Nanos, true),
+        ),
+    ];
+    for (key, value) in log.metadata().unwrap_or_default() {
+        let taken: Vec<String> = metadata.iter().map(|(key, _)| key.clone()).collect();
+        metadata.push((unique_name(&taken, &key), value));
+    }
+    metadata
+}
+
+/// The sorted and deduplicated timestamps of all the `raw_plots` in nanoseconds
+fn union_of_timestamps(raw_plots: &[RawPlot]) -> Vec<i64> {
+    let mut timestamps: Vec<i64> = raw_plots
+        .iter()
+        .flat_map(|plot| plot.points().iter().map(|[ts, _]| ts.round() as i64))
+        .collect();
+    timestamps.sort_unstable();
+    timestamps.dedup();
+    timestamps
+}
+
+/// The values of the `points` at each of the `timestamps`, null where there is no point at that timestamp
+fn align_values(timestamps: &[i64], points: &[[f64; 2]]) -> Float64Array {
+    let mut values = vec![None; timestamps.len()];
+    for [ts, value] in points {
+        if let Ok(idx) = timestamps.binary_search(&(ts.round() as i64)) {
+            values[idx] = Some(*value);
+        }
+    }
+    Float64Array::from(values)
+}
+
+/// Appends a counter to `name` if it is already among the `taken` names
+fn unique_name(taken: &[String], name: &str) -> String {
+    let mut unique = name.to_owned();
+    let mut counter = 1;
+    while taken.contains(&unique) {
+        counter += 1;
+        unique = format!("{name} ({counter})");
+    }
+    unique
+}
@@ -0,0 +1,233 @@
+use std::{io, sync::Arc};
+
+use arrow_ipc::reader::FileReader;
+use log_if::prelude::*;
+use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+
+use crate::ColumnarLog;
+
+/// Magic bytes at the start and end of a Parquet file
+const PARQUET_MAGIC: &[u8] = b"PAR1";
+/// Magic bytes at the start of an Arrow IPC file
+const ARROW_IPC_MAGIC: &[u8] = b"ARROW1";
+
+/// Apache Parquet files, e.g. exported with [`crate::write_parquet`]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ParquetFormat;
+
+impl LogFormat for ParquetFormat {
+    fn name(&self) -> &str {
+        "Parquet"
+    }
+
+    fn description(&self) -> &str {
+        "Apache Parquet table with a timestamp column"
+    }
+
+    fn detect(&self, buf: &[u8]) -> Confidence {
+        if buf.starts_with(PARQUET_MAGIC) && buf.ends_with(PARQUET_MAGIC) {
+            Confidence::High
+        } else {
+            Confidence::None
+        }
+    }
+
+    fn parse(&self, buf: &[u8]) -> io::Result<ParsedLog> {
+        let builder = ParquetRecordBatchReaderBuilder::try_new(bytes::Bytes::copy_from_slice(buf))?;
+        let schema = Arc::clone(builder.schema());
+        let reader = builder.build()?;
+        let batches = reader
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        let log = ColumnarLog::from_batches(&schema, &batches, "Parquet")?;
+        Ok(ParsedLog {
+            log: Box::new(log),
+            parsed_bytes: buf.len(),
+            total_bytes: buf.len(),
+            diagnostics: vec![],
+        })
+    }
+}
+
+/// Apache Arrow IPC files, also known as Feather V2 files
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ArrowIpcFormat;
+
+impl LogFormat for ArrowIpcFormat {
+    fn name(&self) -> &str {
+        "Arrow IPC"
+    }
+
+    fn description(&self) -> &str {
+        "Apache Arrow IPC (Feather V2) table with a timestamp column"
+    }
+
+    fn detect(&self, buf: &[u8]) -> Confidence {
+        if buf.starts_with(ARROW_IPC_MAGIC) && buf.ends_with(ARROW_IPC_MAGIC) {
+            Confidence::High
+        } else {
+            Confidence::None
+        }
+    }
+
+    fn parse(&self, buf: &[u8]) -> io::Result<ParsedLog> {
+        let reader = FileReader::try_new(io::Cursor::new(buf), None)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        let schema = reader.schema();
+        let batches = reader
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        let log = ColumnarLog::from_batches(&schema, &batches, "Arrow IPC")?;
+        Ok(ParsedLog {
+            log: Box::new(log),
+            parsed_bytes: buf.len(),
+            total_bytes: buf.len(),
+            diagnostics: vec![],
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use arrow_array::{BooleanArray, Float64Array, Int64Array, RecordBatch, StringArray};
+    use arrow_ipc::writer::FileWriter;
+    use arrow_schema::{DataType, Field, Schema};
+    use chrono::{DateTime, Utc};
+    use pretty_assertions::assert_eq;
+    use testresult::TestResult;
+
+    use super::*;
+    use crate::write_parquet;
+
+    #[test]
+    fn test_parquet_round_trip() -> TestResult {
+        let first_timestamp = DateTime::from_timestamp_nanos(1_700_000_000_123_456_789);
+        let log = ColumnarLog::new(
+            "Mbed PID v2".to_owned(),
+            first_timestamp,
+            vec![
+                RawPlot::new(
+                    "RPM".to_owned(),
+                    vec![[1.0, 2500.0], [2.0, 2600.0]],
+                    ExpectedPlotRange::Thousands,
+                ),
+                RawPlot::new(
+                    "RPM".to_owned(),
+                    vec![[2.0, 0.6], [3.0, 0.5]],
+                    ExpectedPlotRange::Percentage,
+                ),
+            ],
+            vec![
+                ("Project Version".to_owned(), "2.3.2".to_owned()),
+                ("Config values".to_owned(), String::new()),
+            ],
+        );
+        let mut buf = vec![];
+        write_parquet(&mut buf, &log)?;
+        assert_eq!(ParquetFormat.detect(&buf), Confidence::High);
+        assert_eq!(ArrowIpcFormat.detect(&buf), Confidence::None);
+
+        let parsed = ParquetFormat.parse(&buf)?.log;
+
+        assert_eq!(parsed.descriptive_name(), "Mbed PID v2");
+        assert_eq!(parsed.first_timestamp(), first_timestamp);
+        assert_eq!(parsed.raw_plots()[0], log.raw_plots()[0]);
+        assert_eq!(parsed.raw_plots()[1].name(), "RPM (2)");
+        assert_eq!(parsed.raw_plots()[1].points(), log.raw_plots()[1].points());
+        assert_eq!(
+            parsed.raw_plots()[1].expected_range(),
+            ExpectedPlotRange::Percentage
+        );
+        assert_eq!(
+            parsed.metadata().unwrap_or_default(),
+            [
+                ("Config values".to_owned(), String::new()),
+                ("Project Version".to_owned(), "2.3.2".to_owned()),
+                ("Timestamp Column".to_owned(), "timestamp".to_owned()),
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_arrow_ipc_detects_timestamp_unit_from_column_name() -> TestResult {
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("label", DataType::Utf8, false),
+            Field::new("Time [ms]", DataType::Int64, false),
+            Field::new("temperature", DataType::Float64, true),
+            Field::new("heater on", DataType::Boolean, false),
+        ]));
+        let batch = RecordBatch::try_new(
+            Arc::clone(&schema),
+            vec![
+                Arc::new(StringArray::from(vec!["a", "b"])),
+                Arc::new(Int64Array::from(vec![1_000, 2_000])),
+                Arc::new(Float64Array::from(vec![Some(20.5), None])),
+                Arc::new(BooleanArray::from(vec![false, true])),
+            ],
+        )?;
+        let mut buf = vec![];
+        let mut writer = FileWriter::try_new(&mut buf, &schema)?;
+        writer.write(&batch)?;
+        writer.finish()?;
+        drop(writer);
+        assert_eq!(ArrowIpcFormat.detect(&buf), Confidence::High);
+
+        let parsed = ArrowIpcFormat.parse(&buf)?.log;
+
+        assert_eq!(parsed.descriptive_name(), "Arrow IPC");
+        assert_eq!(
+            parsed.first_timestamp(),
+            DateTime::<Utc>::from_timestamp_nanos(1_000_000_000)
+        );
+        assert_eq!(parsed.raw_plots().len(), 2);
+        assert_eq!(parsed.raw_plots()[0].name(), "temperature");
+        assert_eq!(parsed.raw_plots()[0].points(), [[1e9, 20.5]]);
+        assert_eq!(parsed.raw_plots()[1].points(), [[1e9, 0.0], [2e9, 1.0]]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_columns_named_partly_like_a_timestamp_are_not_the_timestamp() -> TestResult {
+        let schema = Schema::new(vec![
+            Field::new("T_STANDBY", DataType::Int64, false),
+            Field::new("ts_count", DataType::Int64, false),
+            Field::new("t motor", DataType::Float64, false),
+            Field::new("t [s]", DataType::Float64, false),
+        ]);
+        let batch = RecordBatch::try_new(
+            Arc::new(schema.clone()),
+            vec![
+                Arc::new(Int64Array::from(vec![5, 5])),
+                Arc::new(Int64Array::from(vec![1, 2])),
+                Arc::new(Float64Array::from(vec![40.0, 41.0])),
+                Arc::new(Float64Array::from(vec![10.0, 11.0])),
+            ],
+        )?;
+
+        let log = ColumnarLog::from_batches(&schema, &[batch], "test")?;
+
+        assert!(log
+            .metadata()
+            .unwrap_or_default()
+            .contains(&("Timestamp Column".to_owned(), "t [s]".to_owned())));
+        let names: Vec<&str> = log.raw_plots().iter().map(RawPlot::name).collect();
+        assert_eq!(names, ["T_STANDBY", "ts_count", "t motor"]);
+        assert_eq!(log.raw_plots()[2].points(), [[10e9, 40.0], [11e9, 41.0]]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_missing_timestamp_column_is_an_error() -> TestResult {
+        let schema = Schema::new(vec![Field::new("voltage", DataType::Float64, false)]);
+        let batch = RecordBatch::try_new(
+            Arc::new(schema.clone()),
+            vec![Arc::new(Float64Array::from(vec![1.0]))],
+        )?;
+        let err = ColumnarLog::from_batches(&schema, &[batch], "test")
+            .err()
+            .ok_or("expected an error")?;
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        Ok(())
+    }
+}
@@ -0,0 +1,295 @@
+use std::{io, sync::Arc};
+
+use arrow_array::{cast::AsArray, types::Float64Type, Array, ArrayRef, RecordBatch};
+use arrow_cast::cast;
+use arrow_schema::{DataType, Field, Schema, TimeUnit};
+use chrono::{DateTime, Utc};
+use log_if::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{DESCRIPTIVE_NAME_KEY, EXPECTED_RANGE_KEY, FIRST_TIMESTAMP_KEY};
+
+/// Column names that mark a numeric column as the timestamp column, optionally followed by a unit, e.g. `t [s]`
+const TIMESTAMP_NAMES: [&str; 6] = ["timestamp", "time", "datetime", "ts", "epoch", "t"];
+
+/// Schema metadata that is written by arrow itself and is not shown as metadata of the log
+const ARROW_SCHEMA_KEY: &str = "ARROW:schema";
+
+/// A log loaded from a columnar file like Parquet or Arrow IPC, or about to be exported to Parquet
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ColumnarLog {
+    descriptive_name: String,
+    first_timestamp: DateTime<Utc>,
+    raw_plots: Vec<RawPlot>,
+    metadata: Vec<(String, String)>,
+}
+
+impl ColumnarLog {
+    pub fn new(
+        descriptive_name: String,
+        first_timestamp: DateTime<Utc>,
+        raw_plots: Vec<RawPlot>,
+        metadata: Vec<(String, String)>,
+    ) -> Self {
+        Self {
+            descriptive_name,
+            first_timestamp,
+            raw_plots,
+            metadata,
+        }
+    }
+
+    /// Creates a log from the record `batches` of a table with the given `schema`.
+    ///
+    /// The timestamp column is the first column with a timestamp or date type, or else the first numeric
+    /// column named like a timestamp, e.g. `time`, `timestamp_ns` or `time [ms]`. The unit of a numeric
+    /// timestamp is taken from its name, or else guessed from the magnitude of the values. Every other numeric
+    /// or boolean column becomes a plot, other columns are skipped.
+    ///
+    /// `default_name` is the descriptive name if the schema metadata does not have one.
+    pub fn from_batches(
+        schema: &Schema,
+        batches: &[RecordBatch],
+        default_name: &str,
+    ) -> io::Result<Self> {
+        let Some(ts_idx) = timestamp_column(schema) else {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!(
+                    "No timestamp column found among the columns: {}",
+                    schema
+                        .fields()
+                        .iter()
+                        .map(|field| field.name().as_str())
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                ),
+            ));
+        };
+        let ts_field = schema.field(ts_idx);
+        let mut timestamps = vec![];
+        for batch in batches {
+            timestamps.extend(column_as_f64(batch.column(ts_idx))?);
+        }
+        let ns_per_unit = ns_per_unit(ts_field, &timestamps);
+        let timestamps: Vec<Option<f64>> = timestamps
+            .into_iter()
+            .map(|ts| ts.map(|ts| ts * ns_per_unit))
+            .collect();
+
+        let mut raw_plots = vec![];
+        for (idx, field) in schema.fields().iter().enumerate() {
+            if idx == ts_idx || !is_plotable(field.data_type()) {
+                continue;
+            }
+            let mut values = vec![];
+            for batch in batches {
+                values.extend(column_as_f64(batch.column(idx))?);
+            }
+            let points: Vec<[f64; 2]> = timestamps
+                .iter()
+                .zip(values)
+                .filter_map(|(ts, value)| Some([(*ts)?, value?]))
+                .filter(|[ts, value]| ts.is_finite() && value.is_finite())
+                .collect();
+            if points.is_empty() {
+                log::debug!("Skipping column '{}' without values", field.name());
+                continue;
+            }
+            let expected_range = field
+                .metadata()
+                .get(EXPECTED_RANGE_KEY)
+                .and_then(|range| parse_expected_range(range))
+                .unwrap_or_else(|| expected_range(&points));
+            raw_plots.push(RawPlot::new(field.name().clone(), points, expected_range));
+        }
+
+        Ok(Self::with_schema_metadata(
+            schema,
+            ts_field.name(),
+            raw_plots,
+            default_name,
+        ))
+    }
+
+    /// Splits the schema metadata into the descriptive name, the first timestamp and the remaining metadata
+    fn with_schema_metadata(
+        schema: &Schema,
+        timestamp_column: &str,
+        raw_plots: Vec<RawPlot>,
+        default_name: &str,
+    ) -> Self {
+        let mut descriptive_name = default_name.to_owned();
+        let mut first_timestamp = None;
+        let mut metadata = vec![];
+        for (key, value) in schema.metadata() {
+            match key.as_str() {
+                DESCRIPTIVE_NAME_KEY => descriptive_name.clone_from(value),
+                FIRST_TIMESTAMP_KEY => {
+                    first_timestamp = DateTime::parse_from_rfc3339(value)
+                        .map(|ts| ts.to_utc())
+                        .ok();
+                }
+                ARROW_SCHEMA_KEY => (),
+                _ => metadata.push((key.clone(), value.clone())),
+            }
+        }
+        metadata.sort();
+        metadata.push(("Timestamp Column".to_owned(), timestamp_column.to_owned()));
+        let first_timestamp = first_timestamp.unwrap_or_else(|| {
+            DateTime::from_timestamp_nanos(
+                raw_plots
+                    .iter()
+                    .filter_map(|plot| plot.points().first())
+                    .map(|[ts, _]| *ts)
+                    .reduce(f64::min)
+                    .unwrap_or_default() as i64,
+            )
+        });
+        Self {
+            descriptive_name,
+            first_timestamp,
+            raw_plots,
+            metadata,
+        }
+    }
+}
+
+impl Plotable for ColumnarLog {
+    fn raw_plots(&self) -> &[RawPlot] {
+        &self.raw_plots
+    }
+
+    fn first_timestamp(&self) -> DateTime<Utc> {
+        self.first_timestamp
+    }
+
+    fn descriptive_name(&self) -> &str {
+        &self.descriptive_name
+    }
+
+    fn labels(&self) -> Option<&[PlotLabels]> {
+        None
+    }
+
+    fn metadata(&self) -> Option<Vec<(String, String)>> {
+        Some(self.metadata.clone())
+    }
+}
+
+/// The index of the timestamp column of the `schema`, see [`ColumnarLog::from_batches`]
+fn timestamp_column(schema: &Schema) -> Option<usize> {
+    let fields = schema.fields();
+    fields
+        .iter()
+        .position(|field| {
+            matches!(
+                field.data_type(),
+                DataType::Timestamp(..) | DataType::Date32 | DataType::Date64
+            )
+        })
+        .or_else(|| {
+            fields
+                .iter()
+                .position(|field| field.data_type().is_numeric() && is_timestamp_name(field.name()))
+        })
+}
+
+/// Returns true if the column name is one of the [`TIMESTAMP_NAMES`], optionally followed by a unit,
+/// e.g. `time`, `t [s]` or `timestamp_ns` but not `T_STANDBY` or `ts_count`
+fn is_timestamp_name(name: &str) -> bool {
+    let parts: Vec<String> = name_parts(name).collect();
+    match parts.as_slice() {
+        [name] => TIMESTAMP_NAMES.contains(&name.as_str()),
+        [name, unit] => {
+            TIMESTAMP_NAMES.contains(&name.as_str()) && ns_per_named_unit(unit).is_some()
+        }
+        _ => false,
+    }
+}
+
+/// The lowercase alphanumeric parts of a column name, e.g. `time` and `ms` of `Time [ms]`
+fn name_parts(name: &str) -> impl Iterator<Item = String> + '_ {
+    name.split(|c: char| !c.is_alphanumeric())
+        .filter(|part| !part.is_empty())
+        .map(str::to_lowercase)
+}
+
+/// How many nanoseconds one unit of the timestamp column is
+fn ns_per_unit(field: &Field, timestamps: &[Option<f64>]) -> f64 {
+    match field.data_type() {
+        DataType::Timestamp(TimeUnit::Second, _) => 1e9,
+        DataType::Timestamp(TimeUnit::Millisecond, _) | DataType::Date64 => 1e6,
+        DataType::Timestamp(TimeUnit::Microsecond, _) => 1e3,
+        DataType::Timestamp(TimeUnit::Nanosecond, _) => 1.0,
+        DataType::Date32 => 86_400e9,
+        _ => name_parts(field.name())
+            .find_map(|part| ns_per_named_unit(&part))
+            .unwrap_or_else(|| ns_per_unit_from_magnitude(timestamps)),
+    }
+}
+
+/// How many nanoseconds a unit written in a column name is, e.g. `ms`
+fn ns_per_named_unit(unit: &str) -> Option<f64> {
+    match unit {
+        "ns" | "nanoseconds" => Some(1.0),
+        "us" | "µs" | "microseconds" => Some(1e3),
+        "ms" | "milliseconds" => Some(1e6),
+        "s" | "sec" | "seconds" => Some(1e9),
+        _ => None,
+    }
+}
+
+/// Guesses the unit of Unix timestamps from their magnitude, any date after 1973 is recognized correctly
+fn ns_per_unit_from_magnitude(timestamps: &[Option<f64>]) -> f64 {
+    let max = timestamps
+        .iter()
+        .flatten()
+        .map(|ts| ts.abs())
+        .filter(|ts| ts.is_finite())
+        .fold(0.0, f64::max);
+    if max >= 1e17 {
+        1.0
+    } else if max >= 1e14 {
+        1e3
+    } else if max >= 1e11 {
+        1e6
+    } else {
+        1e9
+    }
+}
+
+fn is_plotable(data_type: &DataType) -> bool {
+    data_type.is_numeric() || *data_type == DataType::Boolean
+}
+
+/// Casts a numeric, boolean or temporal column to its values as `f64`, temporal values in their own unit
+fn column_as_f64(array: &ArrayRef) -> io::Result<Vec<Option<f64>>> {
+    let array = match array.data_type() {
+        DataType::Timestamp(..) | DataType::Date64 => cast(array, &DataType::Int64),
+        DataType::Date32 => cast(array, &DataType::Int32),
+        _ => Ok(Arc::clone(array)),
+    }
+    .and_then(|array| cast(&array, &DataType::Float64))
+    .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+    Ok(array.as_primitive::<Float64Type>().iter().collect())
+}
+
+/// Picks the [`ExpectedPlotRange`] from the largest absolute value of the points
+fn expected_range(points: &[[f64; 2]]) -> ExpectedPlotRange {
+    let max_abs = points.iter().map(|[_, val]| val.abs()).fold(0.0, f64::max);
+    if max_abs <= 100.0 {
+        ExpectedPlotRange::OneToOneHundred
+    } else {
+        ExpectedPlotRange::Thousands
+    }
+}
+
+fn parse_expected_range(range: &str) -> Option<ExpectedPlotRange> {
+    match range {
+        "Percentage" => Some(ExpectedPlotRange::Percentage),
+        "OneToOneHundred" => Some(ExpectedPlotRange::OneToOneHundred),
+        "Thousands" => Some(ExpectedPlotRange::Thousands),
+        _ => None,
+    }
+}
@@ -0,0 +1,23 @@
+//! Export of plotable logs to Apache Parquet, and loading of Parquet and Arrow IPC files as plotable logs.
+//!
+//! An exported file has a `timestamp` column with nanosecond precision and a nullable `Float64` column per plot.
+//! The metadata of the log is stored in the key-value metadata of the schema, so the file can be opened in the
+//! viewer again, or processed by any tool that reads Parquet.
+//!
+//! Files that were not exported by the viewer are loaded too, as long as a timestamp column can be
+//! auto-detected, see [`ColumnarLog::from_batches`].
+
+mod export;
+mod format;
+mod import;
+
+pub use export::{write_parquet, TIMESTAMP_COLUMN};
+pub use format::{ArrowIpcFormat, ParquetFormat};
+pub use import::ColumnarLog;
+
+/// Schema metadata key for the descriptive name of the log
+const DESCRIPTIVE_NAME_KEY: &str = "descriptive_name";
+/// Schema metadata key for the first timestamp of the log, in RFC 3339 format
+const FIRST_TIMESTAMP_KEY: &str = "first_timestamp";
+/// Field metadata key for the [`log_if::prelude::ExpectedPlotRange`] of a plot
+const EXPECTED_RANGE_KEY: &str = "expected_range";
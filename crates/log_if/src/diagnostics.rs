@@ -0,0 +1,149 @@
+use std::{fmt, io};
+
+use serde::{Deserialize, Serialize};
+
+/// The category of a failure encountered while parsing log entries
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+pub enum ParseErrorKind {
+    /// The entry was malformed, e.g. an invalid enum value or a line with unexpected fields
+    InvalidData,
+    /// The data ended in the middle of an entry, typically a log that was cut short
+    UnexpectedEof,
+    Other,
+}
+
+impl From<io::ErrorKind> for ParseErrorKind {
+    fn from(kind: io::ErrorKind) -> Self {
+        match kind {
+            io::ErrorKind::InvalidData | io::ErrorKind::InvalidInput => Self::InvalidData,
+            io::ErrorKind::UnexpectedEof => Self::UnexpectedEof,
+            _ => Self::Other,
+        }
+    }
+}
+
+impl fmt::Display for ParseErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::InvalidData => write!(f, "Invalid data"),
+            Self::UnexpectedEof => write!(f, "Unexpected end of data"),
+            Self::Other => write!(f, "Other"),
+        }
+    }
+}
+
+/// How a parser should recover when a log entry fails to parse
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ResyncStrategy {
+    /// Stop parsing at the first invalid entry
+    Stop,
+    /// Skip to the beginning of the next line and continue, suitable for line based text formats
+    NextLine,
+    /// Advance this many bytes from the start of the invalid entry and retry until an entry parses again,
+    /// suitable for binary formats with fixed size entries
+    SkipBytes(usize),
+}
+
+/// Describes a (possibly corrupt) region of the input where log entries failed to parse
+///
+/// Consecutive failures are merged into a single [`ParseDiagnostic`] such that a corrupt region
+/// shows up as one diagnostic rather than one per attempted entry.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct ParseDiagnostic {
+    byte_offset: usize,
+    line: Option<usize>,
+    kind: ParseErrorKind,
+    message: String,
+    skipped_bytes: usize,
+    failed_attempts: usize,
+    timestamp_ns: Option<f64>,
+}
+
+impl ParseDiagnostic {
+    pub fn new(byte_offset: usize, line: Option<usize>, error: &io::Error) -> Self {
+        Self {
+            byte_offset,
+            line,
+            kind: error.kind().into(),
+            message: error.to_string(),
+            skipped_bytes: 0,
+            failed_attempts: 1,
+            timestamp_ns: None,
+        }
+    }
+
+    /// Offset in bytes from the start of the input where the failure occurred
+    pub fn byte_offset(&self) -> usize {
+        self.byte_offset
+    }
+
+    /// Line number (1-indexed) where the failure occurred, only available for text formats
+    pub fn line(&self) -> Option<usize> {
+        self.line
+    }
+
+    pub fn kind(&self) -> ParseErrorKind {
+        self.kind
+    }
+
+    /// The error message of the first failure in the region
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+
+    /// How many bytes were skipped before parsing could continue
+    pub fn skipped_bytes(&self) -> usize {
+        self.skipped_bytes
+    }
+
+    /// How many entries failed to parse in the region
+    pub fn failed_attempts(&self) -> usize {
+        self.failed_attempts
+    }
+
+    /// Timestamp in nanoseconds of the closest valid entry, used for locating the failure in a plot
+    pub fn timestamp_ns(&self) -> Option<f64> {
+        self.timestamp_ns
+    }
+
+    pub(crate) fn set_timestamp_ns(&mut self, timestamp_ns: f64) {
+        self.timestamp_ns = Some(timestamp_ns);
+    }
+
+    /// Set the timestamp used for locating the failure, for parsers that build diagnostics themselves
+    #[must_use]
+    pub fn with_timestamp_ns(mut self, timestamp_ns: f64) -> Self {
+        self.set_timestamp_ns(timestamp_ns);
+        self
+    }
+
+    pub(crate) fn add_failed_attempt(&mut self) {
+        self.failed_attempts += 1;
+    }
+
+    pub(crate) fn add_skipped_bytes(&mut self, bytes: usize) {
+        self.skipped_bytes += bytes;
+    }
+
+    /// Shift the position and timestamp of the diagnostic, e.g. when entries were parsed after a header
+    /// or have timestamps relative to a startup time.
+    pub fn offset(&mut self, bytes: usize, lines: usize, timestamp_ns: f64) {
+        self.byte_offset += bytes;
+        if let Some(line) = self.line.as_mut() {
+            *line += lines;
+        }
+        if let Some(ts) = self.timestamp_ns.as_mut() {
+            *ts += timestamp_ns;
+        }
+    }
+}
+
+impl fmt::Display for ParseDiagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} at byte {}", self.kind, self.byte_offset)?;
+        if let Some(line) = self.line {
+            write!(f, " (line {line})")?;
+        }
+        write!(f, ": {}", self.message)
+    }
+}
@@ -1,11 +1,15 @@
+pub mod diagnostics;
 pub mod log;
 pub mod parseable;
 pub mod plotable;
+pub mod registry;
 pub mod util;
 
 pub mod prelude {
+    pub use crate::diagnostics::{ParseDiagnostic, ParseErrorKind, ResyncStrategy};
     pub use crate::log::{GitMetadata, LogEntry, SkytemLog};
     pub use crate::parseable::Parseable;
     pub use crate::plotable::{ExpectedPlotRange, PlotLabels, Plotable, RawPlot};
+    pub use crate::registry::{Confidence, FormatRegistry, LogFormat, ParsedLog};
     pub use crate::util::*;
 }
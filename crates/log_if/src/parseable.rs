@@ -1,5 +1,7 @@
 use std::io;
 
+use crate::{diagnostics::ParseDiagnostic, registry::Confidence};
+
 /// The interface that a supported format has to implement to be able to load it from a file or any buffer.
 pub trait Parseable: Sized {
     /// A descriptive name of what the implementer of [`Parseable`] is. e.g. an "Mbed Pid Log".
@@ -36,4 +38,25 @@ pub trait Parseable: Sized {
     /// and only when the file is loaded, it is much more important that the check is
     /// extremely rigorous.
     fn is_buf_valid(buf: &[u8]) -> bool;
+
+    /// How confident we are that the buffer is a valid instance of [`Self`], used for choosing
+    /// between formats when registered in a [`FormatRegistry`](crate::registry::FormatRegistry).
+    ///
+    /// Defaults to [`Confidence::High`] if [`Parseable::is_buf_valid`] returns true, implementers
+    /// that rely on heuristics rather than e.g. a unique description should return a lower confidence.
+    fn detect(buf: &[u8]) -> Confidence {
+        if Self::is_buf_valid(buf) {
+            Confidence::High
+        } else {
+            Confidence::None
+        }
+    }
+
+    /// Diagnostics for the parts of the input that could not be parsed, e.g. corrupt entries that were skipped.
+    ///
+    /// Implementers that recover from invalid entries should store and return the diagnostics
+    /// so they can be presented to the user.
+    fn parse_diagnostics(&self) -> &[ParseDiagnostic] {
+        &[]
+    }
 }
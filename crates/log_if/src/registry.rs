@@ -0,0 +1,488 @@
+//! A registry of supported formats, used for detecting which format some content is and parsing it.
+//!
+//! Each format registers a detector that scores how confident it is that a buffer contains the format,
+//! and a parser that turns the buffer into something [`Plotable`]. The registry dispatches to the format
+//! with the highest confidence and reports an error if several formats are equally confident.
+
+use std::{fmt, io, marker::PhantomData};
+
+use crate::{diagnostics::ParseDiagnostic, parseable::Parseable, plotable::Plotable};
+
+/// How confident a format detector is that a buffer contains its format
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Confidence {
+    /// The buffer is not of this format
+    None,
+    /// The buffer could be of this format, e.g. a text format recognized by a heuristic
+    Low,
+    Medium,
+    /// The buffer is of this format, e.g. a unique description or magic bytes was recognized
+    High,
+}
+
+impl fmt::Display for Confidence {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::None => write!(f, "None"),
+            Self::Low => write!(f, "Low"),
+            Self::Medium => write!(f, "Medium"),
+            Self::High => write!(f, "High"),
+        }
+    }
+}
+
+/// The result of parsing a buffer with a [`LogFormat`]
+pub struct ParsedLog {
+    pub log: Box<dyn Plotable>,
+    pub parsed_bytes: usize,
+    pub total_bytes: usize,
+    pub diagnostics: Vec<ParseDiagnostic>,
+}
+
+impl fmt::Debug for ParsedLog {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ParsedLog")
+            .field("log", &self.log.descriptive_name())
+            .field("parsed_bytes", &self.parsed_bytes)
+            .field("total_bytes", &self.total_bytes)
+            .field("diagnostics", &self.diagnostics)
+            .finish()
+    }
+}
+
+/// A format that can be registered in a [`FormatRegistry`]
+pub trait LogFormat {
+    /// The name of the format, e.g. "Mbed PID Log"
+    fn name(&self) -> &str;
+
+    /// A short description of the format shown to the user
+    fn description(&self) -> &str;
+
+    /// Score how confident the format is that the buffer contains the format
+    fn detect(&self, buf: &[u8]) -> Confidence;
+
+    /// Parse the buffer, only called if [`LogFormat::detect`] returned the highest confidence among registered formats
+    fn parse(&self, buf: &[u8]) -> io::Result<ParsedLog>;
+}
+
+impl fmt::Debug for dyn LogFormat + '_ {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+/// Registers any type that implements both [`Parseable`] and [`Plotable`] as a [`LogFormat`]
+pub struct ParseableFormat<P> {
+    description: &'static str,
+    _format: PhantomData<fn() -> P>,
+}
+
+impl<P> fmt::Debug for ParseableFormat<P> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ParseableFormat")
+            .field("description", &self.description)
+            .finish()
+    }
+}
+
+impl<P> ParseableFormat<P> {
+    pub fn new(description: &'static str) -> Self {
+        Self {
+            description,
+            _format: PhantomData,
+        }
+    }
+}
+
+impl<P: Parseable + Plotable + 'static> LogFormat for ParseableFormat<P> {
+    fn name(&self) -> &str {
+        P::DESCRIPTIVE_NAME
+    }
+
+    fn description(&self) -> &str {
+        self.description
+    }
+
+    fn detect(&self, buf: &[u8]) -> Confidence {
+        P::detect(buf)
+    }
+
+    fn parse(&self, buf: &[u8]) -> io::Result<ParsedLog> {
+        let (log, parsed_bytes) = P::from_reader(&mut io::Cursor::new(buf))?;
+        let diagnostics = log.parse_diagnostics().to_vec();
+        Ok(ParsedLog {
+            log: Box::new(log),
+            parsed_bytes,
+            total_bytes: buf.len(),
+            diagnostics,
+        })
+    }
+}
+
+/// Where a registered format comes from.
+///
+/// When several formats are equally confident about some content, the format with the highest origin is chosen.
+/// That way a user defined format can't make content that a built-in format recognizes ambiguous.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum FormatOrigin {
+    /// Loaded at runtime from a format definition
+    UserDefined,
+    /// Described by a definition bundled with the application, e.g. a schema of a log version without a handwritten
+    /// parser
+    Bundled,
+    /// Supported out of the box
+    BuiltIn,
+}
+
+struct RegisteredFormat {
+    format: Box<dyn LogFormat>,
+    origin: FormatOrigin,
+}
+
+impl fmt::Debug for RegisteredFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} ({:?})", self.format.name(), self.origin)
+    }
+}
+
+/// The outcome of running all registered detectors on a buffer
+#[derive(Debug)]
+pub enum Detection<'r> {
+    Unrecognized,
+    Match(&'r dyn LogFormat),
+    /// Several formats detected the buffer with the same (highest) confidence
+    Ambiguous(Vec<&'r dyn LogFormat>),
+}
+
+/// A collection of formats that content can be detected and parsed as
+#[derive(Debug, Default)]
+pub struct FormatRegistry {
+    formats: Vec<RegisteredFormat>,
+}
+
+impl FormatRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a built-in format to the registry
+    pub fn register(&mut self, format: impl LogFormat + 'static) {
+        self.formats.push(RegisteredFormat {
+            format: Box::new(format),
+            origin: FormatOrigin::BuiltIn,
+        });
+    }
+
+    /// Add a format described by a definition bundled with the application, it ranks below built-in formats
+    pub fn register_bundled(&mut self, format: impl LogFormat + 'static) {
+        self.formats.push(RegisteredFormat {
+            format: Box::new(format),
+            origin: FormatOrigin::Bundled,
+        });
+    }
+
+    /// Add a user defined format to the registry, replacing a user defined format of the same name.
+    ///
+    /// # Errors
+    /// If a built-in or bundled format has the same name
+    pub fn register_user_defined(&mut self, format: impl LogFormat + 'static) -> io::Result<()> {
+        let same_name = |registered: &RegisteredFormat| registered.format.name() == format.name();
+        if self.formats.iter().any(|registered| {
+            registered.origin > FormatOrigin::UserDefined && same_name(registered)
+        }) {
+            return Err(io::Error::new(
+                io::ErrorKind::AlreadyExists,
+                format!("'{}' is the name of a built-in format", format.name()),
+            ));
+        }
+        if let Some(idx) = self.formats.iter().position(same_name) {
+            log::info!("Replacing the user defined format '{}'", format.name());
+            self.formats.remove(idx);
+        }
+        self.formats.push(RegisteredFormat {
+            format: Box::new(format),
+            origin: FormatOrigin::UserDefined,
+        });
+        Ok(())
+    }
+
+    /// Add a format to the registry and return the registry, for building a registry in a single expression
+    #[must_use]
+    pub fn with(mut self, format: impl LogFormat + 'static) -> Self {
+        self.register(format);
+        self
+    }
+
+    /// Add a bundled format to the registry and return the registry, see [`FormatRegistry::register_bundled`]
+    #[must_use]
+    pub fn with_bundled(mut self, format: impl LogFormat + 'static) -> Self {
+        self.register_bundled(format);
+        self
+    }
+
+    /// Add a [`Parseable`] and [`Plotable`] type to the registry
+    #[must_use]
+    pub fn with_parseable<P: Parseable + Plotable + 'static>(
+        self,
+        description: &'static str,
+    ) -> Self {
+        self.with(ParseableFormat::<P>::new(description))
+    }
+
+    /// All registered formats
+    pub fn formats(&self) -> impl Iterator<Item = &dyn LogFormat> {
+        self.formats
+            .iter()
+            .map(|registered| registered.format.as_ref())
+    }
+
+    /// Run all detectors on the buffer and return the format(s) with the highest confidence, where formats of the same
+    /// confidence are ranked by their [`FormatOrigin`]
+    pub fn detect(&self, buf: &[u8]) -> Detection<'_> {
+        let mut best = (Confidence::None, FormatOrigin::UserDefined);
+        let mut candidates: Vec<&dyn LogFormat> = vec![];
+        for RegisteredFormat { format, origin } in &self.formats {
+            let confidence = format.detect(buf);
+            if confidence == Confidence::None {
+                continue;
+            }
+            log::debug!("Detected '{}' with confidence: {confidence}", format.name());
+            let rank = (confidence, *origin);
+            if rank > best {
+                best = rank;
+                candidates.clear();
+            }
+            if rank == best {
+                candidates.push(format.as_ref());
+            }
+        }
+        match candidates.len() {
+            0 => Detection::Unrecognized,
+            1 => Detection::Match(candidates[0]),
+            _ => Detection::Ambiguous(candidates),
+        }
+    }
+
+    /// Detect the format of the buffer and parse it
+    ///
+    /// # Errors
+    /// If no format recognizes the buffer, if several formats are equally confident,
+    /// or if parsing with the detected format fails.
+    pub fn parse(&self, buf: &[u8]) -> io::Result<ParsedLog> {
+        match self.detect(buf) {
+            Detection::Unrecognized => Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "Unrecognized format",
+            )),
+            Detection::Match(format) => format.parse(buf).map_err(|e| {
+                io::Error::new(e.kind(), format!("Failed parsing '{}': {e}", format.name()))
+            }),
+            Detection::Ambiguous(formats) => {
+                let names: Vec<&str> = formats.iter().map(|f| f.name()).collect();
+                Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!(
+                        "Ambiguous format, the content matches: {}",
+                        names.join(", ")
+                    ),
+                ))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{DateTime, Utc};
+    use pretty_assertions::assert_eq;
+    use testresult::TestResult;
+
+    use crate::plotable::{PlotLabels, RawPlot};
+
+    /// A format that detects its content by the first byte
+    struct FirstByteFormat {
+        name: &'static str,
+        first_byte: u8,
+        confidence: Confidence,
+    }
+
+    struct DummyLog;
+
+    impl Plotable for DummyLog {
+        fn raw_plots(&self) -> &[RawPlot] {
+            &[]
+        }
+
+        fn first_timestamp(&self) -> DateTime<Utc> {
+            DateTime::default()
+        }
+
+        fn descriptive_name(&self) -> &str {
+            "Dummy"
+        }
+
+        fn labels(&self) -> Option<&[PlotLabels]> {
+            None
+        }
+
+        fn metadata(&self) -> Option<Vec<(String, String)>> {
+            None
+        }
+    }
+
+    impl LogFormat for FirstByteFormat {
+        fn name(&self) -> &str {
+            self.name
+        }
+
+        fn description(&self) -> &str {
+            "Test format"
+        }
+
+        fn detect(&self, buf: &[u8]) -> Confidence {
+            if buf.first() == Some(&self.first_byte) {
+                self.confidence
+            } else {
+                Confidence::None
+            }
+        }
+
+        fn parse(&self, buf: &[u8]) -> io::Result<ParsedLog> {
+            Ok(ParsedLog {
+                log: Box::new(DummyLog),
+                parsed_bytes: buf.len(),
+                total_bytes: buf.len(),
+                diagnostics: vec![],
+            })
+        }
+    }
+
+    fn test_registry() -> FormatRegistry {
+        FormatRegistry::new()
+            .with(FirstByteFormat {
+                name: "A",
+                first_byte: 1,
+                confidence: Confidence::High,
+            })
+            .with(FirstByteFormat {
+                name: "B",
+                first_byte: 1,
+                confidence: Confidence::Low,
+            })
+            .with(FirstByteFormat {
+                name: "C",
+                first_byte: 2,
+                confidence: Confidence::Medium,
+            })
+            .with(FirstByteFormat {
+                name: "D",
+                first_byte: 2,
+                confidence: Confidence::Medium,
+            })
+    }
+
+    #[test]
+    fn test_highest_confidence_wins() -> TestResult {
+        let registry = test_registry();
+        let Detection::Match(format) = registry.detect(&[1, 0, 0]) else {
+            panic!("Expected a single match");
+        };
+        assert_eq!(format.name(), "A");
+        let parsed = registry.parse(&[1, 0, 0])?;
+        assert_eq!(parsed.total_bytes, 3);
+        Ok(())
+    }
+
+    #[test]
+    fn test_ambiguous_match_is_reported() {
+        let registry = test_registry();
+        let Detection::Ambiguous(formats) = registry.detect(&[2]) else {
+            panic!("Expected an ambiguous match");
+        };
+        let names: Vec<&str> = formats.iter().map(|f| f.name()).collect();
+        assert_eq!(names, ["C", "D"]);
+
+        let err = registry.parse(&[2]).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        assert!(err.to_string().contains("C, D"));
+    }
+
+    #[test]
+    fn test_user_defined_formats_rank_below_built_in_formats() -> TestResult {
+        let mut registry = test_registry();
+        for first_byte in [1, 2] {
+            registry.register_user_defined(FirstByteFormat {
+                name: "User",
+                first_byte,
+                confidence: Confidence::High,
+            })?;
+        }
+        // Registering a format of the same name replaces it
+        assert_eq!(registry.formats().count(), 5);
+        let Detection::Match(format) = registry.detect(&[1]) else {
+            panic!("Expected the built-in format to win");
+        };
+        assert_eq!(format.name(), "A");
+        // A user defined format still wins when it's more confident
+        let Detection::Match(format) = registry.detect(&[2]) else {
+            panic!("Expected the user defined format to win");
+        };
+        assert_eq!(format.name(), "User");
+
+        let err = registry
+            .register_user_defined(FirstByteFormat {
+                name: "A",
+                first_byte: 3,
+                confidence: Confidence::High,
+            })
+            .unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::AlreadyExists);
+        Ok(())
+    }
+
+    #[test]
+    fn test_unrecognized() {
+        let registry = test_registry();
+        assert!(matches!(registry.detect(&[3]), Detection::Unrecognized));
+        assert!(registry.parse(&[]).is_err());
+    }
+
+    #[test]
+    fn test_bundled_formats_rank_between_user_defined_and_built_in_formats() -> TestResult {
+        let mut registry = test_registry().with_bundled(FirstByteFormat {
+            name: "Bundled",
+            first_byte: 1,
+            confidence: Confidence::High,
+        });
+        registry.register_user_defined(FirstByteFormat {
+            name: "User",
+            first_byte: 3,
+            confidence: Confidence::High,
+        })?;
+        let Detection::Match(format) = registry.detect(&[1]) else {
+            panic!("Expected the built-in format to win");
+        };
+        assert_eq!(format.name(), "A");
+
+        registry.register_bundled(FirstByteFormat {
+            name: "Bundled",
+            first_byte: 3,
+            confidence: Confidence::High,
+        });
+        let Detection::Match(format) = registry.detect(&[3]) else {
+            panic!("Expected the bundled format to win");
+        };
+        assert_eq!(format.name(), "Bundled");
+
+        let err = registry
+            .register_user_defined(FirstByteFormat {
+                name: "Bundled",
+                first_byte: 4,
+                confidence: Confidence::High,
+            })
+            .unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::AlreadyExists);
+        Ok(())
+    }
+}
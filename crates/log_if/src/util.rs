@@ -4,27 +4,134 @@ use std::io;
 /// Take a reader and parse [`LogEntry`]s from it until it returns an error,
 /// then return a vector of all [`LogEntry`]s and the total number of bytes read from the reader.
 pub fn parse_to_vec<T: LogEntry>(reader: &mut impl io::BufRead) -> (Vec<T>, usize) {
-    let mut entries = Vec::new();
+    let (entries, total_bytes_read, diagnostics) =
+        parse_to_vec_with_diagnostics(reader, ResyncStrategy::Stop);
+    for d in diagnostics {
+        log::warn!("Failed parsing log entry: {d}");
+    }
+    (entries, total_bytes_read)
+}
+
+/// Take a reader and parse [`LogEntry`]s from it until the end of the data, recovering from
+/// invalid entries according to the [`ResyncStrategy`].
+///
+/// Returns a vector of all [`LogEntry`]s, the number of bytes that were parsed into entries, and a
+/// [`ParseDiagnostic`] for each region of the data that could not be parsed. Data that ends in the middle of an
+/// entry ends parsing without a diagnostic, since logs are commonly padded.
+///
+/// Byte offsets and line numbers in the diagnostics are relative to the position of the reader
+/// when this function is called.
+pub fn parse_to_vec_with_diagnostics<T: LogEntry>(
+    reader: &mut impl io::BufRead,
+    resync: ResyncStrategy,
+) -> (Vec<T>, usize, Vec<ParseDiagnostic>) {
+    parse_with_diagnostics(
+        reader,
+        resync,
+        |cursor| T::from_reader(cursor),
+        T::timestamp_ns,
+    )
+}
+
+/// Like [`parse_to_vec_with_diagnostics`] but with a function for parsing an entry, for entries that can't implement
+/// [`LogEntry`] because parsing them depends on more than the data, e.g. a schema describing the entries.
+///
+/// `parse_entry` parses an entry from the start of the slice and advances it past the entry, `timestamp_ns`
+/// returns the timestamp of an entry for locating the diagnostics.
+pub fn parse_with_diagnostics<T>(
+    reader: &mut impl io::BufRead,
+    resync: ResyncStrategy,
+    mut parse_entry: impl FnMut(&mut &[u8]) -> io::Result<(T, usize)>,
+    timestamp_ns: impl Fn(&T) -> f64,
+) -> (Vec<T>, usize, Vec<ParseDiagnostic>) {
+    let mut entries: Vec<T> = Vec::new();
     let mut total_bytes_read = 0;
+    let mut diagnostics = Vec::new();
+
+    let mut buf = Vec::new();
+    if let Err(e) = reader.read_to_end(&mut buf) {
+        diagnostics.push(ParseDiagnostic::new(buf.len(), None, &e));
+    }
+
+    let track_lines = resync == ResyncStrategy::NextLine;
+    let mut line = 1;
+    let mut pos = 0;
+    // The diagnostic of the corrupt region we are currently skipping through (if any)
+    let mut current_region: Option<ParseDiagnostic> = None;
 
-    loop {
-        match T::from_reader(reader) {
-            Ok((entry, bytes_read)) => {
+    while pos < buf.len() {
+        let remaining = &buf[pos..];
+        let mut cursor = remaining;
+        let res = parse_entry(&mut cursor);
+        let consumed = remaining.len() - cursor.len();
+        match res {
+            Ok((entry, bytes_read)) if consumed > 0 => {
+                if let Some(mut region) = current_region.take() {
+                    if region.timestamp_ns().is_none() {
+                        region.set_timestamp_ns(timestamp_ns(&entry));
+                    }
+                    diagnostics.push(region);
+                }
+                if track_lines {
+                    line += count_newlines(&remaining[..consumed]);
+                }
                 entries.push(entry);
                 total_bytes_read += bytes_read;
+                pos += consumed;
             }
+            Ok(_) => break,
+            // Running out of data in the middle of an entry is how logs normally end, e.g. logs that are padded
+            // to a block size, the unparsed bytes are reported by the caller
+            Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => break,
             Err(e) => {
-                if e.kind() == io::ErrorKind::UnexpectedEof {
-                    break;
+                let region = if let Some(region) = current_region.as_mut() {
+                    region.add_failed_attempt();
+                    region
                 } else {
-                    log::warn!("Failed parsing log entry: {e}");
-                    break;
+                    let mut diagnostic = ParseDiagnostic::new(pos, track_lines.then_some(line), &e);
+                    if let Some(last_entry) = entries.last() {
+                        diagnostic.set_timestamp_ns(timestamp_ns(last_entry));
+                    }
+                    current_region.insert(diagnostic)
+                };
+
+                let skip = match resync {
+                    ResyncStrategy::Stop => remaining.len(),
+                    ResyncStrategy::NextLine => remaining
+                        .iter()
+                        .position(|b| *b == b'\n')
+                        .map_or(remaining.len(), |newline_idx| newline_idx + 1),
+                    ResyncStrategy::SkipBytes(n) => n.clamp(1, remaining.len()),
+                };
+                region.add_skipped_bytes(skip);
+                if track_lines {
+                    line += count_newlines(&remaining[..skip]);
                 }
+                pos += skip;
             }
         }
     }
+    if let Some(region) = current_region {
+        diagnostics.push(region);
+    }
 
-    (entries, total_bytes_read)
+    (entries, total_bytes_read, diagnostics)
+}
+
+fn count_newlines(bytes: &[u8]) -> usize {
+    bytes.iter().filter(|b| **b == b'\n').count()
+}
+
+/// Shift the positions and timestamps of all [`ParseDiagnostic`]s, see [`ParseDiagnostic::offset`]
+pub fn offset_diagnostics(
+    diagnostics: &mut [ParseDiagnostic],
+    bytes: usize,
+    lines: usize,
+    timestamp_ns: f64,
+) {
+    for d in diagnostics {
+        d.offset(bytes, lines, timestamp_ns);
+    }
 }
 
 /// Utility function for converting a slice of [`LogEntry`] to plot points by supplying extractor functions
@@ -42,3 +149,138 @@ where
         .map(|e| [x_extractor(e), y_extractor(e)])
         .collect()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fmt;
+
+    /// A single byte entry where any byte above 100 is invalid
+    struct ByteEntry(u8);
+
+    impl fmt::Display for ByteEntry {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            write!(f, "{}", self.0)
+        }
+    }
+
+    impl LogEntry for ByteEntry {
+        fn from_reader(reader: &mut impl io::BufRead) -> io::Result<(Self, usize)> {
+            let mut byte = [0u8; 1];
+            reader.read_exact(&mut byte)?;
+            if byte[0] > 100 {
+                return Err(io::Error::new(io::ErrorKind::InvalidData, "Byte too large"));
+            }
+            Ok((Self(byte[0]), 1))
+        }
+
+        fn timestamp_ns(&self) -> f64 {
+            self.0.into()
+        }
+    }
+
+    /// A line containing a number
+    struct LineEntry(u8);
+
+    impl fmt::Display for LineEntry {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            write!(f, "{}", self.0)
+        }
+    }
+
+    impl LogEntry for LineEntry {
+        fn from_reader(reader: &mut impl io::BufRead) -> io::Result<(Self, usize)> {
+            let mut line = String::new();
+            let bytes_read = reader.read_line(&mut line)?;
+            let val = line
+                .trim()
+                .parse()
+                .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+            Ok((Self(val), bytes_read))
+        }
+
+        fn timestamp_ns(&self) -> f64 {
+            self.0.into()
+        }
+    }
+
+    #[test]
+    fn test_parse_to_vec_stops_at_invalid_entry() {
+        let data: &[u8] = &[1, 2, 200, 3];
+        let (entries, bytes_read): (Vec<ByteEntry>, usize) = parse_to_vec(&mut &data[..]);
+        assert_eq!(entries.len(), 2);
+        assert_eq!(bytes_read, 2);
+    }
+
+    #[test]
+    fn test_resync_skip_bytes_merges_corrupt_region() {
+        let data: &[u8] = &[1, 2, 200, 201, 202, 3, 4, 255];
+        let (entries, bytes_read, diagnostics): (Vec<ByteEntry>, usize, _) =
+            parse_to_vec_with_diagnostics(&mut &data[..], ResyncStrategy::SkipBytes(1));
+        assert_eq!(entries.len(), 4);
+        assert_eq!(bytes_read, 4);
+        assert_eq!(diagnostics.len(), 2);
+
+        let region = &diagnostics[0];
+        assert_eq!(region.byte_offset(), 2);
+        assert_eq!(region.line(), None);
+        assert_eq!(region.skipped_bytes(), 3);
+        assert_eq!(region.failed_attempts(), 3);
+        assert_eq!(region.kind(), ParseErrorKind::InvalidData);
+        assert_eq!(region.timestamp_ns(), Some(2.0));
+
+        let trailing = &diagnostics[1];
+        assert_eq!(trailing.byte_offset(), 7);
+        assert_eq!(trailing.skipped_bytes(), 1);
+    }
+
+    #[test]
+    fn test_resync_next_line_tracks_line_numbers() {
+        let data = b"1\n2\nx\ny\n3\n";
+        let (entries, bytes_read, diagnostics): (Vec<LineEntry>, usize, _) =
+            parse_to_vec_with_diagnostics(&mut &data[..], ResyncStrategy::NextLine);
+        assert_eq!(entries.len(), 3);
+        assert_eq!(bytes_read, 6);
+        assert_eq!(diagnostics.len(), 1);
+
+        let region = &diagnostics[0];
+        assert_eq!(region.byte_offset(), 4);
+        assert_eq!(region.line(), Some(3));
+        assert_eq!(region.skipped_bytes(), 4);
+        assert_eq!(region.failed_attempts(), 2);
+    }
+
+    #[test]
+    fn test_partial_entry_at_the_end_is_not_a_diagnostic() {
+        let data: &[u8] = &[1, 2, 200, 201, 3];
+        let (entries, bytes_read, diagnostics) = parse_with_diagnostics(
+            &mut &data[..],
+            ResyncStrategy::SkipBytes(2),
+            |cursor| {
+                let mut entry = [0u8; 2];
+                io::Read::read_exact(cursor, &mut entry)?;
+                if entry[0] > 100 {
+                    return Err(io::Error::new(io::ErrorKind::InvalidData, "Byte too large"));
+                }
+                Ok((entry, 2))
+            },
+            |entry| entry[0].into(),
+        );
+        assert_eq!(entries, [[1, 2]]);
+        assert_eq!(bytes_read, 2);
+        // Only the corrupt entry is reported, not the single byte at the end
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].kind(), ParseErrorKind::InvalidData);
+        assert_eq!(diagnostics[0].skipped_bytes(), 2);
+    }
+
+    #[test]
+    fn test_offset_diagnostics() {
+        let data: &[u8] = &[1, 200];
+        let (_, _, mut diagnostics): (Vec<ByteEntry>, usize, _) =
+            parse_to_vec_with_diagnostics(&mut &data[..], ResyncStrategy::Stop);
+        offset_diagnostics(&mut diagnostics, 10, 2, 1000.0);
+        assert_eq!(diagnostics[0].byte_offset(), 11);
+        assert_eq!(diagnostics[0].timestamp_ns(), Some(1001.0));
+    }
+}
@@ -0,0 +1,171 @@
+//! Frame time benchmarks of drawing a synthetic plot of 10 million points with [`plot_util::plot_lines`].
+//!
+//! Each frame runs a headless egui context with a plot of the full width of a 1920x1080 screen, and tessellates
+//! the output, which is what it costs to draw the plot apart from uploading the meshes to the GPU.
+//!
+//! Run with `cargo bench -p plot_util`, a case is only run if its name contains the (optional) filter argument,
+//! e.g. `cargo bench -p plot_util -- raw`.
+// The benchmarks only run natively, the wasm lints are only checked to keep the workspace lints clean
+#![cfg_attr(target_arch = "wasm32", allow(clippy::disallowed_methods))]
+use std::{
+    hint::black_box,
+    time::{Duration, Instant},
+};
+
+use egui::{Pos2, RawInput, Rect, Vec2};
+use egui_plot::{Plot, PlotBounds};
+use plot_util::{plot_lines, DownsamplingMethod, MipMapConfiguration, PlotValues};
+
+const POINTS: usize = 10_000_000;
+/// 1 kHz sampling from an arbitrary point in time
+const START_NS: f64 = 1_728_470_564_000_000_000.0;
+const INTERVAL_NS: f64 = 1_000_000.0;
+const SCREEN_SIZE: Vec2 = Vec2::new(1920.0, 1080.0);
+
+const WARMUP_FRAMES: usize = 3;
+const MEASURED_FRAMES: usize = 30;
+
+/// Which part of the plot is in view
+#[derive(Debug, Clone, Copy)]
+enum View {
+    /// All the points
+    Full,
+    /// The given fraction of the points in the middle of the plot
+    Zoomed(f64),
+    /// The given fraction of the points, moved by a pixel every frame
+    Panning(f64),
+}
+
+impl View {
+    fn bounds(self, frame: usize) -> PlotBounds {
+        let duration = POINTS as f64 * INTERVAL_NS;
+        let (fraction, pan_offset) = match self {
+            Self::Full => (1.0, 0.0),
+            Self::Zoomed(fraction) => (fraction, 0.0),
+            Self::Panning(fraction) => (
+                fraction,
+                frame as f64 * fraction * duration / f64::from(SCREEN_SIZE.x),
+            ),
+        };
+        let center = START_NS + duration / 2.0 + pan_offset;
+        let half_width = fraction * duration / 2.0;
+        PlotBounds::from_min_max([center - half_width, -2.0], [center + half_width, 2.0])
+    }
+}
+
+/// A sine wave with a few harmonics, some spikes and a minute long gap where the sensor stopped reporting
+fn synthetic_plot() -> PlotValues {
+    let gap = (POINTS / 3)..(POINTS / 3 + 60_000);
+    let points = (0..POINTS)
+        .filter(|i| !gap.contains(i))
+        .map(|i| {
+            let t = i as f64 / 1000.0;
+            let spike = if i % 100_003 == 0 { 1.0 } else { 0.0 };
+            [
+                START_NS + i as f64 * INTERVAL_NS,
+                (t * 0.1).sin() + 0.3 * (t * 7.0).sin() + 0.05 * (t * 331.0).sin() + spike,
+            ]
+        })
+        .collect();
+    PlotValues::new(points, "synthetic".to_owned(), 1)
+}
+
+fn render_frame(
+    ctx: &egui::Context,
+    plot: &PlotValues,
+    (mipmap_cfg, method): (MipMapConfiguration, DownsamplingMethod),
+    bounds: PlotBounds,
+) {
+    let input = RawInput {
+        screen_rect: Some(Rect::from_min_size(Pos2::ZERO, SCREEN_SIZE)),
+        ..Default::default()
+    };
+    let output = ctx.run(input, |ctx| {
+        egui::CentralPanel::default().show(ctx, |ui| {
+            Plot::new("bench").show(ui, |plot_ui| {
+                plot_ui.set_plot_bounds(bounds);
+                plot_lines(
+                    plot_ui,
+                    std::iter::once(plot),
+                    1.5,
+                    mipmap_cfg,
+                    method,
+                    SCREEN_SIZE.x as usize,
+                    true,
+                );
+            });
+        });
+    });
+    black_box(ctx.tessellate(output.shapes, output.pixels_per_point));
+}
+
+fn bench_case(
+    name: &str,
+    plot: &PlotValues,
+    config: (MipMapConfiguration, DownsamplingMethod),
+    view: View,
+) {
+    let ctx = egui::Context::default();
+    for frame in 0..WARMUP_FRAMES {
+        render_frame(&ctx, plot, config, view.bounds(frame));
+    }
+    let mut frame_times: Vec<Duration> = (WARMUP_FRAMES..WARMUP_FRAMES + MEASURED_FRAMES)
+        .map(|frame| {
+            let bounds = view.bounds(frame);
+            let start = Instant::now();
+            render_frame(&ctx, plot, config, bounds);
+            start.elapsed()
+        })
+        .collect();
+    frame_times.sort_unstable();
+    let mean = frame_times.iter().sum::<Duration>() / MEASURED_FRAMES as u32;
+    println!(
+        "{name:<40} min {min:>10.3?}  median {median:>10.3?}  mean {mean:>10.3?}",
+        min = frame_times[0],
+        median = frame_times[MEASURED_FRAMES / 2],
+    );
+}
+
+fn main() {
+    // `cargo bench` passes `--bench`, anything else is a filter
+    let filter = std::env::args()
+        .skip(1)
+        .find(|arg| !arg.starts_with('-'))
+        .unwrap_or_default();
+
+    let plot = synthetic_plot();
+    let start = Instant::now();
+    plot.build_mipmaps_blocking();
+    println!(
+        "Built the mipmaps of {points} points in {elapsed:.3?}",
+        points = plot.get_raw().len(),
+        elapsed = start.elapsed()
+    );
+
+    let methods = [
+        ("min/max", DownsamplingMethod::MinMax),
+        ("envelope", DownsamplingMethod::Envelope),
+        ("lttb", DownsamplingMethod::Lttb),
+    ];
+    let views = [
+        ("full", View::Full),
+        ("zoomed 1%", View::Zoomed(0.01)),
+        ("zoomed 0.01%", View::Zoomed(0.0001)),
+        ("panning 1%", View::Panning(0.01)),
+    ];
+    for (view_name, view) in views {
+        let mut cases = vec![(
+            format!("raw, {view_name}"),
+            (MipMapConfiguration::Disabled, DownsamplingMethod::default()),
+        )];
+        cases.extend(methods.iter().map(|&(method_name, method)| {
+            (
+                format!("{method_name}, {view_name}"),
+                (MipMapConfiguration::Auto, method),
+            )
+        }));
+        for (name, config) in cases.iter().filter(|(name, _)| name.contains(&filter)) {
+            bench_case(name, &plot, *config, view);
+        }
+    }
+}
@@ -0,0 +1,302 @@
+use std::{fmt, io};
+
+use serde::{Deserialize, Serialize};
+
+use crate::filters::{self, SignalFilter};
+
+const NANOS_PER_SEC: f64 = 1_000_000_000.0;
+
+/// How a plot is computed from another plot, see [`crate::PlotValues::derived`]
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize, Serialize)]
+pub enum Operation {
+    Filter(SignalFilter),
+    /// The rate of change per second, of the samples smoothed by a moving average of `smoothing_samples` samples
+    Derivative {
+        smoothing_samples: usize,
+    },
+    /// The cumulative integral over time in seconds, from 0 at the first sample
+    Integral,
+    /// The plot minus another plot interpolated at its timestamps, see [`crate::PlotValues::difference`]
+    Difference,
+}
+
+impl fmt::Display for Operation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Filter(filter) => write!(f, "{filter}"),
+            Self::Derivative { smoothing_samples } if *smoothing_samples > 1 => {
+                write!(f, "derivative, smoothed over {smoothing_samples} samples")
+            }
+            Self::Derivative { .. } => write!(f, "derivative"),
+            Self::Integral => write!(f, "integral"),
+            Self::Difference => write!(f, "difference"),
+        }
+    }
+}
+
+impl Operation {
+    /// The name of the plot computed from the plot named `source_name`
+    pub fn derived_name(&self, source_name: &str) -> String {
+        match self {
+            Self::Filter(filter) => format!("{source_name} [{filter}]"),
+            Self::Derivative { smoothing_samples } if *smoothing_samples > 1 => {
+                format!("d/dt {source_name} [moving average {smoothing_samples}]")
+            }
+            Self::Derivative { .. } => format!("d/dt {source_name}"),
+            Self::Integral => format!("∫ {source_name} dt"),
+            Self::Difference => format!("Δ {source_name}"),
+        }
+    }
+
+    /// Computes the points of the derived plot from the sorted `points`, restarting after each gap longer than
+    /// `gap_threshold`. The x values are timestamps in nanoseconds and are kept as they are.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`io::ErrorKind::InvalidInput`] if the parameters of the operation are invalid for the samples, see
+    /// [`SignalFilter::apply`], or for [`Operation::Difference`] which needs a second plot
+    pub fn apply(
+        &self,
+        points: &[[f64; 2]],
+        gap_threshold: Option<f64>,
+    ) -> io::Result<Vec<[f64; 2]>> {
+        match *self {
+            Self::Filter(filter) => filter.apply(points, gap_threshold),
+            Self::Derivative { smoothing_samples } => {
+                let smoothed = SignalFilter::MovingAverage {
+                    samples: smoothing_samples,
+                }
+                .apply(points, gap_threshold)?;
+                Ok(filters::split_at_gaps(&smoothed, gap_threshold)
+                    .flat_map(derivative)
+                    .collect())
+            }
+            Self::Integral => {
+                let mut integrated = Vec::with_capacity(points.len());
+                let mut total = 0.0;
+                for segment in filters::split_at_gaps(points, gap_threshold) {
+                    // Nothing accumulates in a gap
+                    integrated.push([segment[0][0], total]);
+                    for pair in segment.windows(2) {
+                        let ([x0, y0], [x1, y1]) = (pair[0], pair[1]);
+                        total += (y0 + y1) / 2.0 * (x1 - x0) / NANOS_PER_SEC;
+                        integrated.push([x1, total]);
+                    }
+                }
+                Ok(integrated)
+            }
+            Self::Difference => Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "the difference needs a plot to subtract",
+            )),
+        }
+    }
+}
+
+/// Tags a plot that was computed from another plot
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct Derivation {
+    /// The label of the plot it was computed from, see [`crate::PlotValues::label`]
+    source: String,
+    operation: Operation,
+    /// The label of the plot that was subtracted from the source, for [`Operation::Difference`]
+    #[serde(default)]
+    subtrahend: Option<String>,
+}
+
+impl Derivation {
+    pub(crate) fn new(source: String, operation: Operation) -> Self {
+        Self {
+            source,
+            operation,
+            subtrahend: None,
+        }
+    }
+
+    pub(crate) fn difference(source: String, subtrahend: String) -> Self {
+        Self {
+            source,
+            operation: Operation::Difference,
+            subtrahend: Some(subtrahend),
+        }
+    }
+
+    /// The label of the plot it was computed from, see [`crate::PlotValues::label`]
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+
+    pub fn operation(&self) -> Operation {
+        self.operation
+    }
+
+    /// The label of the plot that was subtracted from the source, see [`crate::PlotValues::difference`]
+    pub fn subtrahend(&self) -> Option<&str> {
+        self.subtrahend.as_deref()
+    }
+}
+
+/// The `minuend` minus the `subtrahend` linearly interpolated at the timestamps of the `minuend`, both sorted and on
+/// the same time base. Samples of the `minuend` outside the `subtrahend`, or where the neighbouring samples of the
+/// `subtrahend` are more than `subtrahend_gap_threshold` apart, are left out.
+pub(crate) fn difference(
+    minuend: &[[f64; 2]],
+    subtrahend: &[[f64; 2]],
+    subtrahend_gap_threshold: Option<f64>,
+) -> Vec<[f64; 2]> {
+    minuend
+        .iter()
+        .filter_map(|&[x, y]| {
+            let after = subtrahend.partition_point(|&[x_sub, _]| x_sub < x);
+            let [x1, y1] = *subtrahend.get(after)?;
+            if x1 == x {
+                return Some([x, y - y1]);
+            }
+            let [x0, y0] = subtrahend[after.checked_sub(1)?];
+            if subtrahend_gap_threshold.is_some_and(|threshold| x1 - x0 > threshold) {
+                return None;
+            }
+            let interpolated = y0 + (y1 - y0) * (x - x0) / (x1 - x0);
+            Some([x, y - interpolated])
+        })
+        .collect()
+}
+
+/// The derivative per second of sorted points that may be irregularly spaced, from the second order accurate central
+/// difference of the neighbouring samples, and one-sided differences at the ends. Samples without a neighbour at
+/// another timestamp are left out.
+fn derivative(points: &[[f64; 2]]) -> impl Iterator<Item = [f64; 2]> + '_ {
+    (0..points.len()).filter_map(move |idx| {
+        let [x, y] = points[idx];
+        let before = idx
+            .checked_sub(1)
+            .map(|prev| points[prev])
+            .filter(|[x_prev, _]| x - x_prev > 0.0);
+        let after = points
+            .get(idx + 1)
+            .copied()
+            .filter(|[x_next, _]| x_next - x > 0.0);
+        let per_ns = match (before, after) {
+            (Some([x0, y0]), Some([x2, y2])) => {
+                let (h1, h2) = (x - x0, x2 - x);
+                (h1 * h1 * y2 - h2 * h2 * y0 + (h2 * h2 - h1 * h1) * y) / (h1 * h2 * (h1 + h2))
+            }
+            (Some([x0, y0]), None) => (y - y0) / (x - x0),
+            (None, Some([x2, y2])) => (y2 - y) / (x2 - x),
+            (None, None) => return None,
+        };
+        Some([x, per_ns * NANOS_PER_SEC])
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use testresult::TestResult;
+
+    /// Irregular timestamps (ns) between 0 and 2 seconds
+    fn irregular_timestamps() -> Vec<f64> {
+        (0..200)
+            .map(|n| n as f64 * 1e7 + if n % 3 == 0 { 4e6 } else { 0.0 })
+            .collect()
+    }
+
+    #[test]
+    fn test_derivative_of_irregular_samples() -> TestResult {
+        // y = 3t² + 2t, y' = 6t + 2
+        let points: Vec<[f64; 2]> = irregular_timestamps()
+            .into_iter()
+            .map(|x| {
+                let t = x / NANOS_PER_SEC;
+                [x, 3.0 * t * t + 2.0 * t]
+            })
+            .collect();
+        let derived = Operation::Derivative {
+            smoothing_samples: 1,
+        }
+        .apply(&points, None)?;
+
+        assert_eq!(derived.len(), points.len());
+        // The central difference is exact for a second order polynomial
+        for [x, dy] in &derived[1..derived.len() - 1] {
+            let expected = 6.0 * x / NANOS_PER_SEC + 2.0;
+            assert!((dy - expected).abs() < 1e-6, "{x}: {dy} != {expected}");
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_derivative_skips_duplicate_timestamps_and_restarts_after_gaps() -> TestResult {
+        let points = [
+            [0.0, 0.0],
+            [0.0, 5.0],
+            [1e9, 1.0],
+            [1e11, 7.0],
+            [1.01e11, 9.0],
+        ];
+        let derived = Operation::Derivative {
+            smoothing_samples: 1,
+        }
+        .apply(&points, Some(2e9))?;
+        assert_eq!(
+            derived,
+            [[0.0, -4.0], [1e9, -4.0], [1e11, 2.0], [1.01e11, 2.0]]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_cumulative_integral_of_irregular_samples() -> TestResult {
+        // A constant 2 A for 2 seconds, a 10 second gap and then 1 A for a second
+        let mut points: Vec<[f64; 2]> = irregular_timestamps()
+            .into_iter()
+            .map(|x| [x, 2.0])
+            .collect();
+        points.extend((0..=10).map(|n| [12e9 + n as f64 * 1e8, 1.0]));
+        let integrated = Operation::Integral.apply(&points, Some(1e9))?;
+
+        assert_eq!(integrated.len(), points.len());
+        assert_eq!(integrated[0], [points[0][0], 0.0]);
+        let last_before_gap = integrated[199];
+        assert!((last_before_gap[1] - 2.0 * (last_before_gap[0] - 4e6) / 1e9).abs() < 1e-9);
+        let last = integrated[integrated.len() - 1][1];
+        assert!((last - (last_before_gap[1] + 1.0)).abs() < 1e-9, "{last}");
+        Ok(())
+    }
+
+    #[test]
+    fn test_difference_interpolates_the_subtrahend() {
+        let minuend = [
+            [0.0, 1.0],
+            [1.0, 5.0],
+            [2.5, 5.0],
+            [4.0, 3.0],
+            [9.0, 1.0],
+            [12.0, 0.0],
+        ];
+        let subtrahend = [[1.0, 2.0], [3.0, 4.0], [5.0, 0.0], [10.0, 0.0]];
+        assert_eq!(
+            difference(&minuend, &subtrahend, Some(3.0)),
+            [[1.0, 3.0], [2.5, 1.5], [4.0, 1.0]]
+        );
+        assert_eq!(difference(&minuend, &subtrahend, None).len(), 4);
+        assert!(Operation::Difference.apply(&minuend, None).is_err());
+    }
+
+    #[test]
+    fn test_derived_names() {
+        assert_eq!(Operation::Integral.derived_name("current"), "∫ current dt");
+        assert_eq!(
+            Operation::Derivative {
+                smoothing_samples: 5
+            }
+            .derived_name("rpm"),
+            "d/dt rpm [moving average 5]"
+        );
+        assert_eq!(
+            Operation::Filter(SignalFilter::Median { samples: 3 }).derived_name("rpm"),
+            "rpm [median 3]"
+        );
+    }
+}
@@ -0,0 +1,179 @@
+use std::ops::RangeInclusive;
+
+use egui::{epaint::Mesh, Color32, Id, Shape, Stroke, Ui};
+use egui_plot::{PlotBounds, PlotGeometry, PlotItem, PlotPoint, PlotTransform};
+
+/// Opacity of the filled area between the minimum and maximum values
+const FILL_ALPHA: f32 = 0.35;
+/// Opacity of the filled area when the plot is highlighted, e.g. by hovering its legend entry
+const HIGHLIGHTED_FILL_ALPHA: f32 = 0.6;
+
+/// A band that fills the area between the minimum and maximum values of a downsampled plot, like the
+/// envelope shown by an oscilloscope, with a single legend entry.
+///
+/// The min and max points are paired by index, which is how the levels of the min and max mipmaps line up.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct EnvelopeBand {
+    /// The min points followed by the max points, used for hovering
+    points: Vec<PlotPoint>,
+    name: String,
+    color: Color32,
+    width: f32,
+    highlight: bool,
+}
+
+impl EnvelopeBand {
+    pub(crate) fn new(
+        name: impl Into<String>,
+        points_min: &[[f64; 2]],
+        points_max: &[[f64; 2]],
+    ) -> Self {
+        let len = points_min.len().min(points_max.len());
+        let points = points_min[..len]
+            .iter()
+            .chain(&points_max[..len])
+            .map(|&[x, y]| PlotPoint::new(x, y))
+            .collect();
+        Self {
+            points,
+            name: name.into(),
+            color: Color32::TRANSPARENT,
+            width: 1.0,
+            highlight: false,
+        }
+    }
+
+    /// Moves the band by `x_offset` along the x axis
+    #[inline]
+    pub(crate) fn x_offset(mut self, x_offset: f64) -> Self {
+        for point in &mut self.points {
+            point.x += x_offset;
+        }
+        self
+    }
+
+    /// Color of the edges, the fill is a translucent version of it
+    #[inline]
+    pub(crate) fn color(mut self, color: impl Into<Color32>) -> Self {
+        self.color = color.into();
+        self
+    }
+
+    /// Width of the lines along the minimum and maximum values
+    #[inline]
+    pub(crate) fn width(mut self, width: f32) -> Self {
+        self.width = width;
+        self
+    }
+
+    fn min_max_points(&self) -> (&[PlotPoint], &[PlotPoint]) {
+        self.points.split_at(self.points.len() / 2)
+    }
+}
+
+impl PlotItem for EnvelopeBand {
+    fn shapes(&self, _ui: &Ui, transform: &PlotTransform, shapes: &mut Vec<Shape>) {
+        let (points_min, points_max) = self.min_max_points();
+        let edges: [&[PlotPoint]; 2] = self.min_max_points().into();
+        let alpha = if self.highlight {
+            HIGHLIGHTED_FILL_ALPHA
+        } else {
+            FILL_ALPHA
+        };
+        let fill_color = self.color.gamma_multiply(alpha);
+
+        // A vertex at the min and max of each index, and two triangles between each pair of neighbouring indices
+        let mut mesh = Mesh::default();
+        for (min, max) in points_min.iter().zip(points_max) {
+            mesh.colored_vertex(transform.position_from_point(min), fill_color);
+            mesh.colored_vertex(transform.position_from_point(max), fill_color);
+        }
+        for idx in 1..points_min.len() as u32 {
+            let (prev_min, prev_max, min, max) = (2 * idx - 2, 2 * idx - 1, 2 * idx, 2 * idx + 1);
+            mesh.add_triangle(prev_min, prev_max, max);
+            mesh.add_triangle(prev_min, max, min);
+        }
+        shapes.push(Shape::mesh(mesh));
+
+        let width = if self.highlight {
+            self.width * 2.0
+        } else {
+            self.width
+        };
+        for edge in edges {
+            shapes.push(Shape::line(
+                edge.iter()
+                    .map(|point| transform.position_from_point(point))
+                    .collect(),
+                Stroke::new(width, self.color),
+            ));
+        }
+    }
+
+    fn initialize(&mut self, _x_range: RangeInclusive<f64>) {}
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn color(&self) -> Color32 {
+        self.color
+    }
+
+    fn highlight(&mut self) {
+        self.highlight = true;
+    }
+
+    fn highlighted(&self) -> bool {
+        self.highlight
+    }
+
+    fn allow_hover(&self) -> bool {
+        true
+    }
+
+    fn geometry(&self) -> PlotGeometry<'_> {
+        PlotGeometry::Points(&self.points)
+    }
+
+    fn bounds(&self) -> PlotBounds {
+        let mut bounds = PlotBounds::NOTHING;
+        for point in &self.points {
+            bounds.extend_with(point);
+        }
+        bounds
+    }
+
+    fn id(&self) -> Option<Id> {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn test_envelope_pairs_min_and_max_points() {
+        let band = EnvelopeBand::new(
+            "RPM #1",
+            &[[1.0, 1.0], [2.0, 0.0], [3.0, -1.0]],
+            &[[1.0, 2.0], [2.0, 3.0]],
+        );
+
+        let (points_min, points_max) = band.min_max_points();
+        assert_eq!(
+            points_min,
+            [PlotPoint::new(1.0, 1.0), PlotPoint::new(2.0, 0.0)]
+        );
+        assert_eq!(
+            points_max,
+            [PlotPoint::new(1.0, 2.0), PlotPoint::new(2.0, 3.0)]
+        );
+        assert_eq!(
+            band.bounds(),
+            PlotBounds::from_min_max([1.0, 0.0], [2.0, 3.0])
+        );
+    }
+}
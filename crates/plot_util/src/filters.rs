@@ -0,0 +1,406 @@
+use std::{f64::consts::PI, fmt, io};
+
+use serde::{Deserialize, Serialize};
+
+use crate::mipmap::median_interval;
+
+const NANOS_PER_SEC: f64 = 1_000_000_000.0;
+
+/// Highest order of the Butterworth filters
+pub const MAX_ORDER: usize = 8;
+
+/// A filter that produces a smoothed or band limited companion of a plot, see [`crate::derived::Operation::Filter`]
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize, Serialize)]
+pub enum SignalFilter {
+    /// The mean of a centered window of `samples` samples, an even number is rounded up to the next odd number
+    MovingAverage { samples: usize },
+    /// The median of a centered window of `samples` samples, which removes spikes but keeps steps. An even number
+    /// is rounded up to the next odd number.
+    Median { samples: usize },
+    /// Butterworth low-pass filter
+    LowPass { cutoff_hz: f64, order: usize },
+    /// Butterworth high-pass filter
+    HighPass { cutoff_hz: f64, order: usize },
+    /// Butterworth high-pass filter at `low_hz` followed by a low-pass filter at `high_hz`
+    BandPass {
+        low_hz: f64,
+        high_hz: f64,
+        order: usize,
+    },
+}
+
+impl fmt::Display for SignalFilter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::MovingAverage { samples } => write!(f, "moving average {samples}"),
+            Self::Median { samples } => write!(f, "median {samples}"),
+            Self::LowPass { cutoff_hz, order } => {
+                write!(f, "low-pass {cutoff_hz} Hz order {order}")
+            }
+            Self::HighPass { cutoff_hz, order } => {
+                write!(f, "high-pass {cutoff_hz} Hz order {order}")
+            }
+            Self::BandPass {
+                low_hz,
+                high_hz,
+                order,
+            } => write!(f, "band-pass {low_hz}-{high_hz} Hz order {order}"),
+        }
+    }
+}
+
+impl SignalFilter {
+    /// Filters the `points`, restarting the filter after each gap longer than `gap_threshold`. The x values are
+    /// timestamps in nanoseconds and are kept as they are.
+    ///
+    /// The Butterworth filters run forwards and then backwards over the samples, so the filtered plot isn't delayed,
+    /// which also doubles the attenuation (-6 dB at the cutoff). They assume the samples are uniformly spaced at the
+    /// median sample interval.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`io::ErrorKind::InvalidInput`] if the parameters of the filter are invalid for the samples, e.g. a
+    /// cutoff frequency above the Nyquist frequency.
+    pub fn apply(
+        &self,
+        points: &[[f64; 2]],
+        gap_threshold: Option<f64>,
+    ) -> io::Result<Vec<[f64; 2]>> {
+        let sections = self.biquad_sections(points)?;
+        let mut filtered = Vec::with_capacity(points.len());
+        for segment in split_at_gaps(points, gap_threshold) {
+            let values: Vec<f64> = segment.iter().map(|p| p[1]).collect();
+            let values = match *self {
+                Self::MovingAverage { samples } => moving_average(&values, samples),
+                Self::Median { samples } => moving_median(&values, samples),
+                Self::LowPass { .. } | Self::HighPass { .. } | Self::BandPass { .. } => {
+                    filter_forward_backward(&sections, values)
+                }
+            };
+            filtered.extend(segment.iter().zip(values).map(|(p, value)| [p[0], value]));
+        }
+        Ok(filtered)
+    }
+
+    /// Validates the filter and designs the cascade of biquad sections of the Butterworth filters, which is empty
+    /// for the other filters
+    fn biquad_sections(&self, points: &[[f64; 2]]) -> io::Result<Vec<Biquad>> {
+        let invalid = |msg: String| io::Error::new(io::ErrorKind::InvalidInput, msg);
+        let (cutoffs, order) = match *self {
+            Self::MovingAverage { samples } | Self::Median { samples } => {
+                if samples == 0 {
+                    return Err(invalid(format!(
+                        "The window of the {self} filter must have at least one sample"
+                    )));
+                }
+                return Ok(vec![]);
+            }
+            Self::LowPass { cutoff_hz, order } => ([None, Some(cutoff_hz)], order),
+            Self::HighPass { cutoff_hz, order } => ([Some(cutoff_hz), None], order),
+            Self::BandPass {
+                low_hz,
+                high_hz,
+                order,
+            } => {
+                if low_hz >= high_hz {
+                    return Err(invalid(format!(
+                        "The lower cutoff frequency {low_hz} Hz of the band-pass filter must be below the upper cutoff frequency {high_hz} Hz"
+                    )));
+                }
+                ([Some(low_hz), Some(high_hz)], order)
+            }
+        };
+        if order == 0 || order % 2 != 0 || order > MAX_ORDER {
+            return Err(invalid(format!(
+                "The order of the {self} filter must be an even number from 2 to {MAX_ORDER}"
+            )));
+        }
+        let sample_rate = median_interval(points)
+            .map(|interval| NANOS_PER_SEC / interval)
+            .ok_or_else(|| invalid("Can't determine the sample rate of the plot".to_owned()))?;
+        let nyquist = sample_rate / 2.0;
+        if let Some(cutoff) = cutoffs
+            .iter()
+            .flatten()
+            .find(|&&cutoff| cutoff <= 0.0 || cutoff >= nyquist)
+        {
+            return Err(invalid(format!(
+                "The cutoff frequency {cutoff} Hz must be above 0 Hz and below the Nyquist frequency {nyquist} Hz of the plot"
+            )));
+        }
+
+        let [high_pass, low_pass] = cutoffs;
+        let butterworth = |cutoff: f64, kind: PassKind| {
+            (0..order / 2).map(move |section| {
+                let q = 1.0 / (2.0 * (PI * (2 * section + 1) as f64 / (2 * order) as f64).cos());
+                Biquad::new(kind, cutoff / sample_rate, q)
+            })
+        };
+        Ok(high_pass
+            .into_iter()
+            .flat_map(|cutoff| butterworth(cutoff, PassKind::High))
+            .chain(
+                low_pass
+                    .into_iter()
+                    .flat_map(|cutoff| butterworth(cutoff, PassKind::Low)),
+            )
+            .collect())
+    }
+}
+
+/// Splits the sorted points where consecutive points are further apart than `gap_threshold`
+pub(crate) fn split_at_gaps(
+    points: &[[f64; 2]],
+    gap_threshold: Option<f64>,
+) -> impl Iterator<Item = &[[f64; 2]]> {
+    points.chunk_by(move |a, b| gap_threshold.map_or(true, |threshold| b[0] - a[0] <= threshold))
+}
+
+/// The mean of a centered window of `samples` samples, shortened at the ends
+fn moving_average(values: &[f64], samples: usize) -> Vec<f64> {
+    let half = samples / 2;
+    let mut prefix_sums = Vec::with_capacity(values.len() + 1);
+    prefix_sums.push(0.0);
+    let mut sum = 0.0;
+    for value in values {
+        sum += value;
+        prefix_sums.push(sum);
+    }
+    (0..values.len())
+        .map(|idx| {
+            let (start, end) = (idx.saturating_sub(half), (idx + half + 1).min(values.len()));
+            (prefix_sums[end] - prefix_sums[start]) / (end - start) as f64
+        })
+        .collect()
+}
+
+/// The median of a centered window of `samples` samples, shortened at the ends
+fn moving_median(values: &[f64], samples: usize) -> Vec<f64> {
+    let half = samples / 2;
+    let insertion_idx =
+        |window: &[f64], value: f64| window.partition_point(|v| v.total_cmp(&value).is_lt());
+    let mut window: Vec<f64> = values[..(half + 1).min(values.len())].to_vec();
+    window.sort_unstable_by(f64::total_cmp);
+    (0..values.len())
+        .map(|idx| {
+            if idx > 0 {
+                if let Some(&entering) = values.get(idx + half) {
+                    window.insert(insertion_idx(&window, entering), entering);
+                }
+                if idx > half {
+                    let leaving = values[idx - half - 1];
+                    window.remove(insertion_idx(&window, leaving));
+                }
+            }
+            let mid = window.len() / 2;
+            if window.len() % 2 == 0 {
+                (window[mid - 1] + window[mid]) / 2.0
+            } else {
+                window[mid]
+            }
+        })
+        .collect()
+}
+
+/// Runs the cascade of `sections` over the values forwards and then backwards
+fn filter_forward_backward(sections: &[Biquad], mut values: Vec<f64>) -> Vec<f64> {
+    for _ in 0..2 {
+        for section in sections {
+            section.filter(&mut values);
+        }
+        values.reverse();
+    }
+    values
+}
+
+#[derive(Debug, Clone, Copy)]
+enum PassKind {
+    Low,
+    High,
+}
+
+/// A second order IIR filter section, normalized so `a0` is 1
+#[derive(Debug, Clone, Copy)]
+struct Biquad {
+    b: [f64; 3],
+    a: [f64; 2],
+}
+
+impl Biquad {
+    /// A low- or high-pass section with the cutoff at `normalized_cutoff` times the sample rate, from the bilinear
+    /// transform of the analog prototype
+    fn new(kind: PassKind, normalized_cutoff: f64, q: f64) -> Self {
+        let w0 = 2.0 * PI * normalized_cutoff;
+        let (sin, cos) = w0.sin_cos();
+        let alpha = sin / (2.0 * q);
+        let a0 = 1.0 + alpha;
+        let b = match kind {
+            PassKind::Low => [(1.0 - cos) / 2.0, 1.0 - cos, (1.0 - cos) / 2.0],
+            PassKind::High => [(1.0 + cos) / 2.0, -(1.0 + cos), (1.0 + cos) / 2.0],
+        };
+        Self {
+            b: b.map(|b| b / a0),
+            a: [-2.0 * cos / a0, (1.0 - alpha) / a0],
+        }
+    }
+
+    /// Filters the values in place, starting as if the first value had been the input forever so there's no
+    /// transient from a step at the start
+    fn filter(&self, values: &mut [f64]) {
+        let Some(&first) = values.first() else {
+            return;
+        };
+        let ([b0, b1, b2], [a1, a2]) = (self.b, self.a);
+        let steady_state_gain = (b0 + b1 + b2) / (1.0 + a1 + a2);
+        let steady_output = steady_state_gain * first;
+        let mut z2 = b2 * first - a2 * steady_output;
+        let mut z1 = b1 * first - a1 * steady_output + z2;
+        for value in values {
+            let input = *value;
+            let output = b0 * input + z1;
+            z1 = b1 * input - a1 * output + z2;
+            z2 = b2 * input - a2 * output;
+            *value = output;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f64::consts::TAU;
+
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use testresult::TestResult;
+
+    /// 1 kHz sampling
+    const INTERVAL_NS: f64 = 1_000_000.0;
+
+    fn sampled(count: usize, signal: impl Fn(f64) -> f64) -> Vec<[f64; 2]> {
+        (0..count)
+            .map(|n| {
+                let t = n as f64 * INTERVAL_NS;
+                [t, signal(t / NANOS_PER_SEC)]
+            })
+            .collect()
+    }
+
+    fn max_error(points: &[[f64; 2]], expected: impl Fn(f64) -> f64) -> f64 {
+        points
+            .iter()
+            .map(|[x, y]| (y - expected(x / NANOS_PER_SEC)).abs())
+            .fold(0.0, f64::max)
+    }
+
+    #[test]
+    fn test_low_pass_removes_high_frequencies() -> TestResult {
+        let slow = |t: f64| 5.0 + (TAU * t).sin();
+        let points = sampled(5_000, |t| slow(t) + 0.5 * (TAU * 100.0 * t).sin());
+        let filtered = SignalFilter::LowPass {
+            cutoff_hz: 10.0,
+            order: 4,
+        }
+        .apply(&points, None)?;
+
+        assert_eq!(filtered.len(), points.len());
+        assert!(filtered.iter().zip(&points).all(|(f, p)| f[0] == p[0]));
+        let error = max_error(&filtered[100..4_900], slow);
+        assert!(error < 0.01, "{error}");
+        Ok(())
+    }
+
+    #[test]
+    fn test_high_and_band_pass_remove_offset() -> TestResult {
+        let tone = |t: f64| (TAU * 50.0 * t).sin();
+        let points = sampled(5_000, |t| 100.0 + tone(t));
+        for filter in [
+            SignalFilter::HighPass {
+                cutoff_hz: 5.0,
+                order: 2,
+            },
+            SignalFilter::BandPass {
+                low_hz: 20.0,
+                high_hz: 80.0,
+                order: 4,
+            },
+        ] {
+            let filtered = filter.apply(&points, None)?;
+            let error = max_error(&filtered[1_000..4_000], tone);
+            assert!(error < 0.05, "{filter}: {error}");
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_moving_average_and_median() -> TestResult {
+        let points: Vec<[f64; 2]> = [1.0, 2.0, 3.0, 100.0, 5.0, 6.0]
+            .into_iter()
+            .enumerate()
+            .map(|(x, y)| [x as f64, y])
+            .collect();
+        let values = |filter: SignalFilter| -> io::Result<Vec<f64>> {
+            Ok(filter.apply(&points, None)?.iter().map(|p| p[1]).collect())
+        };
+        assert_eq!(
+            values(SignalFilter::MovingAverage { samples: 3 })?,
+            [1.5, 2.0, 35.0, 36.0, 37.0, 5.5]
+        );
+        assert_eq!(
+            values(SignalFilter::Median { samples: 3 })?,
+            [1.5, 2.0, 3.0, 5.0, 6.0, 5.5]
+        );
+        assert_eq!(
+            values(SignalFilter::Median { samples: 1 })?,
+            [1.0, 2.0, 3.0, 100.0, 5.0, 6.0]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_filters_restart_after_gaps() -> TestResult {
+        let mut points: Vec<[f64; 2]> = (0..10).map(|x| [x as f64, 0.0]).collect();
+        points.extend((100..110).map(|x| [x as f64, 10.0]));
+        let filtered = SignalFilter::MovingAverage { samples: 5 }.apply(&points, Some(10.0))?;
+        assert!(filtered[..10].iter().all(|p| p[1] == 0.0));
+        assert!(filtered[10..].iter().all(|p| p[1] == 10.0));
+
+        // A step after a gap doesn't ring in the low-pass filter either
+        let filtered = SignalFilter::LowPass {
+            cutoff_hz: 1e4,
+            order: 2,
+        }
+        .apply(
+            &points
+                .iter()
+                .map(|[x, y]| [x * 1e3, *y])
+                .collect::<Vec<_>>(),
+            Some(1e4),
+        )?;
+        assert!(filtered[10..].iter().all(|p| (p[1] - 10.0).abs() < 1e-9));
+        Ok(())
+    }
+
+    #[test]
+    fn test_invalid_filters() {
+        let points = sampled(100, |t| t);
+        for filter in [
+            SignalFilter::MovingAverage { samples: 0 },
+            SignalFilter::LowPass {
+                cutoff_hz: 500.0,
+                order: 2,
+            },
+            SignalFilter::HighPass {
+                cutoff_hz: 10.0,
+                order: 3,
+            },
+            SignalFilter::BandPass {
+                low_hz: 20.0,
+                high_hz: 10.0,
+                order: 2,
+            },
+        ] {
+            let err = filter.apply(&points, None).expect_err(&filter.to_string());
+            assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+        }
+    }
+}
@@ -0,0 +1,207 @@
+use std::ops::RangeInclusive;
+
+use egui::{Color32, Id, Rect, Shape, Ui};
+use egui_plot::{PlotBounds, PlotGeometry, PlotItem, PlotTransform};
+use serde::{Deserialize, Serialize};
+
+use crate::mipmap::median_interval;
+
+/// A gap is detected where two consecutive samples of a plot are more than this many median sample intervals apart
+pub const DEFAULT_GAP_FACTOR: f64 = 10.0;
+
+/// Opacity of the shading of a gap
+const SHADING_ALPHA: f32 = 0.12;
+/// Opacity of the shading when the plot is highlighted, e.g. by hovering its legend entry
+const HIGHLIGHTED_SHADING_ALPHA: f32 = 0.3;
+
+/// A period where a plot has no samples, e.g. because a sensor stopped reporting
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize, Serialize)]
+pub struct Gap {
+    start: f64,
+    end: f64,
+}
+
+impl Gap {
+    /// The x value (timestamp) of the last sample before the gap
+    pub fn start(&self) -> f64 {
+        self.start
+    }
+
+    /// The x value (timestamp) of the first sample after the gap
+    pub fn end(&self) -> f64 {
+        self.end
+    }
+
+    pub fn duration(&self) -> f64 {
+        self.end - self.start
+    }
+
+    /// The x value in the middle of the gap
+    pub fn center(&self) -> f64 {
+        self.start + self.duration() / 2.0
+    }
+
+    /// The gap moved by `x_offset`
+    pub(crate) fn translated(self, x_offset: f64) -> Self {
+        Self {
+            start: self.start + x_offset,
+            end: self.end + x_offset,
+        }
+    }
+}
+
+/// The gap threshold of `points` is `factor` times the median interval between samples, or [`None`] if
+/// the median interval is not positive.
+pub fn gap_threshold(points: &[[f64; 2]], factor: f64) -> Option<f64> {
+    median_interval(points).map(|median| median * factor)
+}
+
+/// Returns the gaps between consecutive points that are more than `threshold` apart in x
+pub fn detect_gaps(points: &[[f64; 2]], threshold: f64) -> Vec<Gap> {
+    points
+        .windows(2)
+        .filter(|pair| pair[1][0] - pair[0][0] > threshold)
+        .map(|pair| Gap {
+            start: pair[0][0],
+            end: pair[1][0],
+        })
+        .collect()
+}
+
+/// Returns the gaps of the sorted `gaps` that overlap the x range
+pub(crate) fn gaps_in_range(gaps: &[Gap], (x_min, x_max): (f64, f64)) -> &[Gap] {
+    let start = gaps.partition_point(|gap| gap.end < x_min);
+    let end = gaps.partition_point(|gap| gap.start <= x_max);
+    &gaps[start..end.max(start)]
+}
+
+/// Translucent shading over the full height of the plot for each gap of a plot, with the legend entry of the plot
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct GapShading {
+    gaps: Vec<Gap>,
+    name: String,
+    color: Color32,
+    highlight: bool,
+}
+
+impl GapShading {
+    pub(crate) fn new(name: impl Into<String>, gaps: Vec<Gap>) -> Self {
+        Self {
+            gaps,
+            name: name.into(),
+            color: Color32::TRANSPARENT,
+            highlight: false,
+        }
+    }
+
+    #[inline]
+    pub(crate) fn color(mut self, color: impl Into<Color32>) -> Self {
+        self.color = color.into();
+        self
+    }
+}
+
+impl PlotItem for GapShading {
+    fn shapes(&self, _ui: &Ui, transform: &PlotTransform, shapes: &mut Vec<Shape>) {
+        let alpha = if self.highlight {
+            HIGHLIGHTED_SHADING_ALPHA
+        } else {
+            SHADING_ALPHA
+        };
+        let fill_color = self.color.gamma_multiply(alpha);
+        let frame = transform.frame();
+        for gap in &self.gaps {
+            let (start, end) = (
+                transform.position_from_point_x(gap.start),
+                transform.position_from_point_x(gap.end),
+            );
+            shapes.push(Shape::rect_filled(
+                Rect::from_x_y_ranges(start..=end, frame.y_range()),
+                0.0,
+                fill_color,
+            ));
+        }
+    }
+
+    fn initialize(&mut self, _x_range: RangeInclusive<f64>) {}
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn color(&self) -> Color32 {
+        self.color
+    }
+
+    fn highlight(&mut self) {
+        self.highlight = true;
+    }
+
+    fn highlighted(&self) -> bool {
+        self.highlight
+    }
+
+    fn allow_hover(&self) -> bool {
+        false
+    }
+
+    fn geometry(&self) -> PlotGeometry<'_> {
+        PlotGeometry::None
+    }
+
+    fn bounds(&self) -> PlotBounds {
+        // The shading spans the visible area, so it should not affect the automatic bounds
+        PlotBounds::NOTHING
+    }
+
+    fn id(&self) -> Option<Id> {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn test_detect_gaps_from_median_interval() {
+        let mut points: Vec<[f64; 2]> = (0..10).map(|x| [x as f64, 1.0]).collect();
+        // The sensor stops reporting for a while, twice
+        points.extend((30..40).map(|x| [x as f64, 2.0]));
+        points.extend((55..60).map(|x| [x as f64, 3.0]));
+
+        let threshold = gap_threshold(&points, DEFAULT_GAP_FACTOR);
+        assert_eq!(threshold, Some(10.0));
+        let gaps = detect_gaps(&points, 10.0);
+        assert_eq!(
+            gaps,
+            [
+                Gap {
+                    start: 9.0,
+                    end: 30.0
+                },
+                Gap {
+                    start: 39.0,
+                    end: 55.0
+                }
+            ]
+        );
+        assert_eq!(gaps[0].duration(), 21.0);
+        assert_eq!(gaps[1].center(), 47.0);
+
+        assert_eq!(detect_gaps(&points, 20.0).len(), 1);
+
+        assert_eq!(gaps_in_range(&gaps, (0.0, 8.0)), []);
+        assert_eq!(gaps_in_range(&gaps, (0.0, 9.0)), &gaps[..1]);
+        assert_eq!(gaps_in_range(&gaps, (31.0, 40.0)), &gaps[1..]);
+        assert_eq!(gaps_in_range(&gaps, (20.0, 60.0)), gaps);
+        assert_eq!(gaps_in_range(&gaps, (56.0, 60.0)), []);
+    }
+
+    #[test]
+    fn test_no_gap_threshold_without_increasing_timestamps() {
+        assert_eq!(gap_threshold(&[[1.0, 0.0], [1.0, 1.0]], 5.0), None);
+        assert_eq!(gap_threshold(&[[1.0, 0.0]], 5.0), None);
+    }
+}
@@ -1,15 +1,51 @@
+pub mod derived;
+mod envelope;
+pub mod filters;
+pub mod gaps;
 pub mod mipmap;
+mod series_line;
+pub mod spectrum;
+pub mod step_response;
 
-use egui::Color32;
-use egui_plot::{Line, PlotBounds, PlotPoint};
+use std::fmt;
+
+use egui_plot::{PlotBounds, PlotPoint};
+use envelope::EnvelopeBand;
+use gaps::GapShading;
+use serde::{Deserialize, Serialize};
+use series_line::{LineKind, SeriesLine};
 
 pub mod plots;
 
+pub use series_line::{RenderCache, SharedPoints};
+
 pub use plots::{
     plot_data::{PlotData, PlotValues, StoredPlotLabels},
     Plots,
 };
 
+/// How plots are downsampled when mipmapping is enabled
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum DownsamplingMethod {
+    /// Two lines per plot, one through the minimum values and one through the maximum values
+    #[default]
+    MinMax,
+    /// A band per plot that fills the area between the minimum and the maximum values
+    Envelope,
+    /// A single line per plot that keeps the visual shape, with the Largest-Triangle-Three-Buckets algorithm
+    Lttb,
+}
+
+impl fmt::Display for DownsamplingMethod {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::MinMax => write!(f, "Min/Max"),
+            Self::Envelope => write!(f, "Envelope"),
+            Self::Lttb => write!(f, "LTTB"),
+        }
+    }
+}
+
 /// An instance of a `MipMap` configuration for a given frame
 #[derive(Debug, Clone, Copy)]
 pub enum MipMapConfiguration {
@@ -23,22 +59,57 @@ pub fn plot_lines<'pv>(
     plots: impl Iterator<Item = &'pv PlotValues>,
     line_width: f32,
     mipmap_cfg: MipMapConfiguration,
+    method: DownsamplingMethod,
     plots_width_pixels: usize,
+    shade_gaps: bool,
 ) {
     let (x_lower, x_higher) = extended_x_plot_bound(plot_ui.plot_bounds(), 0.1);
     for plot_vals in plots {
+        if shade_gaps {
+            let visible_gaps = plot_vals.gaps_in_view((x_lower, x_higher));
+            if !visible_gaps.is_empty() {
+                plot_ui.add(
+                    GapShading::new(plot_vals.label(), visible_gaps).color(plot_vals.get_color()),
+                );
+            }
+        }
+        match mipmap_cfg {
+            MipMapConfiguration::Auto => plot_vals.build_mipmaps_if_zoomed_out(
+                plot_ui.ctx(),
+                plots_width_pixels,
+                (x_lower, x_higher),
+            ),
+            MipMapConfiguration::Manual(level) if level > 0 => {
+                plot_vals.build_mipmaps(plot_ui.ctx());
+            }
+            MipMapConfiguration::Manual(_) | MipMapConfiguration::Disabled => (),
+        }
         match mipmap_cfg {
-            MipMapConfiguration::Disabled => plot_raw(plot_ui, plot_vals, (x_lower, x_higher)),
+            MipMapConfiguration::Disabled => {
+                plot_raw(
+                    plot_ui,
+                    plot_vals,
+                    line_width,
+                    (x_lower, x_higher),
+                    plots_width_pixels,
+                );
+            }
             MipMapConfiguration::Auto => {
                 let (level, idx_range) =
-                    plot_vals.get_scaled_mipmap_levels(plots_width_pixels, (x_lower, x_higher));
+                    match method {
+                        DownsamplingMethod::MinMax | DownsamplingMethod::Envelope => plot_vals
+                            .get_scaled_mipmap_levels(plots_width_pixels, (x_lower, x_higher)),
+                        DownsamplingMethod::Lttb => plot_vals
+                            .get_scaled_lttb_levels(plots_width_pixels, (x_lower, x_higher)),
+                    };
 
                 plot_with_mipmapping(
                     plot_ui,
                     plot_vals,
                     line_width,
-                    level,
+                    (level, method),
                     (x_lower, x_higher),
+                    plots_width_pixels,
                     idx_range,
                 );
             }
@@ -47,8 +118,9 @@ pub fn plot_lines<'pv>(
                     plot_ui,
                     plot_vals,
                     line_width,
-                    level,
+                    (level, method),
                     (x_lower, x_higher),
+                    plots_width_pixels,
                     None,
                 );
             }
@@ -60,90 +132,130 @@ fn plot_with_mipmapping(
     plot_ui: &mut egui_plot::PlotUi,
     plot_vals: &PlotValues,
     line_width: f32,
-    mipmap_lvl: usize,
+    (mipmap_lvl, method): (usize, DownsamplingMethod),
     x_range: (f64, f64),
+    plots_width_pixels: usize,
     // if the range is already known then we can skip filtering
     known_idx_range: Option<(usize, usize)>,
 ) {
-    let (x_lower, x_higher) = x_range;
     if mipmap_lvl == 0 {
-        plot_raw(plot_ui, plot_vals, (x_lower, x_higher));
-    } else {
-        let (plot_points_min, plot_points_max) = plot_vals.get_level_or_max(mipmap_lvl);
-        if plot_points_min.is_empty() {
-            // In this case there was so few samples that downsampling just once was below the minimum threshold, so we just plot all samples
-            plot_raw(plot_ui, plot_vals, (x_lower, x_higher));
-        } else {
-            let (plot_points_min, plot_points_max) = match known_idx_range {
-                Some((start, end)) => {
-                    extract_range_points(plot_points_min, plot_points_max, start, end)
-                }
-                None => (
-                    filter_plot_points(plot_points_min, (x_lower, x_higher)),
-                    filter_plot_points(plot_points_max, (x_lower, x_higher)),
-                ),
-            };
-
-            plot_min_max_lines(
-                plot_ui,
-                plot_vals.label(),
-                (plot_points_min, plot_points_max),
-                line_width,
-                plot_vals.get_color(),
+        plot_raw(plot_ui, plot_vals, line_width, x_range, plots_width_pixels);
+        return;
+    }
+    match method {
+        DownsamplingMethod::MinMax | DownsamplingMethod::Envelope => plot_min_max_mipmap(
+            plot_ui,
+            plot_vals,
+            line_width,
+            (mipmap_lvl, method),
+            x_range,
+            plots_width_pixels,
+            known_idx_range,
+        ),
+        DownsamplingMethod::Lttb => {
+            if plot_vals.get_lttb_level_or_max(mipmap_lvl).is_empty() {
+                plot_raw(plot_ui, plot_vals, line_width, x_range, plots_width_pixels);
+                return;
+            }
+            plot_ui.add(
+                SeriesLine::new(
+                    plot_vals.label(),
+                    plot_vals.line_view((LineKind::Lttb, mipmap_lvl), x_range, plots_width_pixels),
+                )
+                .color(plot_vals.get_color())
+                .width(line_width)
+                .dashed(plot_vals.is_dashed()),
             );
         }
     }
 }
 
-#[inline(always)]
-fn extract_range_points(
-    points_min: &[[f64; 2]],
-    points_max: &[[f64; 2]],
-    start: usize,
-    end: usize,
-) -> (Vec<[f64; 2]>, Vec<[f64; 2]>) {
-    let element_count = end - start + 2;
-    let mut min_points = Vec::with_capacity(element_count);
-    let mut max_points = Vec::with_capacity(element_count);
-
-    min_points.push(points_min[0]);
-    max_points.push(points_max[0]);
-
-    min_points.extend_from_slice(&points_min[start..end]);
-    max_points.extend_from_slice(&points_max[start..end]);
-
-    if let Some(last_point) = points_min.last() {
-        if min_points.last().is_some_and(|lp| lp != last_point) {
-            min_points.push(*last_point);
-        }
+/// Plots a level of the min and max mipmaps as two lines or as an [`EnvelopeBand`]
+fn plot_min_max_mipmap(
+    plot_ui: &mut egui_plot::PlotUi,
+    plot_vals: &PlotValues,
+    line_width: f32,
+    (mipmap_lvl, method): (usize, DownsamplingMethod),
+    x_range: (f64, f64),
+    plots_width_pixels: usize,
+    known_idx_range: Option<(usize, usize)>,
+) {
+    let (plot_points_min, plot_points_max) = plot_vals.get_level_or_max(mipmap_lvl);
+    if plot_points_min.is_empty() {
+        // In this case there was so few samples that downsampling just once was below the minimum threshold, so we just plot all samples
+        plot_raw(plot_ui, plot_vals, line_width, x_range, plots_width_pixels);
+        return;
     }
-    if let Some(last_point) = points_max.last() {
-        if max_points.last().is_some_and(|lp| lp != last_point) {
-            max_points.push(*last_point);
+    if method == DownsamplingMethod::Envelope {
+        let gap_threshold = plot_vals.get_level_gap_threshold(mipmap_lvl);
+        let x_offset = plot_vals.x_offset();
+        // The envelope pairs the min and max points by index, so both are extracted from the same index range
+        let (start, end) = known_idx_range.unwrap_or_else(|| {
+            (
+                plot_points_min.partition_point(|point| point[0] < x_range.0 - x_offset),
+                plot_points_min.partition_point(|point| point[0] < x_range.1 - x_offset),
+            )
+        });
+        let plot_points_max = extract_range_points(plot_points_max, start, end);
+        let mut segment_start = 0;
+        for segment_min in split_at_gaps(
+            &extract_range_points(plot_points_min, start, end),
+            gap_threshold,
+        ) {
+            let segment_end = segment_start + segment_min.len();
+            plot_ui.add(
+                EnvelopeBand::new(
+                    plot_vals.label(),
+                    segment_min,
+                    // The max points can be one shorter, if the last point was already in the range
+                    &plot_points_max[segment_start.min(plot_points_max.len())
+                        ..segment_end.min(plot_points_max.len())],
+                )
+                .x_offset(x_offset)
+                .color(plot_vals.get_color())
+                .width(line_width),
+            );
+            segment_start = segment_end;
         }
+        return;
     }
 
-    (min_points, max_points)
+    for (kind, suffix) in [(LineKind::Min, " (min)"), (LineKind::Max, " (max)")] {
+        let mut label = plot_vals.label().to_owned();
+        label.push_str(suffix);
+        plot_ui.add(
+            SeriesLine::new(
+                label,
+                plot_vals.line_view((kind, mipmap_lvl), x_range, plots_width_pixels),
+            )
+            .color(plot_vals.get_color())
+            .width(line_width)
+            .dashed(plot_vals.is_dashed()),
+        );
+    }
 }
 
-#[inline]
-fn plot_min_max_lines(
-    plot_ui: &mut egui_plot::PlotUi,
-    base_label: &str,
-    (points_min, points_max): (Vec<[f64; 2]>, Vec<[f64; 2]>),
-    line_width: f32,
-    color: Color32,
-) {
-    let mut label_min = base_label.to_owned();
-    label_min.push_str(" (min)");
-    let mut label_max = base_label.to_owned();
-    label_max.push_str(" (max)");
-
-    let line_min = Line::new(points_min).name(label_min).color(color);
-    let line_max = Line::new(points_max).name(label_max).color(color);
+/// Extracts the points from `start` to `end`, along with the first and last point
+#[inline(always)]
+fn extract_range_points(points: &[[f64; 2]], start: usize, end: usize) -> Vec<[f64; 2]> {
+    let mut range_points = Vec::with_capacity(end - start + 2);
+    range_points.push(points[0]);
+    range_points.extend_from_slice(&points[start..end]);
+    if let Some(last_point) = points.last() {
+        if range_points.last().is_some_and(|lp| lp != last_point) {
+            range_points.push(*last_point);
+        }
+    }
+    range_points
+}
 
-    plot_ui.line(line_min.width(line_width));
-    plot_ui.line(line_max.width(line_width));
+/// Splits `points` into the segments between gaps, where a gap is two consecutive points that are more than
+/// `gap_threshold` apart in x. Without a threshold all the points are one segment.
+fn split_at_gaps(
+    points: &[[f64; 2]],
+    gap_threshold: Option<f64>,
+) -> impl Iterator<Item = &[[f64; 2]]> {
+    points.chunk_by(move |a, b| gap_threshold.map_or(true, |threshold| b[0] - a[0] <= threshold))
 }
 
 pub fn plot_labels(plot_ui: &mut egui_plot::PlotUi, plot_data: &PlotData, id_filter: &[usize]) {
@@ -161,13 +273,26 @@ pub fn plot_labels(plot_ui: &mut egui_plot::PlotUi, plot_data: &PlotData, id_fil
     }
 }
 
-fn plot_raw(plot_ui: &mut egui_plot::PlotUi, plot_vals: &PlotValues, x_min_max_ext: (f64, f64)) {
-    let plot_points = plot_vals.get_raw();
-    let filtered_points = filter_plot_points(plot_points, x_min_max_ext);
-    let line = Line::new(filtered_points)
-        .name(plot_vals.label())
-        .color(plot_vals.get_color());
-    plot_ui.line(line);
+/// Plots the points of the plot within the x range, with breaks at the gaps of the plot.
+///
+/// The points are reduced to the ones that make a difference at the width of the plots, and borrowed from
+/// the plot data through its render cache, so no points are copied when the view doesn't change.
+fn plot_raw(
+    plot_ui: &mut egui_plot::PlotUi,
+    plot_vals: &PlotValues,
+    line_width: f32,
+    x_min_max_ext: (f64, f64),
+    plots_width_pixels: usize,
+) {
+    plot_ui.add(
+        SeriesLine::new(
+            plot_vals.label(),
+            plot_vals.line_view((LineKind::Raw, 0), x_min_max_ext, plots_width_pixels),
+        )
+        .color(plot_vals.get_color())
+        .width(line_width)
+        .dashed(plot_vals.is_dashed()),
+    );
 }
 
 #[inline(always)]
@@ -237,6 +362,15 @@ pub fn filter_plot_points(points: &[[f64; 2]], x_range: (f64, f64)) -> Vec<[f64;
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_split_at_gaps() {
+        let points = [[0.0, 1.0], [1.0, 1.0], [5.0, 1.0], [6.0, 1.0], [20.0, 1.0]];
+
+        let segments: Vec<&[[f64; 2]]> = split_at_gaps(&points, Some(2.0)).collect();
+        assert_eq!(segments, [&points[..2], &points[2..4], &points[4..]]);
+        assert_eq!(split_at_gaps(&points, None).count(), 1);
+    }
+
     #[test]
     fn test_less_than_1024_points_no_filtering() {
         let points: Vec<[f64; 2]> = (0..500).map(|i| [i as f64, i as f64 + 1.0]).collect();
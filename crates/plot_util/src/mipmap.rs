@@ -1,7 +1,6 @@
-use std::cell::RefCell;
-
 /// Adapted from: <https://github.com/nchechulin/mipmap-1d/>
 use num_traits::{FromPrimitive, Num, ToPrimitive};
+use parking_lot::Mutex;
 use serde::{Deserialize, Serialize};
 
 #[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
@@ -65,6 +64,8 @@ pub enum MipMapStrategy {
     Linear,
     Min,
     Max,
+    /// Largest-Triangle-Three-Buckets, keeps the visual shape of the data in a single line, see [`lttb`]
+    Lttb,
 }
 
 #[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
@@ -82,55 +83,109 @@ impl<T: Num + ToPrimitive + FromPrimitive + Copy + PartialOrd> LevelLookupCached
     }
 }
 
+/// The most recent [`MipMap2D::get_level_match`], behind a lock such that a mipmap can be built on another thread
+#[derive(Debug, Default)]
+struct LookupCache<T: Num + ToPrimitive + FromPrimitive + PartialOrd>(Mutex<LevelLookupCached<T>>);
+
+impl<T: Num + ToPrimitive + FromPrimitive + PartialOrd> PartialEq for LookupCache<T> {
+    /// The cache doesn't affect equality
+    fn eq(&self, _other: &Self) -> bool {
+        true
+    }
+}
+
+/// At most this many intervals between points are sampled for finding the median sample interval
+const MEDIAN_INTERVAL_SAMPLES: usize = 100_000;
+
+/// A level is chosen for a view if it has more than this many points per pixel within the view
+pub(crate) const MIN_POINTS_PER_PIXEL: f64 = 1.0;
+
+/// Downsampled levels of 2D points sorted by x, e.g. timestamps.
+///
+/// The points of each level are put in buckets of time, twice as wide as those of the level below, starting
+/// at twice the median sample interval of the source, and one point is kept per bucket with the chosen
+/// [`MipMapStrategy`]. Points on either side of a gap in the data are therefore never merged, and a level keeps
+/// the gap, see [`MipMap2D::gap_threshold`]. If the source has no regular spacing in time, e.g. if most points
+/// share their timestamp with the point before, the points are paired by index instead.
 #[derive(Debug, PartialEq, Serialize, Deserialize)]
 pub struct MipMap2D<T: Num + ToPrimitive + FromPrimitive + PartialOrd> {
     strategy: MipMapStrategy,
     data: Vec<Vec<[T; 2]>>,
-    most_recent_lookup: RefCell<LevelLookupCached<T>>,
+    /// The width of the time buckets of each level, [`None`] for level 0 and for levels paired by index
+    #[serde(default)]
+    bucket_widths: Vec<Option<f64>>,
+    #[serde(skip)]
+    most_recent_lookup: LookupCache<T>,
 }
 
 impl<T: Num + ToPrimitive + FromPrimitive + Copy + PartialOrd + Default> MipMap2D<T> {
     pub fn new(source: Vec<[T; 2]>, strategy: MipMapStrategy, min_elements: usize) -> Self {
-        let mut data = vec![source.clone()];
-        let mut current = source;
-
-        while current.len() > min_elements {
-            let mipmap = Self::downsample(&current, strategy);
-            current.clone_from(&mipmap);
-            data.push(mipmap);
-        }
-
-        Self {
-            data,
-            strategy,
-            most_recent_lookup: RefCell::new(LevelLookupCached::default()),
-        }
+        let levels = Self::build_levels(&source, strategy, min_elements);
+        Self::from_levels(source, levels, strategy)
     }
 
     /// Create a [`MipMap2D`] but don't include the base level. Retrieving level 0 will then return an empty vec.
     ///
     /// Useful to avoid multiple redundant copies of the source if creating multiple [`MipMap2D`] from the same source.
     pub fn without_base(source: &[[T; 2]], strategy: MipMapStrategy, min_elements: usize) -> Self {
-        // Include an empty vector at level 0 to make the levels align with what they normally would.
-        let mut data = vec![Vec::<[T; 2]>::default()];
-
-        let mut current = Self::downsample(source, strategy);
-        if current.len() > min_elements {
-            data.push(current.clone());
-            while current.len() > min_elements {
-                let mipmap = Self::downsample(&current, strategy);
-                current.clone_from(&mipmap);
-                data.push(mipmap);
-            }
+        let mut levels = Self::build_levels(source, strategy, min_elements);
+        // If the first level is already below the minimum, there's no point in downsampling at all
+        if levels
+            .first()
+            .is_some_and(|(level, _)| level.len() <= min_elements)
+        {
+            levels.clear();
         }
+        // Include an empty vector at level 0 to make the levels align with what they normally would.
+        Self::from_levels(Vec::default(), levels, strategy)
+    }
 
+    fn from_levels(
+        base: Vec<[T; 2]>,
+        levels: Vec<(Vec<[T; 2]>, Option<f64>)>,
+        strategy: MipMapStrategy,
+    ) -> Self {
+        let (levels, widths): (Vec<_>, Vec<_>) = levels.into_iter().unzip();
         Self {
-            data,
+            data: std::iter::once(base).chain(levels).collect(),
+            bucket_widths: std::iter::once(None).chain(widths).collect(),
             strategy,
-            most_recent_lookup: RefCell::new(LevelLookupCached::default()),
+            most_recent_lookup: LookupCache::default(),
         }
     }
 
+    /// Downsamples the `source` level by level until a level has at most `min_elements` points, and returns
+    /// each level with the width of its time buckets.
+    fn build_levels(
+        source: &[[T; 2]],
+        strategy: MipMapStrategy,
+        min_elements: usize,
+    ) -> Vec<(Vec<[T; 2]>, Option<f64>)> {
+        let x_origin = source
+            .first()
+            .and_then(|[x, _]| x.to_f64())
+            .unwrap_or_default();
+        let mut bucket_width = median_interval(source);
+        let mut levels: Vec<(Vec<[T; 2]>, Option<f64>)> = vec![];
+        loop {
+            let current = levels.last().map_or(source, |(level, _)| level.as_slice());
+            if current.len() <= min_elements.max(1) {
+                break;
+            }
+            bucket_width = bucket_width.map(|width| width * 2.0);
+            let mipmap = Self::downsample(
+                current,
+                strategy,
+                bucket_width.map(|width| (x_origin, width)),
+            );
+            // If no points shared a bucket the level is skipped, and the next tries twice as wide buckets
+            if mipmap.len() < current.len() {
+                levels.push((mipmap, bucket_width));
+            }
+        }
+        levels
+    }
+
     /// Returns the total number of downsampled levels.
     /// Equal to `ceil(log2(source.len())`
     pub fn num_levels(&self) -> usize {
@@ -162,40 +217,79 @@ impl<T: Num + ToPrimitive + FromPrimitive + Copy + PartialOrd + Default> MipMap2
         &self.data[self.num_levels() - 1]
     }
 
-    /// Downsamples a vector to `ceil(len / 2)` elements with the chosen [`MipMapStrategy`]
-    fn downsample(source: &[[T; 2]], strategy: MipMapStrategy) -> Vec<[T; 2]> {
-        let strategy = match strategy {
-            MipMapStrategy::Linear => |point_pair: &[[T; 2]]| {
-                let x = Self::linear_interpolate(point_pair[0][0], point_pair[1][0])
-                    .expect("Interpolation error, this is a bug, please report it");
-                let y = Self::linear_interpolate(point_pair[0][1], point_pair[1][1])
-                    .expect("Interpolation error, this is a bug, please report it");
-                [x, y]
-            },
-            MipMapStrategy::Min => |pairs: &[[T; 2]]| {
-                // Branchless way of selecting the point with the smallest X-value
-                let index_bool: usize = (pairs[0][1] > pairs[1][1]) as usize;
-                pairs[index_bool]
-            },
-            MipMapStrategy::Max => |pairs: &[[T; 2]]| {
-                // Branchless way of selecting the point with the greatest X-value
-                let index_bool: usize = (pairs[0][1] < pairs[1][1]) as usize;
-                pairs[index_bool]
-            },
+    /// The distance in x between two consecutive points of `level` above which there is a gap in the data, i.e.
+    /// at least one empty time bucket between them. Levels above the max are treated as the max level like in
+    /// [`MipMap2D::get_level_or_max`].
+    ///
+    /// Returns [`None`] for level 0, and for levels where the points were paired by index.
+    pub fn gap_threshold(&self, level: usize) -> Option<f64> {
+        self.bucket_widths
+            .get(level.min(self.num_levels() - 1))
+            .copied()
+            .flatten()
+            .map(|width| 2.0 * width)
+    }
+
+    /// Downsamples the `source` to one point per bucket with the chosen [`MipMapStrategy`].
+    ///
+    /// With `time_buckets` as `(x_origin, width)` the buckets span `width` in x starting from `x_origin`,
+    /// otherwise the points are paired by index.
+    fn downsample(
+        source: &[[T; 2]],
+        strategy: MipMapStrategy,
+        time_buckets: Option<(f64, f64)>,
+    ) -> Vec<[T; 2]> {
+        let buckets: Vec<&[[T; 2]]> = match time_buckets {
+            Some((x_origin, width)) => {
+                let bucket_idx = |[x, _]: &[T; 2]| {
+                    ((x.to_f64().unwrap_or_default() - x_origin) / width).floor() as i64
+                };
+                source
+                    .chunk_by(|a, b| bucket_idx(a) == bucket_idx(b))
+                    .collect()
+            }
+            None => source.chunks(2).collect(),
         };
-        source
-            .chunks(2)
-            .map(|pairs| match pairs.len() {
-                1 => pairs[0],
-                2 => strategy(pairs),
-                _ => unreachable!("Unsound condition"),
-            })
-            .collect()
+        match strategy {
+            MipMapStrategy::Lttb => lttb_buckets(&buckets),
+            MipMapStrategy::Linear => buckets
+                .iter()
+                .map(|bucket| {
+                    Self::average(bucket)
+                        .expect("Interpolation error, this is a bug, please report it")
+                })
+                .collect(),
+            // The first of equal points is kept
+            MipMapStrategy::Min => buckets
+                .iter()
+                .filter_map(|bucket| {
+                    bucket
+                        .iter()
+                        .copied()
+                        .reduce(|min, point| if point[1] < min[1] { point } else { min })
+                })
+                .collect(),
+            MipMapStrategy::Max => buckets
+                .iter()
+                .filter_map(|bucket| {
+                    bucket
+                        .iter()
+                        .copied()
+                        .reduce(|max, point| if point[1] > max[1] { point } else { max })
+                })
+                .collect(),
+        }
     }
 
-    fn linear_interpolate(a: T, b: T) -> Option<T> {
-        let res = T::from_f64((a + b).to_f64()? / 2.0)?;
-        Some(res)
+    /// The average of the points in `bucket`
+    fn average(bucket: &[[T; 2]]) -> Option<[T; 2]> {
+        let mut sum = [0.0; 2];
+        for [x, y] in bucket {
+            sum[0] += x.to_f64()?;
+            sum[1] += y.to_f64()?;
+        }
+        let count = bucket.len() as f64;
+        Some([T::from_f64(sum[0] / count)?, T::from_f64(sum[1] / count)?])
     }
 
     /// Retrieves the index of the level that matches the specified pixel width
@@ -220,20 +314,20 @@ impl<T: Num + ToPrimitive + FromPrimitive + Copy + PartialOrd + Default> MipMap2
     /// The level that matches the requirement (or the highest resolution), and if
     /// the match was found in a level below the max resolution, also returns a tuple
     /// of the start and end index of the level that matches the requirement.
+    ///
+    /// The matching level is the most downsampled level that has more than one point
+    /// per pixel within the bounds, so gaps in the visible time range count as the
+    /// empty space they are, rather than by the number of points of the whole level.
     pub fn get_level_match(
         &self,
         pixel_width: usize,
         x_bounds: (T, T),
     ) -> (usize, Option<(usize, usize)>) {
-        if self
-            .most_recent_lookup
-            .borrow()
-            .is_equal(pixel_width, x_bounds)
         {
-            return (
-                self.most_recent_lookup.borrow().result_idx,
-                Some(self.most_recent_lookup.borrow().result_span),
-            );
+            let cached = self.most_recent_lookup.0.lock();
+            if cached.is_equal(pixel_width, x_bounds) {
+                return (cached.result_idx, Some(cached.result_span));
+            }
         }
         let target_point_count = pixel_width;
         let (x_min, x_max) = x_bounds;
@@ -256,14 +350,14 @@ impl<T: Num + ToPrimitive + FromPrimitive + Copy + PartialOrd + Default> MipMap2
             // Use saturating_sub for safety and to avoid potential panic
             let count_within_bounds = end_idx.saturating_sub(start_idx);
 
-            if count_within_bounds > target_point_count {
+            if count_within_bounds as f64 / pixel_width as f64 > MIN_POINTS_PER_PIXEL {
                 let new_cached = LevelLookupCached {
                     pixel_width,
                     x_bounds,
                     result_span: (start_idx, end_idx),
                     result_idx: lvl_idx,
                 };
-                self.most_recent_lookup.replace(new_cached);
+                *self.most_recent_lookup.0.lock() = new_cached;
                 return (lvl_idx, Some((start_idx, end_idx)));
             }
         }
@@ -271,6 +365,109 @@ impl<T: Num + ToPrimitive + FromPrimitive + Copy + PartialOrd + Default> MipMap2
     }
 }
 
+/// Downsamples `source` to `threshold` points with the Largest-Triangle-Three-Buckets algorithm.
+///
+/// The first and last points are always kept. The points in between are split into `threshold - 2` buckets,
+/// and from each bucket the point is kept that forms the largest triangle with the previously kept point and
+/// the average of the next bucket. Peaks and valleys are therefore kept, and the result is a subset of `source`.
+///
+/// Source: Sveinn Steinarsson, "Downsampling Time Series for Visual Representation", 2013.
+pub fn lttb<T: ToPrimitive + Copy>(source: &[[T; 2]], threshold: usize) -> Vec<[T; 2]> {
+    let len = source.len();
+    if threshold >= len {
+        return source.to_vec();
+    }
+    match threshold {
+        0 => return vec![],
+        1 => return vec![source[0]],
+        2 => return vec![source[0], source[len - 1]],
+        _ => (),
+    }
+    // u64 as the product overflows a 32-bit usize on wasm
+    let bucket_start =
+        |bucket: usize| (bucket as u64 * (len - 2) as u64 / (threshold - 2) as u64) as usize + 1;
+    let mut buckets = Vec::with_capacity(threshold);
+    buckets.push(&source[..1]);
+    buckets.extend(
+        (0..threshold - 2).map(|bucket| &source[bucket_start(bucket)..bucket_start(bucket + 1)]),
+    );
+    buckets.push(&source[len - 1..]);
+    lttb_buckets(&buckets)
+}
+
+/// Keeps one point per bucket with the Largest-Triangle-Three-Buckets algorithm, see [`lttb`].
+///
+/// The first point of the first bucket and the last point of the last bucket are kept.
+fn lttb_buckets<T: ToPrimitive + Copy>(buckets: &[&[[T; 2]]]) -> Vec<[T; 2]> {
+    // Coordinates relative to the first point, to keep the precision of the areas with large timestamps
+    let origin = buckets
+        .first()
+        .and_then(|bucket| bucket.first())
+        .map_or([0.0; 2], |point| coords(point, [0.0; 2]));
+    let mut sampled: Vec<[T; 2]> = Vec::with_capacity(buckets.len());
+    for (idx, bucket) in buckets.iter().enumerate() {
+        let (Some(first), Some(last)) = (bucket.first(), bucket.last()) else {
+            continue;
+        };
+        let next = buckets.get(idx + 1).filter(|next| !next.is_empty());
+        let point = match (sampled.last(), next) {
+            (None, _) => *first,
+            (Some(_), None) => *last,
+            (Some(prev), Some(next)) => {
+                let [prev_x, prev_y] = coords(prev, origin);
+                let [avg_x, avg_y] = next
+                    .iter()
+                    .map(|point| coords(point, origin))
+                    .fold([0.0; 2], |[xs, ys], [x, y]| [xs + x, ys + y])
+                    .map(|sum| sum / next.len() as f64);
+                let area = |point: &[T; 2]| {
+                    let [x, y] = coords(point, origin);
+                    ((prev_x - avg_x) * (y - prev_y) - (prev_x - x) * (avg_y - prev_y)).abs()
+                };
+                // The first of equally large triangles is kept
+                bucket
+                    .iter()
+                    .copied()
+                    .reduce(|max, point| {
+                        if area(&point) > area(&max) {
+                            point
+                        } else {
+                            max
+                        }
+                    })
+                    .unwrap_or(*first)
+            }
+        };
+        sampled.push(point);
+    }
+    sampled
+}
+
+/// The coordinates of `point` as `f64` relative to `origin`
+fn coords<T: ToPrimitive>([x, y]: &[T; 2], [x_origin, y_origin]: [f64; 2]) -> [f64; 2] {
+    [
+        x.to_f64().unwrap_or_default() - x_origin,
+        y.to_f64().unwrap_or_default() - y_origin,
+    ]
+}
+
+/// The median interval between the x values of consecutive points, or [`None`] if it is not positive, e.g. if
+/// most points share their x value with the point before.
+pub(crate) fn median_interval<T: ToPrimitive>(source: &[[T; 2]]) -> Option<f64> {
+    let stride = (source.len() / MEDIAN_INTERVAL_SAMPLES).max(1);
+    let mut intervals: Vec<f64> = source
+        .windows(2)
+        .step_by(stride)
+        .filter_map(|pair| Some(pair[1][0].to_f64()? - pair[0][0].to_f64()?))
+        .collect();
+    if intervals.is_empty() {
+        return None;
+    }
+    let mid = intervals.len() / 2;
+    let (_, median, _) = intervals.select_nth_unstable_by(mid, f64::total_cmp);
+    (median.is_finite() && *median > 0.0).then_some(*median)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -419,6 +616,113 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_lttb_keeps_endpoints_and_peaks() {
+        let mut source: Vec<[f64; 2]> = (0..100).map(|i| [i as f64, 0.0]).collect();
+        source[42][1] = 10.0;
+        source[77][1] = -10.0;
+
+        let sampled = lttb(&source, 10);
+
+        assert_eq!(sampled.len(), 10);
+        assert_eq!(sampled.first(), source.first());
+        assert_eq!(sampled.last(), source.last());
+        assert!(sampled.contains(&[42.0, 10.0]));
+        assert!(sampled.contains(&[77.0, -10.0]));
+        assert!(sampled.windows(2).all(|w| w[0][0] < w[1][0]));
+    }
+
+    #[test]
+    fn test_lttb_small_thresholds() {
+        let source: Vec<[f64; 2]> = vec![[1.0, 2.0], [2.0, 5.0], [3.0, 1.0]];
+
+        assert_eq!(lttb(&source, 5), source);
+        assert_eq!(lttb(&source, 2), vec![[1.0, 2.0], [3.0, 1.0]]);
+        assert_eq!(lttb(&source, 1), vec![[1.0, 2.0]]);
+        assert!(lttb(&source, 0).is_empty());
+    }
+
+    #[test]
+    fn test_mipmap_strategy_lttb_levels() {
+        let source: Vec<[f64; 2]> = (0..16)
+            .map(|i| [i as f64 + UNIX_TS_NS, (i as f64).sin()])
+            .collect();
+        let mipmap = MipMap2D::new(source, MipMapStrategy::Lttb, 1);
+
+        let level_lens: Vec<usize> = (0..mipmap.num_levels())
+            .filter_map(|lvl| mipmap.get_level(lvl).map(<[_]>::len))
+            .collect();
+        assert_eq!(level_lens, [16, 8, 4, 2, 1]);
+    }
+
+    #[test]
+    fn test_time_buckets_keep_gaps() {
+        // Two runs sampled every second with a gap of 900 seconds in between
+        let source: Vec<[f64; 2]> = (0..100)
+            .chain(1000..1100)
+            .map(|i| [i as f64, i as f64])
+            .collect();
+        let mipmap = MipMap2D::new(source, MipMapStrategy::Linear, 1);
+
+        assert_eq!(mipmap.gap_threshold(0), None);
+        // Once the buckets are as wide as the gap, the gap is below a pixel anyway and the runs are merged
+        let levels_narrower_than_gap =
+            (1..mipmap.num_levels()).take_while(|&level| mipmap.gap_threshold(level) < Some(900.0));
+        assert_eq!(levels_narrower_than_gap.clone().count(), 7);
+        for level in levels_narrower_than_gap {
+            let points = mipmap.get_level(level).unwrap_or_default();
+            assert!(
+                points.iter().all(|[x, _]| *x < 100.0 || *x >= 1000.0),
+                "Level {level} bridged the gap: {points:?}"
+            );
+            let threshold = mipmap.gap_threshold(level).unwrap_or_default();
+            assert_eq!(
+                points
+                    .windows(2)
+                    .filter(|w| w[1][0] - w[0][0] > threshold)
+                    .count(),
+                1,
+                "Expected exactly one gap at level {level}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_irregular_sampling_is_bucketed_by_time() {
+        // A burst of 8 points followed by points every 4 seconds, the burst is merged into one bucket
+        let source: Vec<[f64; 2]> = (0..8)
+            .map(|i| [i as f64 * 0.5, 1.0])
+            .chain((1..9).map(|i| [3.5 + 4.0 * i as f64, 1.0]))
+            .collect();
+        let mipmap = MipMap2D::new(source, MipMapStrategy::Max, 1);
+
+        // The median interval is 4 seconds, so the first level has buckets of 8 seconds
+        assert_eq!(mipmap.gap_threshold(1), Some(16.0));
+        let first_level = mipmap.get_level(1).unwrap_or_default();
+        assert_eq!(
+            first_level,
+            [
+                [0.0, 1.0],
+                [11.5, 1.0],
+                [19.5, 1.0],
+                [27.5, 1.0],
+                [35.5, 1.0]
+            ]
+        );
+    }
+
+    #[test]
+    fn test_shared_timestamps_fall_back_to_index_pairs() {
+        let source: Vec<[f64; 2]> = (0..8).map(|i| [(i / 4) as f64, i as f64]).collect();
+        let mipmap = MipMap2D::new(source, MipMapStrategy::Max, 1);
+
+        assert_eq!(mipmap.gap_threshold(1), None);
+        assert_eq!(
+            mipmap.get_level(1),
+            Some([[0.0, 1.0], [0.0, 3.0], [1.0, 5.0], [1.0, 7.0]].as_slice())
+        );
+    }
+
     #[test]
     fn test_out_of_bounds_level() {
         let source: Vec<[f64; 2]> = vec![[1.0, 2.0], [3.0, 4.0]];
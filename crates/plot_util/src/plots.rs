@@ -1,5 +1,6 @@
 use serde::{Deserialize, Serialize};
 
+mod lazy_mipmaps;
 pub mod plot_data;
 mod util;
 
@@ -28,6 +29,29 @@ impl Plots {
         total_points
     }
 
+    /// All the plots, in the order of the plot areas
+    pub fn iter(&self) -> impl Iterator<Item = &PlotValues> {
+        self.percentage
+            .plots()
+            .iter()
+            .chain(self.one_to_hundred.plots())
+            .chain(self.thousands.plots())
+    }
+
+    /// Returns the plot with the given label, see [`PlotValues::label`]
+    pub fn find_by_label(&self, label: &str) -> Option<&PlotValues> {
+        self.iter().find(|plot| plot.label() == label)
+    }
+
+    /// The plot data of each plot area
+    pub fn plot_data_mut(&mut self) -> [&mut PlotData; 3] {
+        [
+            &mut self.percentage,
+            &mut self.one_to_hundred,
+            &mut self.thousands,
+        ]
+    }
+
     pub fn percentage(&self) -> &PlotData {
         &self.percentage
     }
@@ -86,21 +110,21 @@ fn calc_plot_x_min_max(plots: &[PlotValues], x_min_max: &mut Option<(f64, f64)>)
         if plot.raw_plot().len() < 2 {
             continue;
         }
-        let Some(first_x) = plot.raw_plot().first().and_then(|f| f.first()) else {
+        let Some(first_x) = plot.raw_plot().first().map(|f| f[0] + plot.x_offset()) else {
             continue;
         };
-        let Some(last_x) = plot.raw_plot().last().and_then(|l| l.first()) else {
+        let Some(last_x) = plot.raw_plot().last().map(|l| l[0] + plot.x_offset()) else {
             continue;
         };
         if let Some((current_x_min, current_x_max)) = x_min_max {
-            if first_x < current_x_min {
-                *current_x_min = *first_x;
+            if first_x < *current_x_min {
+                *current_x_min = first_x;
             }
-            if last_x > current_x_max {
-                *current_x_max = *last_x;
+            if last_x > *current_x_max {
+                *current_x_max = last_x;
             }
         } else {
-            x_min_max.replace((*first_x, *last_x));
+            x_min_max.replace((first_x, last_x));
         }
     }
 }
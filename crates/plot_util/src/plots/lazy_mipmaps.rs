@@ -0,0 +1,139 @@
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    Arc, OnceLock,
+};
+
+use crate::{
+    mipmap::{MipMap2D, MipMapStrategy},
+    series_line::SharedPoints,
+};
+
+/// Don't mipmap/downsample to more than this amount of elements
+const MIPMAP_MIN_ELEMENTS: usize = 512;
+
+/// The mipmaps of a plot, one for each [`MipMapStrategy`] that is drawn
+#[derive(Debug, PartialEq)]
+pub(crate) struct Mipmaps {
+    pub(crate) min: MipMap2D<f64>,
+    pub(crate) max: MipMap2D<f64>,
+    pub(crate) lttb: MipMap2D<f64>,
+}
+
+impl Mipmaps {
+    /// Builds the mipmaps in parallel
+    fn build(points: &[[f64; 2]]) -> Self {
+        let build_mipmap = |strategy| MipMap2D::without_base(points, strategy, MIPMAP_MIN_ELEMENTS);
+        let (min, (max, lttb)) = rayon::join(
+            || build_mipmap(MipMapStrategy::Min),
+            || {
+                rayon::join(
+                    || build_mipmap(MipMapStrategy::Max),
+                    || build_mipmap(MipMapStrategy::Lttb),
+                )
+            },
+        );
+        Self { min, max, lttb }
+    }
+}
+
+#[derive(Debug, Default)]
+struct LazyState {
+    build_started: AtomicBool,
+    mipmaps: OnceLock<Mipmaps>,
+}
+
+/// [`Mipmaps`] that are built the first time they're needed, which is when the plot is zoomed out far enough
+/// that a downsampled level is drawn.
+///
+/// On native the mipmaps are built in the background, and the plot is drawn without downsampling until they
+/// are ready. A clone shares the mipmaps with the original.
+#[derive(Debug, Default, Clone)]
+pub(crate) struct LazyMipmaps(Arc<LazyState>);
+
+impl LazyMipmaps {
+    /// Returns the mipmaps if they are built
+    pub(crate) fn get(&self) -> Option<&Mipmaps> {
+        self.0.mipmaps.get()
+    }
+
+    /// Returns the mipmaps if they are built, otherwise starts building them (if not already started) and
+    /// calls `on_ready` once they are.
+    pub(crate) fn get_or_build(
+        &self,
+        points: &SharedPoints,
+        on_ready: impl FnOnce() + Send + 'static,
+    ) -> Option<&Mipmaps> {
+        if let Some(mipmaps) = self.get() {
+            return Some(mipmaps);
+        }
+        if self.0.build_started.swap(true, Ordering::AcqRel) {
+            return None;
+        }
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            let (state, points) = (Arc::clone(&self.0), Arc::clone(points));
+            rayon::spawn(move || {
+                state.mipmaps.get_or_init(|| Mipmaps::build(&points));
+                on_ready();
+            });
+            None
+        }
+        // There's no threads to build them on in the background
+        #[cfg(target_arch = "wasm32")]
+        {
+            let mipmaps = self.0.mipmaps.get_or_init(|| Mipmaps::build(points));
+            on_ready();
+            Some(mipmaps)
+        }
+    }
+
+    /// Returns the mipmaps, building them on the current thread if needed
+    pub(crate) fn get_blocking(&self, points: &[[f64; 2]]) -> &Mipmaps {
+        self.0.build_started.store(true, Ordering::Release);
+        self.0.mipmaps.get_or_init(|| Mipmaps::build(points))
+    }
+}
+
+impl PartialEq for LazyMipmaps {
+    /// The mipmaps are derived from the plot data, so they don't affect equality
+    fn eq(&self, _other: &Self) -> bool {
+        true
+    }
+}
+
+#[cfg(test)]
+#[cfg(not(target_arch = "wasm32"))]
+mod tests {
+    use std::sync::mpsc;
+
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use testresult::TestResult;
+
+    #[test]
+    fn test_mipmaps_are_built_once_in_the_background() -> TestResult {
+        let points: SharedPoints = Arc::new((0..10_000).map(|x| [x as f64, x as f64]).collect());
+        let mipmaps = LazyMipmaps::default();
+        assert!(mipmaps.get().is_none());
+
+        let (tx, rx) = mpsc::channel();
+        let ready = tx.clone();
+        assert!(mipmaps
+            .get_or_build(&points, move || ready.send(()).unwrap())
+            .is_none());
+        // Already building, so this doesn't start another build
+        assert!(mipmaps
+            .get_or_build(&points, move || tx.send(()).unwrap())
+            .is_none());
+        rx.recv()?;
+        assert!(rx.recv().is_err(), "expected a single build");
+
+        let built = mipmaps.get().ok_or("expected built mipmaps")?;
+        assert_eq!(built, mipmaps.get_blocking(&points));
+        assert_eq!(
+            built.min,
+            MipMap2D::without_base(&points, MipMapStrategy::Min, MIPMAP_MIN_ELEMENTS)
+        );
+        Ok(())
+    }
+}
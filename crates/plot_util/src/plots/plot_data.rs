@@ -1,11 +1,20 @@
+use std::{io, sync::Arc};
+
 use chrono::{DateTime, Utc};
 use egui::Color32;
+use egui_plot::PlotBounds;
 use log_if::prelude::RawPlot;
 use serde::{Deserialize, Serialize};
 
-use crate::mipmap::{MipMap2D, MipMapStrategy};
+use crate::{
+    derived::{self, Derivation, Operation},
+    gaps::{self, Gap},
+    mipmap::MIN_POINTS_PER_PIXEL,
+    series_line::{LineKind, ReducedLine, RenderCache, SharedPoints},
+    spectrum::{self, Spectrogram, Spectrum, WindowFunction},
+};
 
-use super::util;
+use super::{lazy_mipmaps::LazyMipmaps, util};
 
 #[derive(Debug, Default, PartialEq, Deserialize, Serialize)]
 pub struct PlotData {
@@ -42,6 +51,33 @@ impl PlotData {
         self.plots.iter().any(|p| p.label() == plot_label)
     }
 
+    /// Returns the plot with the label `plot_label`
+    pub fn find_plot(&self, plot_label: &str) -> Option<&PlotValues> {
+        self.plots.iter().find(|p| p.label() == plot_label)
+    }
+
+    /// Adds a plot computed from another plot, e.g. by [`PlotValues::derived`], if another plot with the same label
+    /// doesn't already exist. It gets a color of its own if it doesn't have one.
+    pub fn add_derived_plot(&mut self, plot: PlotValues) {
+        if self.contains_plot(plot.label()) {
+            return;
+        }
+        let plot = if plot.get_color() == Color32::TRANSPARENT {
+            let color = self.auto_color();
+            plot.color(color)
+        } else {
+            plot
+        };
+        self.plots.push(plot);
+    }
+
+    /// Removes the plot with the label `plot_label`, returns whether there was one
+    pub fn remove_plot(&mut self, plot_label: &str) -> bool {
+        let count = self.plots.len();
+        self.plots.retain(|p| p.label() != plot_label);
+        self.plots.len() != count
+    }
+
     /// Adds a plot to the [`PlotData`] collection if another plot with the same label doesn't already exist
     pub fn add_plot_if_not_exists(&mut self, raw_plot: &RawPlot, log_id: usize) {
         let mut plot_label = String::with_capacity(30); // Approx. enough to not reallocate
@@ -73,42 +109,61 @@ impl PlotData {
 
 #[derive(Debug, PartialEq, Deserialize, Serialize)]
 pub struct PlotValues {
-    raw_plot: Vec<[f64; 2]>,
-    mipmap_max: MipMap2D<f64>,
-    mipmap_min: MipMap2D<f64>,
+    /// The samples as they were loaded, see [`PlotValues::x_offset`]
+    raw_plot: SharedPoints,
+    /// Added to the x value (timestamp) of every sample, e.g. when the start date of the log is changed
+    #[serde(default)]
+    x_offset: f64,
+    #[serde(skip)]
+    mipmaps: LazyMipmaps,
+    /// Consecutive samples further apart than this are not connected by a line, see [`PlotValues::set_gap_factor`]
+    #[serde(default)]
+    gap_threshold: Option<f64>,
+    #[serde(default)]
+    gaps: Vec<Gap>,
     name: String,
     log_id: usize,
     // Label = "<name> #<log_id>"
     label: String,
+    /// Set if the plot was computed from another plot
+    #[serde(default)]
+    derivation: Option<Derivation>,
     color: Color32,
+    /// Drawn with a dashed line, e.g. to tell compared runs apart
+    #[serde(default)]
+    dashed: bool,
+    #[serde(skip)]
+    render_cache: RenderCache,
 }
 
 type PointList<'pl> = &'pl [[f64; 2]];
 
 impl PlotValues {
-    // Don't mipmap/downsample to more than this amount of elements
-    const MIPMAP_MIN_ELEMENTS: usize = 512;
-
     pub fn new(raw_plot: Vec<[f64; 2]>, name: String, log_id: usize) -> Self {
-        let label = format!("{name} #{log_id}");
-        Self {
-            mipmap_max: MipMap2D::without_base(
-                &raw_plot,
-                MipMapStrategy::Max,
-                Self::MIPMAP_MIN_ELEMENTS,
-            ),
-            mipmap_min: MipMap2D::without_base(
-                &raw_plot,
-                MipMapStrategy::Min,
-                Self::MIPMAP_MIN_ELEMENTS,
-            ),
-            raw_plot,
+        let label = Self::label_of(&name, log_id);
+        let gap_threshold = gaps::gap_threshold(&raw_plot, gaps::DEFAULT_GAP_FACTOR);
+        let mut plot_values = Self {
+            raw_plot: Arc::new(raw_plot),
+            x_offset: 0.0,
+            mipmaps: LazyMipmaps::default(),
+            gap_threshold,
+            gaps: vec![],
             name,
             log_id,
             label,
+            derivation: None,
             // Color32::TRANSPARENT means we auto assign one
             color: Color32::TRANSPARENT,
-        }
+            dashed: false,
+            render_cache: RenderCache::default(),
+        };
+        plot_values.detect_gaps();
+        plot_values
+    }
+
+    /// The label of the plot named `name` of the log with `log_id`, see [`PlotValues::label`]
+    pub fn label_of(name: &str, log_id: usize) -> String {
+        format!("{name} #{log_id}")
     }
 
     /// Stroke color. Default is `Color32::TRANSPARENT` which means a color will be auto-assigned.
@@ -124,62 +179,271 @@ impl PlotValues {
         self.color
     }
 
+    /// Whether the plot is drawn with a dashed line
+    #[inline]
+    pub fn is_dashed(&self) -> bool {
+        self.dashed
+    }
+
+    pub fn set_dashed(&mut self, dashed: bool) {
+        self.dashed = dashed;
+    }
+
+    /// Returns the samples as they were loaded, without the [`PlotValues::x_offset`]
     pub fn get_raw(&self) -> PointList {
         &self.raw_plot
     }
 
-    pub fn get_level(&self, level: usize) -> Option<(PointList, PointList)> {
-        let mipmap_min = self.mipmap_min.get_level(level)?;
-        let mipmap_max = self.mipmap_max.get_level(level)?;
-        Some((mipmap_min, mipmap_max))
+    /// The points of the plot, with the [`PlotValues::x_offset`] applied
+    pub fn points(&self) -> impl ExactSizeIterator<Item = [f64; 2]> + '_ {
+        self.raw_plot.iter().map(|&[x, y]| [x + self.x_offset, y])
+    }
+
+    /// Added to the x value (timestamp) of the samples when the plot is drawn
+    pub fn x_offset(&self) -> f64 {
+        self.x_offset
+    }
+
+    /// Converts an x range of the drawn plot to the range of the samples
+    fn sample_range(&self, (x_min, x_max): (f64, f64)) -> (f64, f64) {
+        (x_min - self.x_offset, x_max - self.x_offset)
+    }
+
+    /// Starts building the mipmaps in the background if they are needed to draw the plot in `x_range`, which is
+    /// when there are more samples in the range than `pixel_width`. `ctx` is asked to repaint when they're ready.
+    pub fn build_mipmaps_if_zoomed_out(
+        &self,
+        ctx: &egui::Context,
+        pixel_width: usize,
+        x_range: (f64, f64),
+    ) {
+        if self.mipmaps.get().is_some() {
+            return;
+        }
+        let (x_min, x_max) = self.sample_range(x_range);
+        let start = self.raw_plot.partition_point(|point| point[0] < x_min);
+        let end = self.raw_plot.partition_point(|point| point[0] <= x_max);
+        if (end.saturating_sub(start)) as f64 / pixel_width as f64 > MIN_POINTS_PER_PIXEL {
+            self.build_mipmaps(ctx);
+        }
+    }
+
+    /// Starts building the mipmaps in the background if they're not built, `ctx` is asked to repaint when they're ready
+    pub fn build_mipmaps(&self, ctx: &egui::Context) {
+        let ctx = ctx.clone();
+        self.mipmaps
+            .get_or_build(&self.raw_plot, move || ctx.request_repaint());
+    }
+
+    /// Builds the mipmaps on the current thread if they're not built
+    pub fn build_mipmaps_blocking(&self) {
+        self.mipmaps.get_blocking(&self.raw_plot);
+    }
+
+    /// The bounds of all the points and the points of a line to draw in `x_range`, reduced to what can be
+    /// seen in `columns` pixel columns. The level is ignored for [`LineKind::Raw`], and the line is empty if
+    /// the mipmaps of a downsampled line are not built.
+    ///
+    /// The most recent view of each line is cached, so redrawing the same view doesn't copy any points.
+    pub(crate) fn line_view(
+        &self,
+        (kind, level): (LineKind, usize),
+        x_range: (f64, f64),
+        columns: usize,
+    ) -> (PlotBounds, Arc<ReducedLine>) {
+        let mipmap = self.mipmaps.get().map(|mipmaps| match kind {
+            LineKind::Raw | LineKind::Min => &mipmaps.min,
+            LineKind::Max => &mipmaps.max,
+            LineKind::Lttb => &mipmaps.lttb,
+        });
+        let (points, gap_threshold, level) = match (kind, mipmap) {
+            (LineKind::Raw, _) => (&self.raw_plot[..], self.gap_threshold, 0),
+            (_, Some(mipmap)) => (
+                mipmap.get_level_or_max(level),
+                mipmap.gap_threshold(level),
+                level,
+            ),
+            (_, None) => (&[][..], None, level),
+        };
+        let reduced = self.render_cache.view(
+            (kind, level, points),
+            x_range,
+            columns,
+            (gap_threshold, self.x_offset),
+        );
+        let mut bounds = self.render_cache.bounds(&self.raw_plot);
+        bounds.translate_x(self.x_offset);
+        (bounds, reduced)
     }
 
+    /// Returns the min and max level of the mipmaps, or the highest if the requested level is higher or equal
+    /// to the max, and empty levels if the mipmaps are not built.
+    ///
+    /// The points are samples, without the [`PlotValues::x_offset`].
     pub fn get_level_or_max(&self, level: usize) -> (PointList, PointList) {
-        (
-            self.mipmap_min.get_level_or_max(level),
-            self.mipmap_max.get_level_or_max(level),
-        )
+        self.mipmaps.get().map_or((&[], &[]), |mipmaps| {
+            (
+                mipmaps.min.get_level_or_max(level),
+                mipmaps.max.get_level_or_max(level),
+            )
+        })
+    }
+
+    /// Returns the level of the single line LTTB mipmap, or the highest if the requested level is higher or
+    /// equal to the max, and an empty level if the mipmaps are not built
+    pub fn get_lttb_level_or_max(&self, level: usize) -> PointList {
+        self.mipmaps
+            .get()
+            .map_or(&[], |mipmaps| mipmaps.lttb.get_level_or_max(level))
+    }
+
+    /// The gap threshold of the min/max levels, see [`crate::mipmap::MipMap2D::gap_threshold`]
+    pub fn get_level_gap_threshold(&self, level: usize) -> Option<f64> {
+        self.mipmaps.get()?.min.gap_threshold(level)
+    }
+
+    /// Consecutive samples further apart than this are considered a gap
+    pub fn gap_threshold(&self) -> Option<f64> {
+        self.gap_threshold
+    }
+
+    /// The detected gaps, sorted by time, with the [`PlotValues::x_offset`] applied
+    pub fn gaps(&self) -> impl ExactSizeIterator<Item = Gap> + '_ {
+        self.gaps.iter().map(|gap| gap.translated(self.x_offset))
+    }
+
+    /// The detected gaps that overlap the x range of the drawn plot
+    pub(crate) fn gaps_in_view(&self, x_range: (f64, f64)) -> Vec<Gap> {
+        gaps::gaps_in_range(&self.gaps, self.sample_range(x_range))
+            .iter()
+            .map(|gap| gap.translated(self.x_offset))
+            .collect()
     }
 
-    pub fn get_max_level(&self) -> (PointList, PointList) {
-        (
-            self.mipmap_min.get_max_level(),
-            self.mipmap_max.get_max_level(),
+    /// The power spectral density of the samples in the x range of the drawn plot, see
+    /// [`spectrum::power_spectral_density`]
+    pub fn power_spectral_density(
+        &self,
+        x_range: (f64, f64),
+        window: WindowFunction,
+        segment_len: usize,
+    ) -> Option<Spectrum> {
+        spectrum::power_spectral_density(
+            &self.raw_plot,
+            self.sample_range(x_range),
+            window,
+            segment_len,
         )
     }
 
-    pub fn mipmap_levels(&self) -> usize {
-        self.mipmap_min.num_levels()
+    /// The spectrogram of all the samples, see [`spectrum::spectrogram`]. The x range of the spectrogram doesn't
+    /// include the [`PlotValues::x_offset`].
+    pub fn spectrogram(
+        &self,
+        window: WindowFunction,
+        segment_len: usize,
+        max_columns: usize,
+    ) -> Option<Spectrogram> {
+        spectrum::spectrogram(&self.raw_plot, window, segment_len, max_columns)
     }
 
+    /// A plot computed from this plot by `operation`, named by [`Operation::derived_name`] and tagged with this plot
+    /// as its source. It keeps the time offset and the gaps of this plot.
+    ///
+    /// # Errors
+    ///
+    /// If the operation is invalid for the samples of this plot, see [`Operation::apply`]
+    pub fn derived(&self, operation: Operation) -> io::Result<Self> {
+        let points = operation.apply(&self.raw_plot, self.gap_threshold)?;
+        let mut derived = Self::new(points, operation.derived_name(&self.name), self.log_id);
+        derived.x_offset = self.x_offset;
+        derived.gap_threshold = self.gap_threshold;
+        derived.gaps.clone_from(&self.gaps);
+        derived.derivation = Some(Derivation::new(self.label.clone(), operation));
+        Ok(derived)
+    }
+
+    /// This plot minus `subtrahend` on the time base of this plot, see [`Operation::Difference`]. The samples of the
+    /// `subtrahend` are interpolated at the timestamps of this plot as they are drawn, so the time offsets of both
+    /// plots are taken into account. It keeps the time offset of this plot.
+    pub fn difference(&self, subtrahend: &Self) -> Self {
+        let minuend: Vec<[f64; 2]> = self.points().collect();
+        let subtrahend_points: Vec<[f64; 2]> = subtrahend.points().collect();
+        let points = derived::difference(&minuend, &subtrahend_points, subtrahend.gap_threshold)
+            .into_iter()
+            .map(|[x, y]| [x - self.x_offset, y])
+            .collect();
+        let mut difference = Self::new(
+            points,
+            Operation::Difference.derived_name(&self.name),
+            self.log_id,
+        );
+        difference.x_offset = self.x_offset;
+        difference.derivation = Some(Derivation::difference(
+            self.label.clone(),
+            subtrahend.label.clone(),
+        ));
+        difference
+    }
+
+    /// How this plot was computed from another plot, [`None`] if it was loaded
+    pub fn derivation(&self) -> Option<&Derivation> {
+        self.derivation.as_ref()
+    }
+
+    /// Sets the gap threshold to `factor` times the median sample interval and detects the gaps again,
+    /// [`None`] disables gap detection.
+    pub fn set_gap_factor(&mut self, factor: Option<f64>) {
+        self.gap_threshold = factor.and_then(|factor| gaps::gap_threshold(&self.raw_plot, factor));
+        self.detect_gaps();
+    }
+
+    fn detect_gaps(&mut self) {
+        self.gaps = self
+            .gap_threshold
+            .map_or_else(Vec::new, |t| gaps::detect_gaps(&self.raw_plot, t));
+    }
+
+    /// The level of the min/max mipmaps that matches the view, see [`crate::mipmap::MipMap2D::get_level_match`]. Level 0 (the
+    /// samples) if the mipmaps are not built.
     pub fn get_scaled_mipmap_levels(
         &self,
         pixel_width: usize,
         x_bounds: (f64, f64),
     ) -> (usize, Option<(usize, usize)>) {
-        self.mipmap_min.get_level_match(pixel_width, x_bounds)
+        self.mipmaps.get().map_or((0, None), |mipmaps| {
+            mipmaps
+                .min
+                .get_level_match(pixel_width, self.sample_range(x_bounds))
+        })
     }
 
-    /// Apply an offset to the plot based on the difference to the supplied [`DateTime<Utc>`]
-    pub fn offset_plot(&mut self, new_start_date: DateTime<Utc>) {
-        util::offset_data_iter(self.raw_plot.iter_mut(), new_start_date);
-        self.recalc_mipmaps();
+    /// Same as [`PlotValues::get_scaled_mipmap_levels`] but for the levels of the LTTB mipmap
+    pub fn get_scaled_lttb_levels(
+        &self,
+        pixel_width: usize,
+        x_bounds: (f64, f64),
+    ) -> (usize, Option<(usize, usize)>) {
+        self.mipmaps.get().map_or((0, None), |mipmaps| {
+            mipmaps
+                .lttb
+                .get_level_match(pixel_width, self.sample_range(x_bounds))
+        })
     }
 
-    fn recalc_mipmaps(&mut self) {
-        self.mipmap_min = MipMap2D::without_base(
-            &self.raw_plot,
-            MipMapStrategy::Min,
-            Self::MIPMAP_MIN_ELEMENTS,
-        );
-        self.mipmap_max = MipMap2D::without_base(
-            &self.raw_plot,
-            MipMapStrategy::Max,
-            Self::MIPMAP_MIN_ELEMENTS,
-        );
+    /// Offsets the plot such that it starts at the supplied [`DateTime<Utc>`]
+    ///
+    /// Only the [`PlotValues::x_offset`] changes, so the mipmaps and gaps don't need to be recalculated.
+    pub fn offset_plot(&mut self, new_start_date: DateTime<Utc>) {
+        if let Some(first_point) = self.raw_plot.first() {
+            let new_date_ns = new_start_date
+                .timestamp_nanos_opt()
+                .expect("Nanoseconds overflow") as f64;
+            self.x_offset = new_date_ns - first_point[0];
+        }
     }
 
-    /// Returns a borrowed list of all plot points
+    /// Returns a borrowed list of all the samples as they were loaded, without the [`PlotValues::x_offset`]
     pub fn raw_plot(&self) -> PointList {
         &self.raw_plot
     }
@@ -0,0 +1,436 @@
+use std::{ops::RangeInclusive, sync::Arc};
+
+use egui::{Color32, Id, Pos2, Shape, Stroke, Ui};
+use egui_plot::{PlotBounds, PlotGeometry, PlotItem, PlotPoint, PlotTransform};
+use parking_lot::Mutex;
+
+/// The points of a plot, shared between the plot data and the items that draw it, so drawing doesn't copy them
+pub type SharedPoints = Arc<Vec<[f64; 2]>>;
+
+/// Below this many points per pixel column, all the points in view are drawn
+const MAX_POINTS_PER_COLUMN: usize = 4;
+
+/// The points of a plot that are drawn in a view, with at most 4 points per pixel column: The first, last,
+/// minimum and maximum point of the column. This draws the same pixels as drawing all the points.
+#[derive(Debug, Default, PartialEq)]
+pub(crate) struct ReducedLine {
+    points: Vec<PlotPoint>,
+    /// The end (exclusive) of each line segment in `points`, the line is broken between segments
+    segment_ends: Vec<usize>,
+}
+
+impl ReducedLine {
+    /// Reduces the `points` within `x_range`, and one point on either side so the line reaches the edges.
+    ///
+    /// Consecutive points that are more than `gap_threshold` apart are put in separate segments. The points
+    /// are moved by `x_offset`, which the range is of.
+    fn reduce_into(
+        &mut self,
+        points: &[[f64; 2]],
+        x_range: (f64, f64),
+        columns: usize,
+        (gap_threshold, x_offset): (Option<f64>, f64),
+    ) {
+        let (x_min, x_max) = (x_range.0 - x_offset, x_range.1 - x_offset);
+        self.points.clear();
+        self.segment_ends.clear();
+        let start = points
+            .partition_point(|point| point[0] < x_min)
+            .saturating_sub(1);
+        let end = (points.partition_point(|point| point[0] <= x_max) + 1).min(points.len());
+        let Some(visible) = points.get(start..end).filter(|visible| !visible.is_empty()) else {
+            return;
+        };
+
+        let column_width = (x_max - x_min) / columns.max(1) as f64;
+        let reduce = visible.len() > MAX_POINTS_PER_COLUMN * columns && column_width > 0.0;
+        let column_of = |x: f64| ((x - x_min) / column_width).floor() as i64;
+
+        let mut column = Column::new(0, visible[0]);
+        let mut column_idx = column_of(visible[0][0]);
+        for (idx, pair) in visible.windows(2).enumerate() {
+            let (prev, point) = (pair[0], pair[1]);
+            let idx = idx + 1;
+            let is_gap = gap_threshold.is_some_and(|threshold| point[0] - prev[0] > threshold);
+            let point_column_idx = column_of(point[0]);
+            if is_gap || !reduce || point_column_idx != column_idx {
+                column.push_points(visible, x_offset, &mut self.points);
+                if is_gap {
+                    self.segment_ends.push(self.points.len());
+                }
+                column = Column::new(idx, point);
+                column_idx = point_column_idx;
+            } else {
+                column.add(idx, point);
+            }
+        }
+        column.push_points(visible, x_offset, &mut self.points);
+        self.segment_ends.push(self.points.len());
+    }
+
+    fn segments(&self) -> impl Iterator<Item = &[PlotPoint]> {
+        let starts = std::iter::once(0).chain(self.segment_ends.iter().copied());
+        starts
+            .zip(&self.segment_ends)
+            .map(|(start, &end)| &self.points[start..end])
+    }
+}
+
+/// The indices of the first, last, minimum and maximum point of a pixel column
+#[derive(Debug, Clone, Copy)]
+struct Column {
+    first: usize,
+    last: usize,
+    min: (usize, f64),
+    max: (usize, f64),
+}
+
+impl Column {
+    fn new(idx: usize, [_, y]: [f64; 2]) -> Self {
+        Self {
+            first: idx,
+            last: idx,
+            min: (idx, y),
+            max: (idx, y),
+        }
+    }
+
+    fn add(&mut self, idx: usize, [_, y]: [f64; 2]) {
+        self.last = idx;
+        if y < self.min.1 {
+            self.min = (idx, y);
+        }
+        if y > self.max.1 {
+            self.max = (idx, y);
+        }
+    }
+
+    /// Pushes the points of the column in the order they were sampled, moved by `x_offset`
+    fn push_points(&self, points: &[[f64; 2]], x_offset: f64, out: &mut Vec<PlotPoint>) {
+        let mut indices = [self.first, self.min.0, self.max.0, self.last];
+        indices.sort_unstable();
+        let mut prev_idx = None;
+        for idx in indices {
+            if prev_idx != Some(idx) {
+                let [x, y] = points[idx];
+                out.push(PlotPoint::new(x + x_offset, y));
+                prev_idx = Some(idx);
+            }
+        }
+    }
+}
+
+/// The lines of a plot that are drawn with a [`SeriesLine`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum LineKind {
+    Raw,
+    Min,
+    Max,
+    Lttb,
+}
+
+/// Identifies the view a [`ReducedLine`] was made for
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct ViewKey {
+    level: usize,
+    x_range: (f64, f64),
+    columns: usize,
+    gap_threshold: Option<f64>,
+    x_offset: f64,
+}
+
+#[derive(Debug, Default)]
+struct CacheState {
+    bounds: Option<PlotBounds>,
+    /// The most recent view of each [`LineKind`]
+    views: [Option<(ViewKey, Arc<ReducedLine>)>; 4],
+}
+
+/// Caches the bounds of a plot and the [`ReducedLine`] of the most recent view of each of its lines, such that
+/// a frame where the view didn't change doesn't iterate over or copy the points of the plot.
+///
+/// A clone shares the cache with the original.
+#[derive(Debug, Default, Clone)]
+pub struct RenderCache(Arc<Mutex<CacheState>>);
+
+impl RenderCache {
+    /// The bounds of the `points` of the plot, computed once
+    pub(crate) fn bounds(&self, points: &[[f64; 2]]) -> PlotBounds {
+        *self.0.lock().bounds.get_or_insert_with(|| {
+            let mut bounds = PlotBounds::NOTHING;
+            for &[x, y] in points {
+                bounds.extend_with(&PlotPoint::new(x, y));
+            }
+            bounds
+        })
+    }
+
+    /// Returns the reduced line of the `points` of a level of a line in the view, from the cache if possible
+    pub(crate) fn view(
+        &self,
+        (kind, level, points): (LineKind, usize, &[[f64; 2]]),
+        x_range: (f64, f64),
+        columns: usize,
+        (gap_threshold, x_offset): (Option<f64>, f64),
+    ) -> Arc<ReducedLine> {
+        let key = ViewKey {
+            level,
+            x_range,
+            columns,
+            gap_threshold,
+            x_offset,
+        };
+        let mut state = self.0.lock();
+        let cached_view = &mut state.views[kind as usize];
+        let reduced = match cached_view.take() {
+            Some((cached_key, reduced)) if cached_key == key => reduced,
+            previous => {
+                // Reuse the allocations of the previous view if it is no longer drawn
+                let mut reduced = previous
+                    .and_then(|(_, reduced)| Arc::into_inner(reduced))
+                    .unwrap_or_default();
+                reduced.reduce_into(points, x_range, columns, (gap_threshold, x_offset));
+                Arc::new(reduced)
+            }
+        };
+        *cached_view = Some((key, Arc::clone(&reduced)));
+        drop(state);
+        reduced
+    }
+}
+
+impl PartialEq for RenderCache {
+    /// The cache is derived from the plot data, so it doesn't affect equality
+    fn eq(&self, _other: &Self) -> bool {
+        true
+    }
+}
+
+/// Draws a [`ReducedLine`], breaking the line between segments and drawing a single point segment as a marker
+#[derive(Debug, Clone)]
+pub(crate) struct SeriesLine {
+    reduced: Arc<ReducedLine>,
+    bounds: PlotBounds,
+    name: String,
+    color: Color32,
+    width: f32,
+    dashed: bool,
+    highlight: bool,
+}
+
+impl SeriesLine {
+    pub(crate) fn new(
+        name: impl Into<String>,
+        (bounds, reduced): (PlotBounds, Arc<ReducedLine>),
+    ) -> Self {
+        Self {
+            reduced,
+            bounds,
+            name: name.into(),
+            color: Color32::TRANSPARENT,
+            width: 1.0,
+            dashed: false,
+            highlight: false,
+        }
+    }
+
+    #[inline]
+    pub(crate) fn color(mut self, color: impl Into<Color32>) -> Self {
+        self.color = color.into();
+        self
+    }
+
+    #[inline]
+    pub(crate) fn width(mut self, width: f32) -> Self {
+        self.width = width;
+        self
+    }
+
+    #[inline]
+    pub(crate) fn dashed(mut self, dashed: bool) -> Self {
+        self.dashed = dashed;
+        self
+    }
+}
+
+impl PlotItem for SeriesLine {
+    fn shapes(&self, _ui: &Ui, transform: &PlotTransform, shapes: &mut Vec<Shape>) {
+        let width = if self.highlight {
+            self.width * 2.0
+        } else {
+            self.width
+        };
+        for segment in self.reduced.segments() {
+            if let [point] = segment {
+                shapes.push(Shape::circle_filled(
+                    transform.position_from_point(point),
+                    width,
+                    self.color,
+                ));
+            } else {
+                let positions: Vec<Pos2> = segment
+                    .iter()
+                    .map(|point| transform.position_from_point(point))
+                    .collect();
+                let stroke = Stroke::new(width, self.color);
+                if self.dashed {
+                    shapes.extend(Shape::dashed_line(
+                        &positions,
+                        stroke,
+                        4.0 * width,
+                        3.0 * width,
+                    ));
+                } else {
+                    shapes.push(Shape::line(positions, stroke));
+                }
+            }
+        }
+    }
+
+    fn initialize(&mut self, _x_range: RangeInclusive<f64>) {}
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn color(&self) -> Color32 {
+        self.color
+    }
+
+    fn highlight(&mut self) {
+        self.highlight = true;
+    }
+
+    fn highlighted(&self) -> bool {
+        self.highlight
+    }
+
+    fn allow_hover(&self) -> bool {
+        true
+    }
+
+    fn geometry(&self) -> PlotGeometry<'_> {
+        PlotGeometry::Points(&self.reduced.points)
+    }
+
+    fn bounds(&self) -> PlotBounds {
+        self.bounds
+    }
+
+    fn id(&self) -> Option<Id> {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn reduce(
+        points: &[[f64; 2]],
+        x_range: (f64, f64),
+        columns: usize,
+        gap_threshold: Option<f64>,
+    ) -> ReducedLine {
+        let mut reduced = ReducedLine::default();
+        reduced.reduce_into(points, x_range, columns, (gap_threshold, 0.0));
+        reduced
+    }
+
+    fn plot_points(points: &[[f64; 2]]) -> Vec<PlotPoint> {
+        points.iter().map(|&[x, y]| PlotPoint::new(x, y)).collect()
+    }
+
+    #[test]
+    fn test_keeps_all_points_below_points_per_column() {
+        let points = [[0.0, 1.0], [1.0, 2.0], [2.0, 3.0], [3.0, 4.0], [4.0, 5.0]];
+        let reduced = reduce(&points, (1.5, 2.5), 1, None);
+        // One point on either side of the range
+        assert_eq!(reduced.points, plot_points(&points[1..4]));
+        assert_eq!(reduced.segment_ends, [3]);
+    }
+
+    #[test]
+    fn test_keeps_first_last_min_max_of_each_column() {
+        let points: Vec<[f64; 2]> = (0..20)
+            .map(|x| [x as f64, if x % 10 == 3 { -1.0 } else { x as f64 }])
+            .collect();
+        let reduced = reduce(&points, (0.0, 19.0), 2, None);
+        assert_eq!(
+            reduced.points,
+            plot_points(&[
+                [0.0, 0.0],
+                [3.0, -1.0],
+                [9.0, 9.0],
+                [10.0, 10.0],
+                [13.0, -1.0],
+                [18.0, 18.0],
+                // The end of the range is in a column of its own
+                [19.0, 19.0]
+            ])
+        );
+        assert_eq!(reduced.segment_ends, [7]);
+    }
+
+    #[test]
+    fn test_breaks_segments_at_gaps() {
+        let points = [
+            [0.0, 1.0],
+            [1.0, 1.0],
+            [10.0, 2.0],
+            [11.0, 2.0],
+            [30.0, 3.0],
+        ];
+        let reduced = reduce(&points, (0.0, 30.0), 100, Some(5.0));
+        let segments: Vec<&[PlotPoint]> = reduced.segments().collect();
+        assert_eq!(
+            segments,
+            [
+                &plot_points(&points[..2])[..],
+                &plot_points(&points[2..4]),
+                &plot_points(&points[4..])
+            ]
+        );
+    }
+
+    #[test]
+    fn test_moves_points_by_x_offset() {
+        let points = [[0.0, 1.0], [1.0, 2.0], [2.0, 3.0], [3.0, 4.0], [4.0, 5.0]];
+        let mut reduced = ReducedLine::default();
+        // The range is of the moved points
+        reduced.reduce_into(&points, (101.5, 102.5), 1, (None, 100.0));
+        assert_eq!(
+            reduced.points,
+            plot_points(&[[101.0, 2.0], [102.0, 3.0], [103.0, 4.0]])
+        );
+    }
+
+    #[test]
+    fn test_cache_reuses_view() {
+        let points: Vec<[f64; 2]> = (0..100).map(|x| [x as f64, 1.0]).collect();
+        let cache = RenderCache::default();
+        assert_eq!(
+            cache.bounds(&points),
+            PlotBounds::from_min_max([0.0, 1.0], [99.0, 1.0])
+        );
+        let line = (LineKind::Raw, 0, &points[..]);
+        let first = cache.view(line, (10.0, 20.0), 1, (None, 0.0));
+        let second = cache.view(line, (10.0, 20.0), 1, (None, 0.0));
+        assert!(Arc::ptr_eq(&first, &second));
+        // Each kind of line has its own view
+        let min_line = cache.view(
+            (LineKind::Min, 1, &points[..50]),
+            (10.0, 20.0),
+            1,
+            (None, 0.0),
+        );
+        assert!(!Arc::ptr_eq(&first, &min_line));
+        assert!(Arc::ptr_eq(
+            &first,
+            &cache.view(line, (10.0, 20.0), 1, (None, 0.0))
+        ));
+        let other_view = cache.view(line, (10.0, 21.0), 1, (None, 0.0));
+        assert!(!Arc::ptr_eq(&first, &other_view));
+    }
+}
@@ -0,0 +1,519 @@
+use std::{f64::consts::TAU, fmt, sync::Arc};
+
+use rayon::prelude::*;
+use rustfft::{num_complex::Complex, Fft, FftPlanner};
+use serde::{Deserialize, Serialize};
+
+use crate::mipmap::median_interval;
+
+const NANOS_PER_SEC: f64 = 1_000_000_000.0;
+
+/// Segments shorter than this don't resolve any useful frequencies
+pub const MIN_SEGMENT_LEN: usize = 16;
+/// At most this many segments are averaged, if the range has more they are picked evenly across the range, which
+/// keeps the cost of a spectrum of a long range bounded while panning.
+const MAX_SEGMENTS: usize = 64;
+
+/// The window function applied to each segment before the FFT, trading frequency resolution for less leakage
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum WindowFunction {
+    /// No window, the best frequency resolution but the most leakage
+    Rectangular,
+    #[default]
+    Hann,
+    Hamming,
+    /// The least leakage but the widest peaks
+    Blackman,
+}
+
+impl WindowFunction {
+    pub const ALL: [Self; 4] = [Self::Rectangular, Self::Hann, Self::Hamming, Self::Blackman];
+
+    /// The periodic window coefficients of a segment of `len` samples
+    fn coefficients(self, len: usize) -> Vec<f64> {
+        (0..len)
+            .map(|n| {
+                let phase = TAU * n as f64 / len as f64;
+                match self {
+                    Self::Rectangular => 1.0,
+                    Self::Hann => 0.5 - 0.5 * phase.cos(),
+                    Self::Hamming => 0.54 - 0.46 * phase.cos(),
+                    Self::Blackman => 0.42 - 0.5 * phase.cos() + 0.08 * (2.0 * phase).cos(),
+                }
+            })
+            .collect()
+    }
+}
+
+impl fmt::Display for WindowFunction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Rectangular => write!(f, "Rectangular"),
+            Self::Hann => write!(f, "Hann"),
+            Self::Hamming => write!(f, "Hamming"),
+            Self::Blackman => write!(f, "Blackman"),
+        }
+    }
+}
+
+/// The one-sided power spectral density of a series, in units² per Hz
+#[derive(Debug, Clone, PartialEq)]
+pub struct Spectrum {
+    /// `[frequency (Hz), power spectral density]` from 0 Hz up to the Nyquist frequency
+    points: Vec<[f64; 2]>,
+    sample_rate: f64,
+    segment_len: usize,
+    segments: usize,
+}
+
+impl Spectrum {
+    /// `[frequency (Hz), power spectral density]` from 0 Hz up to the Nyquist frequency
+    pub fn points(&self) -> &[[f64; 2]] {
+        &self.points
+    }
+
+    /// The rate (Hz) the series was resampled at
+    pub fn sample_rate(&self) -> f64 {
+        self.sample_rate
+    }
+
+    /// The frequency resolution (Hz), i.e. the spacing of the frequency bins
+    pub fn resolution(&self) -> f64 {
+        self.sample_rate / self.segment_len as f64
+    }
+
+    /// The number of samples in each segment
+    pub fn segment_len(&self) -> usize {
+        self.segment_len
+    }
+
+    /// How many segments were averaged
+    pub fn segments(&self) -> usize {
+        self.segments
+    }
+
+    /// Returns up to `count` of the highest local maxima of the spectrum, excluding 0 Hz, highest first
+    pub fn peaks(&self, count: usize) -> Vec<[f64; 2]> {
+        let mut peaks: Vec<[f64; 2]> = self
+            .points
+            .windows(3)
+            .filter(|bins| bins[1][1] > bins[0][1] && bins[1][1] >= bins[2][1])
+            .map(|bins| bins[1])
+            .collect();
+        peaks.sort_unstable_by(|a, b| b[1].total_cmp(&a[1]));
+        peaks.truncate(count);
+        peaks
+    }
+}
+
+/// Computes the power spectral density of the `points` in the x range with Welch's method, i.e. the average
+/// periodogram of windowed segments of `segment_len` samples that overlap by half.
+///
+/// The x values are timestamps in nanoseconds. The points are resampled to uniform spacing at their median sample
+/// interval by linear interpolation, which also bridges any gaps. `segment_len` is rounded down to a power of two
+/// and shortened to fit the range.
+///
+/// Returns [`None`] if the range has too few samples for a segment of [`MIN_SEGMENT_LEN`] samples.
+pub fn power_spectral_density(
+    points: &[[f64; 2]],
+    (x_min, x_max): (f64, f64),
+    window: WindowFunction,
+    segment_len: usize,
+) -> Option<Spectrum> {
+    let start = points.partition_point(|p| p[0] < x_min);
+    let end = points.partition_point(|p| p[0] <= x_max);
+    let resampled = Resampled::new(points.get(start..end)?)?;
+    let segment_len = resampled.fit_segment_len(segment_len)?;
+
+    let hop = segment_len / 2;
+    let segments = ((resampled.sample_count - segment_len) / hop + 1).min(MAX_SEGMENTS);
+    let last_segment_start = resampled.sample_count - segment_len;
+    let mut periodogram = Periodogram::new(window, segment_len, resampled.sample_rate());
+    let mut power_sums = vec![0.0; segment_len / 2 + 1];
+    for segment in 0..segments {
+        let segment_start = if segments == 1 {
+            0
+        } else {
+            segment * last_segment_start / (segments - 1)
+        };
+        periodogram.accumulate(
+            &resampled.segment(segment_start, segment_len),
+            &mut power_sums,
+        );
+    }
+
+    let resolution = resampled.sample_rate() / segment_len as f64;
+    let points = power_sums
+        .into_iter()
+        .enumerate()
+        .map(|(bin, power_sum)| [bin as f64 * resolution, power_sum / segments as f64])
+        .collect();
+    Some(Spectrum {
+        points,
+        sample_rate: resampled.sample_rate(),
+        segment_len,
+        segments,
+    })
+}
+
+/// The power spectral density of a series over time, a column of frequency bins for each period of time
+#[derive(Debug, Clone, PartialEq)]
+pub struct Spectrogram {
+    x_range: (f64, f64),
+    columns: usize,
+    frequency_bins: usize,
+    resolution: f64,
+    /// The power of each frequency bin of each column, column after column. NaN in columns without samples.
+    power: Vec<f64>,
+}
+
+impl Spectrogram {
+    /// The x range (timestamps in nanoseconds) covered by the columns, without any offset of the plot
+    pub fn x_range(&self) -> (f64, f64) {
+        self.x_range
+    }
+
+    pub fn columns(&self) -> usize {
+        self.columns
+    }
+
+    /// The number of frequency bins of a column, from 0 Hz up to the Nyquist frequency
+    pub fn frequency_bins(&self) -> usize {
+        self.frequency_bins
+    }
+
+    /// The frequency resolution (Hz), i.e. the spacing of the frequency bins
+    pub fn resolution(&self) -> f64 {
+        self.resolution
+    }
+
+    /// The power spectral density of each frequency bin of a column, NaN if the column has no samples,
+    /// e.g. because it's in a gap
+    pub fn column(&self, column: usize) -> &[f64] {
+        &self.power[column * self.frequency_bins..(column + 1) * self.frequency_bins]
+    }
+
+    /// The lowest and highest positive power of all the columns, or [`None`] if there is none
+    pub fn power_range(&self) -> Option<(f64, f64)> {
+        self.power
+            .iter()
+            .filter(|power| **power > 0.0)
+            .fold(None, |range, &power| match range {
+                None => Some((power, power)),
+                Some((min, max)) => Some((power.min(min), power.max(max))),
+            })
+    }
+}
+
+/// Computes the [`Spectrogram`] of all the `points`, with up to `max_columns` columns that each hold the
+/// periodogram of the segment of `segment_len` samples centered on the column.
+///
+/// The points are resampled like in [`power_spectral_density`], but columns where less than a quarter of the segment
+/// are real samples, e.g. in a gap, have no power (NaN). The columns are computed in parallel.
+///
+/// Returns [`None`] if there are too few samples for a segment of [`MIN_SEGMENT_LEN`] samples.
+pub fn spectrogram(
+    points: &[[f64; 2]],
+    window: WindowFunction,
+    segment_len: usize,
+    max_columns: usize,
+) -> Option<Spectrogram> {
+    let resampled = Resampled::new(points)?;
+    let segment_len = resampled.fit_segment_len(segment_len)?;
+    let frequency_bins = segment_len / 2 + 1;
+    // Columns closer than half a segment would mostly show the same samples
+    let columns = (resampled.sample_count / (segment_len / 2))
+        .min(max_columns)
+        .max(1);
+    let column_width = resampled.duration() / columns as f64;
+    let last_segment_start = resampled.sample_count - segment_len;
+
+    let mut power = vec![f64::NAN; columns * frequency_bins];
+    power
+        .par_chunks_mut(frequency_bins)
+        .enumerate()
+        .for_each_init(
+            || Periodogram::new(window, segment_len, resampled.sample_rate()),
+            |periodogram, (column, column_power)| {
+                let center = (column as f64 + 0.5) * column_width / resampled.interval;
+                let segment_start = (center as usize)
+                    .saturating_sub(segment_len / 2)
+                    .min(last_segment_start);
+                let (segment_x_min, segment_x_max) = (
+                    resampled.x(segment_start),
+                    resampled.x(segment_start + segment_len - 1),
+                );
+                let real_samples = points.partition_point(|p| p[0] <= segment_x_max)
+                    - points.partition_point(|p| p[0] < segment_x_min);
+                if real_samples >= segment_len / 4 {
+                    column_power.fill(0.0);
+                    periodogram
+                        .accumulate(&resampled.segment(segment_start, segment_len), column_power);
+                }
+            },
+        );
+    Some(Spectrogram {
+        x_range: (resampled.first, resampled.x(resampled.sample_count - 1)),
+        columns,
+        frequency_bins,
+        resolution: resampled.sample_rate() / segment_len as f64,
+        power,
+    })
+}
+
+/// Sorted points viewed as if they were sampled uniformly at their median sample interval
+struct Resampled<'p> {
+    points: &'p [[f64; 2]],
+    first: f64,
+    interval: f64,
+    sample_count: usize,
+}
+
+impl<'p> Resampled<'p> {
+    fn new(points: &'p [[f64; 2]]) -> Option<Self> {
+        let (first, last) = (points.first()?[0], points.last()?[0]);
+        let interval = median_interval(points)?;
+        Some(Self {
+            points,
+            first,
+            interval,
+            sample_count: ((last - first) / interval) as usize + 1,
+        })
+    }
+
+    fn sample_rate(&self) -> f64 {
+        NANOS_PER_SEC / self.interval
+    }
+
+    fn duration(&self) -> f64 {
+        self.sample_count as f64 * self.interval
+    }
+
+    /// The x value of the sample at `idx`
+    fn x(&self, idx: usize) -> f64 {
+        self.first + idx as f64 * self.interval
+    }
+
+    /// `segment_len` rounded down to a power of two that fits the samples, [`None`] if it's shorter than
+    /// [`MIN_SEGMENT_LEN`]
+    fn fit_segment_len(&self, segment_len: usize) -> Option<usize> {
+        let segment_len = prev_power_of_two(segment_len.min(self.sample_count));
+        (segment_len >= MIN_SEGMENT_LEN).then_some(segment_len)
+    }
+
+    /// `len` samples from the sample at `start`
+    fn segment(&self, start: usize, len: usize) -> Vec<f64> {
+        resample(self.points, self.x(start), self.interval, len)
+    }
+}
+
+/// Computes the one-sided power spectral density of windowed segments of samples
+struct Periodogram {
+    fft: Arc<dyn Fft<f64>>,
+    window_coefficients: Vec<f64>,
+    buffer: Vec<Complex<f64>>,
+    /// Scales the squared magnitudes to power per Hz, compensating for the power lost to the window
+    scale: f64,
+}
+
+impl Periodogram {
+    fn new(window: WindowFunction, segment_len: usize, sample_rate: f64) -> Self {
+        let window_coefficients = window.coefficients(segment_len);
+        let window_power: f64 = window_coefficients.iter().map(|c| c * c).sum();
+        Self {
+            fft: FftPlanner::new().plan_fft_forward(segment_len),
+            window_coefficients,
+            buffer: vec![Complex::default(); segment_len],
+            scale: 1.0 / (sample_rate * window_power),
+        }
+    }
+
+    /// Adds the power spectral density of the `samples`, without their mean, to the bins of `power`, from 0 Hz up to
+    /// the Nyquist frequency
+    fn accumulate(&mut self, samples: &[f64], power: &mut [f64]) {
+        let mean = samples.iter().sum::<f64>() / samples.len() as f64;
+        for ((bin, sample), coefficient) in self
+            .buffer
+            .iter_mut()
+            .zip(samples)
+            .zip(&self.window_coefficients)
+        {
+            *bin = Complex::new((sample - mean) * coefficient, 0.0);
+        }
+        self.fft.process(&mut self.buffer);
+        let nyquist_bin = self.buffer.len() / 2;
+        for (idx, (sum, bin)) in power.iter_mut().zip(&self.buffer).enumerate() {
+            // Fold the power of the negative frequencies into the positive ones
+            let one_sided = if idx == 0 || idx == nyquist_bin {
+                1.0
+            } else {
+                2.0
+            };
+            *sum += bin.norm_sqr() * self.scale * one_sided;
+        }
+    }
+}
+
+/// Linearly interpolates `count` samples `interval` apart from `x_start` of the sorted `points`
+fn resample(points: &[[f64; 2]], x_start: f64, interval: f64, count: usize) -> Vec<f64> {
+    let mut idx = points
+        .partition_point(|p| p[0] <= x_start)
+        .saturating_sub(1);
+    (0..count)
+        .map(|n| {
+            let x = x_start + n as f64 * interval;
+            while idx + 1 < points.len() && points[idx + 1][0] <= x {
+                idx += 1;
+            }
+            match (points[idx], points.get(idx + 1)) {
+                ([x0, y0], Some(&[x1, y1])) if x1 > x0 => {
+                    y0 + (y1 - y0) * ((x - x0) / (x1 - x0)).clamp(0.0, 1.0)
+                }
+                ([_, y0], _) => y0,
+            }
+        })
+        .collect()
+}
+
+fn prev_power_of_two(n: usize) -> usize {
+    if n == 0 {
+        0
+    } else {
+        1 << n.ilog2()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use testresult::TestResult;
+
+    /// 1 kHz sampling
+    const INTERVAL_NS: f64 = 1_000_000.0;
+
+    fn sampled(count: usize, signal: impl Fn(f64) -> f64) -> Vec<[f64; 2]> {
+        (0..count)
+            .map(|n| {
+                let t = n as f64 * INTERVAL_NS;
+                [t, signal(t / NANOS_PER_SEC)]
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_psd_finds_sine_frequencies() -> TestResult {
+        let points = sampled(60_000, |t| {
+            3.0 + 2.0 * (TAU * 50.0 * t).sin() + 0.5 * (TAU * 120.0 * t).sin()
+        });
+        let spectrum =
+            power_spectral_density(&points, (f64::MIN, f64::MAX), WindowFunction::Hann, 1024)
+                .ok_or("expected a spectrum")?;
+
+        assert_eq!(spectrum.sample_rate(), 1000.0);
+        assert_eq!(spectrum.segment_len(), 1024);
+        assert_eq!(spectrum.segments(), MAX_SEGMENTS);
+        assert_eq!(spectrum.points().len(), 513);
+        let resolution = spectrum.resolution();
+        let peaks = spectrum.peaks(2);
+        assert!((peaks[0][0] - 50.0).abs() <= resolution, "{peaks:?}");
+        assert!((peaks[1][0] - 120.0).abs() <= resolution, "{peaks:?}");
+
+        // The mean is removed, and the total power is the variance of the sines (A²/2)
+        let total_power: f64 = spectrum.points().iter().map(|p| p[1]).sum::<f64>() * resolution;
+        assert!((total_power - (2.0 + 0.125)).abs() < 0.05, "{total_power}");
+        Ok(())
+    }
+
+    #[test]
+    fn test_psd_of_visible_range_with_jittered_samples() -> TestResult {
+        let mut points = sampled(4_000, |t| (TAU * 25.0 * t).sin());
+        for (n, point) in points.iter_mut().enumerate() {
+            point[0] += if n % 2 == 0 { 1_000.0 } else { -1_000.0 };
+        }
+        // Only the first second is in view
+        let spectrum = power_spectral_density(
+            &points,
+            (0.0, NANOS_PER_SEC),
+            WindowFunction::Blackman,
+            4096,
+        )
+        .ok_or("expected a spectrum")?;
+
+        assert_eq!(spectrum.segment_len(), 512);
+        assert_eq!(spectrum.segments(), 2);
+        let peak = spectrum.peaks(1)[0];
+        assert!((peak[0] - 25.0).abs() <= spectrum.resolution(), "{peak:?}");
+        Ok(())
+    }
+
+    #[test]
+    fn test_no_psd_of_too_few_samples() {
+        let points = sampled(100, |t| t);
+        assert_eq!(
+            power_spectral_density(&points, (0.0, 10.0 * INTERVAL_NS), WindowFunction::Hann, 64),
+            None
+        );
+        assert_eq!(
+            power_spectral_density(&[], (0.0, 1.0), WindowFunction::Hann, 64),
+            None
+        );
+    }
+
+    #[test]
+    fn test_spectrogram_follows_frequency_over_time() -> TestResult {
+        // 10 seconds of 100 Hz, then 10 seconds without samples, then 10 seconds of 300 Hz
+        let mut points = sampled(10_000, |t| (TAU * 100.0 * t).sin());
+        points.extend(
+            sampled(30_000, |t| (TAU * 300.0 * t).sin())
+                .into_iter()
+                .skip(20_000),
+        );
+        let spectrogram =
+            spectrogram(&points, WindowFunction::Hann, 256, 30).ok_or("expected a spectrogram")?;
+
+        assert_eq!(spectrogram.columns(), 30);
+        assert_eq!(spectrogram.frequency_bins(), 129);
+        assert_eq!(spectrogram.x_range(), (0.0, 29_999.0 * INTERVAL_NS));
+        let peak_frequency = |column: usize| {
+            let power = spectrogram.column(column);
+            let peak_bin = (0..power.len())
+                .max_by(|a, b| power[*a].total_cmp(&power[*b]))
+                .unwrap_or_default();
+            peak_bin as f64 * spectrogram.resolution()
+        };
+        let resolution = spectrogram.resolution();
+        assert!((peak_frequency(2) - 100.0).abs() <= resolution);
+        assert!(spectrogram.column(15).iter().all(|power| power.is_nan()));
+        assert!((peak_frequency(27) - 300.0).abs() <= resolution);
+        let (min, max) = spectrogram.power_range().ok_or("expected power")?;
+        assert!(min < max);
+        Ok(())
+    }
+
+    #[test]
+    fn test_window_coefficients() {
+        let assert_coefficients = |window: WindowFunction, expected: &[f64]| {
+            let coefficients = window.coefficients(expected.len());
+            for (coefficient, expected) in coefficients.iter().zip(expected) {
+                assert!(
+                    (coefficient - expected).abs() < 1e-12,
+                    "{window}: {coefficients:?}"
+                );
+            }
+        };
+        assert_coefficients(WindowFunction::Rectangular, &[1.0, 1.0]);
+        assert_coefficients(WindowFunction::Hann, &[0.0, 0.5, 1.0, 0.5]);
+        assert_coefficients(WindowFunction::Hamming, &[0.08, 0.54, 1.0, 0.54]);
+        assert_coefficients(WindowFunction::Blackman, &[0.0, 0.34, 1.0, 0.34]);
+    }
+
+    #[test]
+    fn test_resample_interpolates_linearly() {
+        let points = [[0.0, 0.0], [10.0, 10.0], [30.0, 0.0]];
+        assert_eq!(
+            resample(&points, 0.0, 5.0, 8),
+            [0.0, 5.0, 10.0, 7.5, 5.0, 2.5, 0.0, 0.0]
+        );
+    }
+}
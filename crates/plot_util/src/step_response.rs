@@ -0,0 +1,287 @@
+const NANOS_PER_SEC: f64 = 1_000_000_000.0;
+/// The steady-state error is the mean error over this last fraction of a step
+const STEADY_STATE_FRACTION: f64 = 0.2;
+
+/// How a response followed a step of its setpoint, such as the RPM of a motor after its setpoint changed.
+///
+/// The metrics are relative to the setpoint before and after the step, times are in seconds after the step.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct StepResponse {
+    /// When the setpoint changed, in nanoseconds like the samples
+    start: f64,
+    /// How long the setpoint was held until it changed again or the samples ended, in seconds
+    duration: f64,
+    from: f64,
+    to: f64,
+    rise_time: Option<f64>,
+    overshoot_percent: f64,
+    settling_time: Option<f64>,
+    steady_state_error: Option<f64>,
+}
+
+impl StepResponse {
+    /// When the setpoint changed, in nanoseconds
+    pub fn start(&self) -> f64 {
+        self.start
+    }
+
+    /// How long the setpoint was held, in seconds
+    pub fn duration(&self) -> f64 {
+        self.duration
+    }
+
+    /// The setpoint before the step
+    pub fn from(&self) -> f64 {
+        self.from
+    }
+
+    /// The setpoint after the step
+    pub fn to(&self) -> f64 {
+        self.to
+    }
+
+    /// Seconds from the response reaching 10% of the step until it reached 90%, [`None`] if it never reached 90%
+    pub fn rise_time(&self) -> Option<f64> {
+        self.rise_time
+    }
+
+    /// How far the response went past the new setpoint, in percent of the step
+    pub fn overshoot_percent(&self) -> f64 {
+        self.overshoot_percent
+    }
+
+    /// Seconds from the step until the response stayed within the settling band around the new setpoint,
+    /// [`None`] if it didn't settle before the setpoint changed again
+    pub fn settling_time(&self) -> Option<f64> {
+        self.settling_time
+    }
+
+    /// The mean of the setpoint minus the response over the last part of the step, [`None`] without samples
+    pub fn steady_state_error(&self) -> Option<f64> {
+        self.steady_state_error
+    }
+}
+
+/// The mean metrics of a number of steps, to compare how well different tunings follow their setpoint
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct StepSummary {
+    pub steps: usize,
+    pub mean_rise_time: Option<f64>,
+    pub mean_overshoot_percent: Option<f64>,
+    pub mean_settling_time: Option<f64>,
+    /// The mean of the absolute steady-state errors
+    pub mean_abs_steady_state_error: Option<f64>,
+}
+
+impl StepSummary {
+    /// Summarizes the `steps`, each mean is over the steps that have the metric
+    pub fn of(steps: &[StepResponse]) -> Self {
+        fn mean(values: impl Iterator<Item = f64>) -> Option<f64> {
+            let (sum, count) = values.fold((0.0, 0), |(sum, count), v| (sum + v, count + 1));
+            (count > 0).then(|| sum / count as f64)
+        }
+        Self {
+            steps: steps.len(),
+            mean_rise_time: mean(steps.iter().filter_map(StepResponse::rise_time)),
+            mean_overshoot_percent: mean(steps.iter().map(StepResponse::overshoot_percent)),
+            mean_settling_time: mean(steps.iter().filter_map(StepResponse::settling_time)),
+            mean_abs_steady_state_error: mean(
+                steps
+                    .iter()
+                    .filter_map(StepResponse::steady_state_error)
+                    .map(f64::abs),
+            ),
+        }
+    }
+}
+
+/// Finds the steps of the piecewise constant `setpoint` and measures how the `response` followed each of them.
+///
+/// Both are sorted points with timestamps in nanoseconds. A step is a change of at least `min_step` from the setpoint
+/// of the previous step, and it lasts until the next step or the last response sample. The response has settled when
+/// it stays within `settling_band` (a fraction, e.g. 0.05 for 5%) of the step size around the new setpoint.
+pub fn analyze_steps(
+    setpoint: &[[f64; 2]],
+    response: &[[f64; 2]],
+    min_step: f64,
+    settling_band: f64,
+) -> Vec<StepResponse> {
+    let Some(&[response_end, _]) = response.last() else {
+        return vec![];
+    };
+    let min_step = min_step.max(f64::EPSILON);
+    let mut changes: Vec<[f64; 3]> = vec![];
+    let mut held: Option<f64> = None;
+    for &[t, value] in setpoint {
+        match held {
+            Some(previous) if (value - previous).abs() < min_step => (),
+            Some(previous) => {
+                changes.push([t, previous, value]);
+                held = Some(value);
+            }
+            None => held = Some(value),
+        }
+    }
+    changes
+        .iter()
+        .enumerate()
+        .filter_map(|(idx, &[start, from, to])| {
+            let next_start = changes.get(idx + 1).map(|next| next[0]);
+            let end = next_start.unwrap_or(response_end);
+            let first = response.partition_point(|&[t, _]| t < start);
+            let last = next_start.map_or(response.len(), |next_start| {
+                response.partition_point(|&[t, _]| t < next_start)
+            });
+            let samples = &response[first..last.max(first)];
+            (!samples.is_empty())
+                .then(|| measure_step(samples, [start, end], from, to, settling_band))
+        })
+        .collect()
+}
+
+fn measure_step(
+    samples: &[[f64; 2]],
+    [start, end]: [f64; 2],
+    from: f64,
+    to: f64,
+    settling_band: f64,
+) -> StepResponse {
+    let step = to - from;
+    let direction = step.signum();
+    // The fraction of the step the response has covered
+    let progress = |y: f64| (y - from) / step;
+    let seconds_after_start = |t: f64| (t - start) / NANOS_PER_SEC;
+
+    let reached = |fraction: f64| {
+        samples
+            .iter()
+            .find(|&&[_, y]| progress(y) >= fraction)
+            .map(|&[t, _]| t)
+    };
+    let rise_time = reached(0.1)
+        .zip(reached(0.9))
+        .map(|(t10, t90)| (t90 - t10) / NANOS_PER_SEC);
+
+    let overshoot_percent = samples
+        .iter()
+        .map(|&[_, y]| (y - to) * direction / step.abs() * 100.0)
+        .fold(0.0, f64::max);
+
+    let band = settling_band * step.abs();
+    let settling_time = match samples.iter().rposition(|&[_, y]| (y - to).abs() > band) {
+        None => Some(0.0),
+        Some(outside) => samples
+            .get(outside + 1)
+            .map(|&[t, _]| seconds_after_start(t)),
+    };
+
+    let steady_state_from = end - (end - start) * STEADY_STATE_FRACTION;
+    let (error_sum, count) = samples
+        .iter()
+        .filter(|&&[t, _]| t >= steady_state_from)
+        .fold((0.0, 0), |(sum, count), &[_, y]| (sum + to - y, count + 1));
+
+    StepResponse {
+        start,
+        duration: seconds_after_start(end),
+        from,
+        to,
+        rise_time,
+        overshoot_percent,
+        settling_time,
+        steady_state_error: (count > 0).then(|| error_sum / count as f64),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    /// Samples every 10 ms of a second order response with 20% overshoot, stepping from 0 to 1000 at 1 s and
+    /// back to 0 at 6 s
+    fn underdamped_response() -> (Vec<[f64; 2]>, Vec<[f64; 2]>) {
+        let zeta: f64 = 0.456;
+        let omega: f64 = 10.0;
+        let omega_d = omega * (1.0 - zeta * zeta).sqrt();
+        let phi = zeta.acos();
+        let unit_step = |t: f64| {
+            if t < 0.0 {
+                0.0
+            } else {
+                1.0 - (-zeta * omega * t).exp() / (1.0 - zeta * zeta).sqrt()
+                    * (omega_d * t + phi).sin()
+            }
+        };
+        let mut setpoint = vec![];
+        let mut response = vec![];
+        for n in 0..1000 {
+            let t = n as f64 * 0.01;
+            let x = t * NANOS_PER_SEC;
+            let target = if (1.0..6.0).contains(&t) { 1000.0 } else { 0.0 };
+            setpoint.push([x, target]);
+            response.push([x, 1000.0 * (unit_step(t - 1.0) - unit_step(t - 6.0))]);
+        }
+        (setpoint, response)
+    }
+
+    #[test]
+    fn test_detects_steps_and_measures_underdamped_response() {
+        let (setpoint, response) = underdamped_response();
+        let steps = analyze_steps(&setpoint, &response, 10.0, 0.05);
+
+        assert_eq!(steps.len(), 2);
+        let up = steps[0];
+        assert_eq!((up.start(), up.from(), up.to()), (1e9, 0.0, 1000.0));
+        assert!((up.duration() - 5.0).abs() < 1e-9);
+        assert!((up.overshoot_percent() - 20.0).abs() < 0.5, "{up:?}");
+        // The analytic 10-90% rise time of this response is about 0.15 s
+        let rise_time = up.rise_time().expect("no rise time");
+        assert!((rise_time - 0.15).abs() < 0.03, "{rise_time}");
+        // and the 5% settling time about 3 / (zeta * omega)
+        let settling_time = up.settling_time().expect("didn't settle");
+        assert!((0.4..0.7).contains(&settling_time), "{settling_time}");
+        assert!(up.steady_state_error().expect("no samples").abs() < 1.0);
+
+        let down = steps[1];
+        assert_eq!((down.from(), down.to()), (1000.0, 0.0));
+        assert!((down.overshoot_percent() - 20.0).abs() < 0.5, "{down:?}");
+    }
+
+    #[test]
+    fn test_response_that_never_reaches_the_setpoint() {
+        let setpoint = [[0.0, 0.0], [1e9, 100.0], [2e9, 100.0]];
+        let response: Vec<[f64; 2]> = (0..=20)
+            .map(|n| [n as f64 * 1e8, if n >= 10 { 50.0 } else { 0.0 }])
+            .collect();
+        let steps = analyze_steps(&setpoint, &response, 1.0, 0.02);
+
+        assert_eq!(steps.len(), 1);
+        let step = steps[0];
+        assert_eq!(step.rise_time(), None);
+        assert_eq!(step.settling_time(), None);
+        assert_eq!(step.overshoot_percent(), 0.0);
+        assert_eq!(step.steady_state_error(), Some(50.0));
+    }
+
+    #[test]
+    fn test_ignores_setpoint_changes_smaller_than_min_step() {
+        let setpoint = [[0.0, 0.0], [1e9, 0.5], [2e9, 10.0]];
+        let response = [[0.0, 0.0], [3e9, 10.0]];
+        let steps = analyze_steps(&setpoint, &response, 1.0, 0.05);
+        assert_eq!(steps.len(), 1);
+        assert_eq!((steps[0].from(), steps[0].to()), (0.0, 10.0));
+        assert!(analyze_steps(&setpoint, &[], 1.0, 0.05).is_empty());
+    }
+
+    #[test]
+    fn test_summary_means() {
+        let (setpoint, response) = underdamped_response();
+        let steps = analyze_steps(&setpoint, &response, 10.0, 0.05);
+        let summary = StepSummary::of(&steps);
+        assert_eq!(summary.steps, 2);
+        let mean_overshoot = summary.mean_overshoot_percent.expect("no steps");
+        assert!((mean_overshoot - 20.0).abs() < 0.5);
+        assert_eq!(StepSummary::of(&[]).mean_rise_time, None);
+    }
+}
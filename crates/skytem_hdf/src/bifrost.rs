@@ -1,29 +1,48 @@
 use std::{io, path::Path};
 
-use chrono::{DateTime, Datelike, NaiveDate, NaiveDateTime, NaiveTime, Utc};
+use chrono::{DateTime, Utc};
 
 use hdf5::Dataset;
 use log_if::prelude::*;
-use num_traits::ToPrimitive;
 use serde::{Deserialize, Serialize};
-use stream_descriptor::StreamDescriptor;
 
-use crate::util::{read_any_attribute_to_string, read_string_attribute};
+use crate::util::read_string_attribute;
 
+#[cfg(test)]
+mod fixture;
+mod stream;
 mod stream_descriptor;
+mod waveform;
+
+pub use stream::BifrostStream;
+pub use waveform::{PeriodStats, Waveform, WaveformChannel};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct BifrostLoopCurrent {
-    // It does not actually contain a timestamp so we just add 1. january current year to make it slightly more convenient
+    // Read from the timestamp stream if there is one, otherwise 1. january current year to make it slightly more convenient
     starting_timestamp_utc: DateTime<Utc>,
     dataset_description: String,
     raw_plots: Vec<RawPlot>,
     metadata: Vec<(String, String)>,
+    waveform: Option<Waveform>,
 }
 
 impl BifrostLoopCurrent {
     pub const DATASET_NAME: &str = "hm_current";
     pub const DATASET_DIMENSIONS: usize = 3;
+    /// Metadata key that is `"true"` if the timestamps are generated because the file has no usable timestamp stream
+    pub const SYNTHETIC_TIME_KEY: &str = BifrostStream::SYNTHETIC_TIME_KEY;
+    const POLARITY_NAMES: [&str; 2] = ["+ Polarity", "- Polarity"];
+
+    /// Returns true if the timestamps are generated rather than read from a timestamp stream
+    pub fn is_time_synthetic(&self) -> bool {
+        stream::is_time_synthetic(&self.metadata)
+    }
+
+    /// The samples of each period of each polarity
+    pub fn waveform(&self) -> Option<&Waveform> {
+        self.waveform.as_ref()
+    }
 }
 
 impl BifrostLoopCurrent {
@@ -80,109 +99,71 @@ impl BifrostLoopCurrent {
     }
 
     pub fn from_path<P: AsRef<Path>>(path: P) -> io::Result<Self> {
-        let current_dataset = Self::open_bifrost_current_dataset(path)?;
+        let current_dataset = Self::open_bifrost_current_dataset(&path)?;
 
         let dataset_description = read_string_attribute(&current_dataset.attr("description")?)?;
-        let stream_descriptor_toml_str =
-            read_string_attribute(&current_dataset.attr("stream_descriptor")?)?;
-        let Ok(stream_descriptor): Result<StreamDescriptor, toml::de::Error> =
-            toml::from_str(&stream_descriptor_toml_str)
-        else {
+        let polarities = current_dataset.shape()[Self::DATASET_DIMENSIONS - 1];
+        if polarities != Self::POLARITY_NAMES.len() {
             return Err(io::Error::new(
                 io::ErrorKind::InvalidData,
                 format!(
-                    "Failed decoding 'stream_descriptor' string as TOML from stream_descriptor: {stream_descriptor_toml_str}"
-                    )));
-        };
-
-        for a in current_dataset.attr_names()? {
-            let attr = current_dataset.attr(&a)?;
-            let attr_val_as_string = read_any_attribute_to_string(&attr)?;
-            eprintln!("Attr: {attr_val_as_string}");
+                    "Expected {} polarities in dataset {}, got {polarities}",
+                    Self::POLARITY_NAMES.len(),
+                    Self::DATASET_NAME,
+                ),
+            ));
         }
-
-        let data_3: ndarray::Array3<f32> = current_dataset.read()?;
-
-        let (gps_timestamps, samples_per_ts, polarities) = data_3.dim();
-        log::info!("Got bifrost current dataset with: GPS timestamps: {gps_timestamps}, samples per timestamp: {samples_per_ts}, polarities: {polarities}");
-        let mut metadata = vec![
-            ("Dataset Description".into(), dataset_description.clone()),
-            ("GPS Timestamps".into(), gps_timestamps.to_string()),
-            ("Samples per timestamp".into(), samples_per_ts.to_string()),
-            ("Polarities".into(), polarities.to_string()),
-        ];
-        metadata.extend_from_slice(&stream_descriptor.to_metadata());
-        // There's no timestamp (it has to be correlated with GPS data) so we just set the time to
-        // 1. january current year, hopefully it will be obvious to the user that it is an auto generated timestamp and not the real one
-        let now = chrono::offset::Local::now();
-        let first_january_this_year =
-            NaiveDate::from_ymd_opt(now.year(), 1, 1).expect("Invalid date");
-        let first_january_this_year = NaiveDateTime::new(
-            first_january_this_year,
-            NaiveTime::from_hms_opt(0, 0, 0).expect("Invalid time"),
-        )
-        .and_utc();
-        let starting_timestamp_ns = first_january_this_year
-            .timestamp_nanos_opt()
-            .expect("timestamp as nanoseconds out of range")
-            .to_f64()
-            .expect("Failed converting timestamp to f64");
-
-        let mut polarity0_currents: Vec<[f64; 2]> = Vec::new();
-        let mut polarity1_currents: Vec<[f64; 2]> = Vec::new();
-
-        let nanosec_multiplier = 1_000_000_000.0;
-        // Assumes one timestamp per second
-        let sample_step_size_approx: f64 = 1.0 * nanosec_multiplier
-            / (samples_per_ts
-                .to_f64()
-                .expect("Failed converting usize to f64"));
-        let mut timestamp_idx = 0;
-        let mut sample_idx = 0;
-
-        for d in data_3.rows() {
-            let offset_from_start = (timestamp_idx as f64) * nanosec_multiplier
-                + (sample_idx as f64) * sample_step_size_approx;
-
-            let offset_ts = starting_timestamp_ns + offset_from_start;
-
-            sample_idx += 1;
-            if sample_idx == samples_per_ts {
-                sample_idx = 0;
-                timestamp_idx += 1;
-            }
-            if d.len() != 2 {
-                log::error!(
-                    "Expected bifrost hm_current row length of 2, got: {}",
-                    d.len()
-                );
-                continue;
-            }
-
-            polarity0_currents.push([offset_ts, d[0].into()]);
-            polarity1_currents.push([offset_ts, d[1].into()]);
+        let BifrostStream {
+            starting_timestamp_utc,
+            raw_plots,
+            mut metadata,
+            waveform,
+            ..
+        } = BifrostStream::from_dataset_with_channel_names(
+            path.as_ref(),
+            &current_dataset,
+            Some(&Self::POLARITY_NAMES),
+        )?;
+        if let Some((key, _)) = metadata
+            .iter_mut()
+            .find(|(key, _)| key == BifrostStream::SELECTOR_VALUES_KEY)
+        {
+            "Polarities".clone_into(key);
         }
 
-        let plot_polarity0 = RawPlot::new(
-            "+ Polarity [A]".to_owned(),
-            polarity0_currents.clone(),
-            ExpectedPlotRange::OneToOneHundred,
-        );
-        let plot_polarity1 = RawPlot::new(
-            "- Polarity [A]".to_owned(),
-            polarity1_currents.clone(),
-            ExpectedPlotRange::OneToOneHundred,
-        );
-
         Ok(Self {
-            starting_timestamp_utc: first_january_this_year,
+            starting_timestamp_utc,
             dataset_description,
-            raw_plots: vec![plot_polarity0, plot_polarity1],
+            raw_plots,
             metadata,
+            waveform,
         })
     }
 }
 
+/// Reads the streams of the Bifrost file at `path`: The TX loop current if the file has a valid `hm_current` dataset,
+/// and every other dataset with a stream descriptor as a [`BifrostStream`].
+///
+/// # Errors
+///
+/// If the file can't be opened
+pub fn read_streams<P: AsRef<Path>>(
+    path: P,
+) -> io::Result<(Option<BifrostLoopCurrent>, Vec<BifrostStream>)> {
+    let path = path.as_ref();
+    let loop_current = BifrostLoopCurrent::from_path(path)
+        .inspect_err(|e| log::debug!("No loop current in {}: {e}", path.display()))
+        .ok();
+    // An `hm_current` dataset that isn't a valid loop current is still decoded as a stream
+    let excluded: &[&str] = if loop_current.is_some() {
+        &[BifrostLoopCurrent::DATASET_NAME]
+    } else {
+        &[]
+    };
+    let streams = BifrostStream::read_all_except(path, excluded)?;
+    Ok((loop_current, streams))
+}
+
 impl Plotable for BifrostLoopCurrent {
     fn raw_plots(&self) -> &[RawPlot] {
         &self.raw_plots
@@ -210,6 +191,7 @@ mod tests {
     use testresult::TestResult;
 
     use super::*;
+    use crate::util::first_january_this_year;
 
     const TEST_DATA: &str = "../../test_data/hdf5/bifrost_current/20240930_100137_bifrost.h5";
 
@@ -236,12 +218,62 @@ mod tests {
 
         let plot_polarity1 = bifrost_currents.raw_plots()[1].points();
 
-        let expected_first_value = 4.434181213378906;
+        // The raw values scaled by the `cal_scale` of the stream
+        let cal_scale = 0.005;
+        let expected_first_value = 4.434181213378906 * cal_scale;
         assert_eq!(plot_polarity1.first().unwrap()[1], expected_first_value);
 
-        let expected_last_value = 17.78993797302246;
+        let expected_last_value = 17.78993797302246 * cal_scale;
         assert_eq!(plot_polarity1.last().unwrap()[1], expected_last_value);
 
+        let waveform = bifrost_currents
+            .waveform()
+            .expect("Expected a waveform but contained none");
+        assert_eq!(waveform.channels()[1].name(), "- Polarity");
+        assert_eq!(waveform.period(1, 0)[0], expected_first_value);
+
+        // The test data has no timestamp stream
+        assert!(bifrost_currents.is_time_synthetic());
+        assert_eq!(
+            bifrost_currents.first_timestamp(),
+            first_january_this_year()
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_read_streams_reads_other_streams_next_to_the_loop_current() -> TestResult {
+        let dir = tempfile::tempdir()?;
+        let path = fixture::copy_test_data(dir.path(), "bifrost.h5")?;
+        let temperatures = ndarray::array![[20.0, 30.0], [21.0, 31.0], [22.0, 32.0]];
+        fixture::write_stream(
+            &hdf5::File::open_rw(&path)?,
+            "temperature",
+            &temperatures,
+            "",
+        )?;
+
+        let (loop_current, streams) = read_streams(&path)?;
+
+        let loop_current = loop_current.expect("Expected the loop current");
+        assert_eq!(loop_current.descriptive_name(), "TX Loop Current");
+        // The loop current is not loaded again as a generic stream
+        assert_eq!(streams.len(), 1);
+        let temperature = &streams[0];
+        assert_eq!(temperature.descriptive_name(), "temperature");
+        let names: Vec<&str> = temperature.raw_plots().iter().map(RawPlot::name).collect();
+        assert_eq!(names, ["temperature 0 [C]", "temperature 1 [C]"]);
+        let [cal_scale, cal_offset] = fixture::CALIBRATION;
+        let values: Vec<f64> = temperature.raw_plots()[1]
+            .points()
+            .iter()
+            .map(|[_, value]| *value)
+            .collect();
+        assert_eq!(
+            values,
+            [30.0, 31.0, 32.0].map(|raw| raw * cal_scale + cal_offset)
+        );
         Ok(())
     }
 }
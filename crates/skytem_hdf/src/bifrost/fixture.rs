@@ -0,0 +1,81 @@
+//! Bifrost files for tests, made by adding streams to a copy of the test data
+
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use hdf5::File;
+
+use super::BifrostStream;
+use crate::util::write_string_attribute;
+
+pub(crate) const TEST_DATA: &str =
+    "../../test_data/hdf5/bifrost_current/20240930_100137_bifrost.h5";
+/// The calibration of the streams written by [`write_stream`], `[cal_scale, cal_offset]`
+pub(crate) const CALIBRATION: [f64; 2] = [0.5, 1.0];
+
+/// Copies the test data, which has an `hm_current` stream without timestamps, to `file_name` in `dir`
+pub(crate) fn copy_test_data(dir: &Path, file_name: &str) -> io::Result<PathBuf> {
+    let path = dir.join(file_name);
+    fs::copy(TEST_DATA, &path)?;
+    Ok(path)
+}
+
+/// Writes a stream of `values` with a row per timestamp and a column per selector value to `file`
+pub(crate) fn write_stream(
+    file: &File,
+    stream_id: &str,
+    values: &ndarray::Array2<f64>,
+    timestamp_stream: &str,
+) -> hdf5::Result<()> {
+    let [cal_scale, cal_offset] = CALIBRATION;
+    let selector_values: Vec<usize> = (0..values.ncols()).collect();
+    let descriptor = format!(
+        r#"stream_id = "{stream_id}"
+chunk_size = [10, {selectors}]
+description = "{stream_id}"
+unit = "C"
+data_type = "numpy.float64"
+timestamp_stream = "{timestamp_stream}"
+
+[axes.0]
+classname = "Primary"
+
+[axes.1]
+classname = "Selector"
+values = {selector_values:?}
+
+[converter]
+classname = "Unity"
+
+[aux_metadata]
+cal_offset = {cal_offset:?}
+cal_scale = {cal_scale:?}
+"#,
+        selectors = values.ncols(),
+    );
+    let dataset = file
+        .new_dataset_builder()
+        .with_data(values)
+        .create(stream_id)?;
+    write_string_attribute(
+        &dataset,
+        BifrostStream::STREAM_DESCRIPTOR_ATTRIBUTE,
+        &descriptor,
+    )
+}
+
+/// Writes a dataset of `timestamps` in `unit` to `file`, the way a timestamp stream is stored
+pub(crate) fn write_timestamps(
+    file: &File,
+    name: &str,
+    timestamps: &[f64],
+    unit: &str,
+) -> hdf5::Result<()> {
+    let dataset = file
+        .new_dataset_builder()
+        .with_data(timestamps)
+        .create(name)?;
+    write_string_attribute(&dataset, "unit", unit)
+}
@@ -0,0 +1,811 @@
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use chrono::{DateTime, Utc};
+use hdf5::Dataset;
+use log_if::prelude::*;
+use num_traits::ToPrimitive;
+use serde::{Deserialize, Serialize};
+
+use super::stream_descriptor::{Axis, AxisClass, StreamDescriptor};
+use super::waveform::{PeriodStats, Waveform};
+use crate::generic::{expected_range, TimestampUnit};
+use crate::util::{first_january_this_year, read_string_attribute};
+
+/// Any dataset in a Bifrost file with a `stream_descriptor` attribute, decoded according to the descriptor.
+///
+/// The first axis of a stream is the [`AxisClass::Primary`] time axis, with one entry per timestamp in the timestamp stream.
+/// The last [`AxisClass::Selector`] axis selects the plot, so there's one plot per selector value. Any selector axes in between
+/// are samples within a timestamp period, which are spread evenly until the next timestamp.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BifrostStream {
+    pub(crate) starting_timestamp_utc: DateTime<Utc>,
+    pub(crate) description: String,
+    pub(crate) raw_plots: Vec<RawPlot>,
+    pub(crate) metadata: Vec<(String, String)>,
+    /// The samples of each period, if the stream is sampled more than once per timestamp
+    pub(crate) waveform: Option<Waveform>,
+}
+
+impl BifrostStream {
+    pub const STREAM_DESCRIPTOR_ATTRIBUTE: &str = "stream_descriptor";
+    /// Metadata key that is `"true"` if the timestamps are generated because the file has no usable timestamp stream
+    pub const SYNTHETIC_TIME_KEY: &str = "Synthetic Time";
+    /// Metadata key of the number of values of the selector axis, i.e. the number of plots
+    pub(crate) const SELECTOR_VALUES_KEY: &str = "Selector Values";
+
+    /// Decodes all streams in the root group of the HDF5 file at `path`, except streams that are the timestamps of other streams.
+    ///
+    /// Streams that fail to decode are skipped with a warning.
+    pub fn read_all<P: AsRef<Path>>(path: P) -> io::Result<Vec<Self>> {
+        Self::read_all_except(path, &[])
+    }
+
+    /// Decodes the streams like [`Self::read_all`], except the datasets named in `excluded`, e.g. because they
+    /// are already loaded as a specific format
+    pub fn read_all_except<P: AsRef<Path>>(path: P, excluded: &[&str]) -> io::Result<Vec<Self>> {
+        let path = path.as_ref();
+        let hdf5_file = hdf5::File::open(path)?;
+        let mut described_datasets = vec![];
+        for dataset in hdf5_file.datasets()? {
+            if let Some(descriptor) = read_stream_descriptor(&dataset)? {
+                described_datasets.push((dataset, descriptor));
+            }
+        }
+        let timestamp_streams: Vec<String> = described_datasets
+            .iter()
+            .filter_map(|(_, descriptor)| descriptor.timestamp_stream())
+            .map(|name| name.trim_start_matches('/').to_owned())
+            .collect();
+
+        let mut streams = vec![];
+        for (dataset, descriptor) in described_datasets {
+            let name = dataset.name();
+            let unprefixed_name = name.trim_start_matches('/');
+            if timestamp_streams.iter().any(|ts| ts == unprefixed_name)
+                || excluded.contains(&unprefixed_name)
+            {
+                continue;
+            }
+            match Self::decode(path, &dataset, &descriptor, None) {
+                Ok(stream) => streams.push(stream),
+                Err(e) => log::warn!("Skipping Bifrost stream {name}: {e}"),
+            }
+        }
+        Ok(streams)
+    }
+
+    /// Decodes the `dataset` in the HDF5 file at `path`
+    ///
+    /// # Errors
+    ///
+    /// If the dataset has no stream descriptor, or the data doesn't have the shape of its axes
+    pub fn from_dataset(path: &Path, dataset: &Dataset) -> io::Result<Self> {
+        Self::from_dataset_with_channel_names(path, dataset, None)
+    }
+
+    /// Decodes the `dataset` like [`Self::from_dataset`], naming the channel of each selector value from `channel_names`
+    pub(crate) fn from_dataset_with_channel_names(
+        path: &Path,
+        dataset: &Dataset,
+        channel_names: Option<&[&str]>,
+    ) -> io::Result<Self> {
+        let descriptor = read_stream_descriptor(dataset)?.ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!(
+                    "Dataset {} has no '{}' attribute",
+                    dataset.name(),
+                    Self::STREAM_DESCRIPTOR_ATTRIBUTE
+                ),
+            )
+        })?;
+        Self::decode(path, dataset, &descriptor, channel_names)
+    }
+
+    fn decode(
+        path: &Path,
+        dataset: &Dataset,
+        descriptor: &StreamDescriptor,
+        channel_names: Option<&[&str]>,
+    ) -> io::Result<Self> {
+        let shape = dataset.shape();
+        let axes = descriptor.ordered_axes()?;
+        let invalid = |msg: String| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("Stream {}: {msg}", dataset.name()),
+            )
+        };
+        validate_axes(&axes, &shape).map_err(invalid)?;
+
+        let primary_len = shape[0];
+        let (samples_per_ts, selector_axis, selector_len): (usize, _, usize) = match shape.len() {
+            1 => (1, None, 1),
+            ndim => (
+                shape[1..ndim - 1].iter().product(),
+                Some(axes[ndim - 1]),
+                shape[ndim - 1],
+            ),
+        };
+        let data: ndarray::Array2<f64> = dataset
+            .read_dyn::<f64>()?
+            .into_shape_with_order((primary_len * samples_per_ts, selector_len))
+            .map_err(|e| invalid(e.to_string()))?;
+
+        let description = if descriptor.description().is_empty() {
+            dataset.name()
+        } else {
+            descriptor.description().to_owned()
+        };
+        log::info!("Got bifrost stream '{description}' with: GPS timestamps: {primary_len}, samples per timestamp: {samples_per_ts}, selector values: {selector_len}");
+        let mut metadata = vec![
+            ("Dataset Description".into(), description.clone()),
+            ("GPS Timestamps".into(), primary_len.to_string()),
+            ("Samples per timestamp".into(), samples_per_ts.to_string()),
+            (Self::SELECTOR_VALUES_KEY.into(), selector_len.to_string()),
+        ];
+        metadata.extend_from_slice(&descriptor.to_metadata());
+
+        let gps_timestamps_ns = gps_timestamps_ns(path, descriptor, primary_len, &mut metadata);
+        let starting_timestamp_utc = DateTime::from_timestamp_nanos(
+            gps_timestamps_ns.first().copied().unwrap_or_default() as i64,
+        );
+        let sample_timestamps_ns = sample_timestamps_ns(&gps_timestamps_ns, samples_per_ts);
+
+        let channels = calibrated_channels(
+            &data,
+            descriptor,
+            &description,
+            selector_axis,
+            channel_names,
+        );
+
+        let unit_suffix = if descriptor.unit().is_empty() {
+            String::new()
+        } else {
+            format!(" [{}]", descriptor.unit())
+        };
+        let mut raw_plots: Vec<RawPlot> = channels
+            .iter()
+            .map(|(name, values)| {
+                let points = sample_timestamps_ns
+                    .iter()
+                    .zip(values)
+                    .map(|(ts, value)| [*ts, *value])
+                    .collect();
+                raw_plot(format!("{name}{unit_suffix}"), points)
+            })
+            .collect();
+        let waveform = (samples_per_ts > 1).then(|| {
+            Waveform::new(
+                description.clone(),
+                descriptor.unit().to_owned(),
+                gps_timestamps_ns.clone(),
+                period_durations_ns(&gps_timestamps_ns),
+                channels,
+            )
+        });
+        if let Some(waveform) = &waveform {
+            raw_plots.extend(period_stats_plots(waveform, &unit_suffix));
+        }
+
+        Ok(Self {
+            starting_timestamp_utc,
+            description,
+            raw_plots,
+            metadata,
+            waveform,
+        })
+    }
+
+    pub fn waveform(&self) -> Option<&Waveform> {
+        self.waveform.as_ref()
+    }
+
+    /// Returns true if the timestamps are generated rather than read from a timestamp stream
+    pub fn is_time_synthetic(&self) -> bool {
+        is_time_synthetic(&self.metadata)
+    }
+}
+
+impl Plotable for BifrostStream {
+    fn raw_plots(&self) -> &[RawPlot] {
+        &self.raw_plots
+    }
+
+    fn first_timestamp(&self) -> DateTime<Utc> {
+        self.starting_timestamp_utc
+    }
+
+    fn descriptive_name(&self) -> &str {
+        &self.description
+    }
+
+    fn labels(&self) -> Option<&[PlotLabels]> {
+        None
+    }
+
+    fn metadata(&self) -> Option<Vec<(String, String)>> {
+        Some(self.metadata.clone())
+    }
+}
+
+/// Checks that the first axis is the primary (GPS timestamp) axis and all other axes are selectors
+fn validate_axes(axes: &[&Axis], shape: &[usize]) -> Result<(), String> {
+    if axes.len() != shape.len() {
+        return Err(format!(
+            "{} axes are described but the dataset has {} dimensions",
+            axes.len(),
+            shape.len()
+        ));
+    }
+    for (dim, axis) in axes.iter().enumerate() {
+        let expected_class = if dim == 0 {
+            AxisClass::Primary
+        } else {
+            AxisClass::Selector
+        };
+        if axis.class().map_err(|e| e.to_string())? != expected_class {
+            return Err(format!("Expected axis {dim} to be {expected_class:?}"));
+        }
+    }
+    Ok(())
+}
+
+/// Calibrates the values of each selector value and names them from `channel_names` or the selector axis
+fn calibrated_channels(
+    data: &ndarray::Array2<f64>,
+    descriptor: &StreamDescriptor,
+    description: &str,
+    selector_axis: Option<&Axis>,
+    channel_names: Option<&[&str]>,
+) -> Vec<(String, Vec<f64>)> {
+    data.columns()
+        .into_iter()
+        .enumerate()
+        .map(|(selector_idx, column)| {
+            let name = match (
+                channel_names.and_then(|names| names.get(selector_idx)),
+                selector_axis,
+            ) {
+                (Some(name), _) => (*name).to_owned(),
+                (None, Some(axis)) => format!("{description} {}", axis.value(selector_idx)),
+                (None, None) => description.to_owned(),
+            };
+            let values = column
+                .iter()
+                .map(|raw| descriptor.calibrate(*raw))
+                .collect();
+            (name, values)
+        })
+        .collect()
+}
+
+fn raw_plot(name: String, points: Vec<[f64; 2]>) -> RawPlot {
+    let expected_range = expected_range(&points);
+    RawPlot::new(name, points, expected_range)
+}
+
+/// Plots of the statistics of each period of each waveform channel, which makes changes of the waveform over time visible
+fn period_stats_plots(waveform: &Waveform, unit_suffix: &str) -> Vec<RawPlot> {
+    let mut plots = vec![];
+    for channel in waveform.channels() {
+        let stat_points = |stat: fn(&PeriodStats) -> Option<f64>| -> Vec<[f64; 2]> {
+            waveform
+                .period_timestamps_ns()
+                .iter()
+                .zip(channel.stats())
+                .filter_map(|(ts, stats)| stat(stats).map(|value| [*ts, value]))
+                .collect()
+        };
+        plots.push(raw_plot(
+            format!("{} peak{unit_suffix}", channel.name()),
+            stat_points(|stats| Some(stats.peak())),
+        ));
+        plots.push(raw_plot(
+            format!("{} rise time [µs]", channel.name()),
+            stat_points(|stats| stats.rise_time_ns().map(|ns| ns / 1e3)),
+        ));
+        plots.push(raw_plot(
+            format!("{} fall time [µs]", channel.name()),
+            stat_points(|stats| stats.fall_time_ns().map(|ns| ns / 1e3)),
+        ));
+    }
+    plots
+}
+
+pub(crate) fn is_time_synthetic(metadata: &[(String, String)]) -> bool {
+    metadata
+        .iter()
+        .any(|(key, value)| key == BifrostStream::SYNTHETIC_TIME_KEY && value == "true")
+}
+
+/// Reads and decodes the stream descriptor of the `dataset`, [`None`] if it has none
+fn read_stream_descriptor(dataset: &Dataset) -> io::Result<Option<StreamDescriptor>> {
+    if !dataset
+        .attr_names()?
+        .iter()
+        .any(|name| name == BifrostStream::STREAM_DESCRIPTOR_ATTRIBUTE)
+    {
+        return Ok(None);
+    }
+    let stream_descriptor_toml_str =
+        read_string_attribute(&dataset.attr(BifrostStream::STREAM_DESCRIPTOR_ATTRIBUTE)?)?;
+    toml::from_str(&stream_descriptor_toml_str)
+        .map(Some)
+        .map_err(|e| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!(
+                    "Failed decoding 'stream_descriptor' string as TOML: {e}\nstream_descriptor: {stream_descriptor_toml_str}"
+                ),
+            )
+        })
+}
+
+/// The GPS timestamps of the stream, read from its timestamp stream if possible, otherwise generated.
+///
+/// Adds metadata about where the timestamps came from.
+fn gps_timestamps_ns(
+    path: &Path,
+    descriptor: &StreamDescriptor,
+    count: usize,
+    metadata: &mut Vec<(String, String)>,
+) -> Vec<f64> {
+    let timestamp_stream = descriptor
+        .timestamp_stream()
+        .map(|name| read_timestamp_stream(path, name, count));
+    match timestamp_stream {
+        Some(Ok((timestamps_ns, source))) => {
+            metadata.push((BifrostStream::SYNTHETIC_TIME_KEY.into(), "false".into()));
+            metadata.push(("Timestamp Source".into(), source));
+            timestamps_ns
+        }
+        Some(Err(e)) => {
+            log::warn!("Falling back to synthetic time: {e}");
+            metadata.extend(synthetic_time_metadata(format!(
+                "Failed reading the timestamp stream: {e}"
+            )));
+            synthetic_gps_timestamps_ns(count)
+        }
+        None => {
+            metadata.extend(synthetic_time_metadata("No timestamp stream".to_owned()));
+            synthetic_gps_timestamps_ns(count)
+        }
+    }
+}
+
+/// Reads the timestamps of the `timestamp_stream` dataset as UTC nanoseconds since the Unix epoch.
+///
+/// The dataset is looked up in the file at `path` first, and then in the HDF5 files in the same directory,
+/// see [`find_timestamp_dataset`].
+/// The values are interpreted in the unit of the dataset's `unit` attribute, and as seconds if it has none.
+/// They are relative to the [`TimestampEpoch`] of the dataset's `epoch` attribute, and to the Unix epoch if it has none.
+///
+/// Returns the timestamps along with a description of where they were read from.
+fn read_timestamp_stream(
+    path: &Path,
+    timestamp_stream: &str,
+    expected_len: usize,
+) -> io::Result<(Vec<f64>, String)> {
+    let (dataset, source_path) = find_timestamp_dataset(path, timestamp_stream, expected_len)?;
+    let unit = dataset
+        .attr("unit")
+        .ok()
+        .and_then(|attr| read_string_attribute(&attr).ok())
+        .filter(|unit| !unit.is_empty());
+    let ts_factor = match unit.as_deref() {
+        None => TimestampUnit::Seconds,
+        Some(unit) => TimestampUnit::from_unit_str(unit).ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("Unsupported unit of timestamp stream '{timestamp_stream}': {unit}"),
+            )
+        })?,
+    }
+    .to_ns_factor();
+    let epoch = match dataset
+        .attr("epoch")
+        .ok()
+        .and_then(|attr| read_string_attribute(&attr).ok())
+        .filter(|epoch| !epoch.is_empty())
+    {
+        None => TimestampEpoch::Unix,
+        Some(epoch) => TimestampEpoch::from_attr_str(&epoch).ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("Unsupported epoch of timestamp stream '{timestamp_stream}': {epoch}"),
+            )
+        })?,
+    };
+    let timestamps_ns: Vec<f64> = dataset
+        .read_raw::<f64>()?
+        .into_iter()
+        .map(|ts| epoch.to_unix_ns(ts * ts_factor))
+        .collect();
+    Ok((
+        timestamps_ns,
+        format!("{}:{}", source_path.display(), dataset.name()),
+    ))
+}
+
+/// The epoch of the values of a timestamp stream, declared by its `epoch` attribute
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum TimestampEpoch {
+    /// 1970-01-01 in UTC, without leap seconds
+    Unix,
+    /// 1980-01-06 in GPS time, which is ahead of UTC by the leap seconds since then
+    Gps,
+}
+
+/// The GPS epoch as seconds since the Unix epoch
+const GPS_EPOCH_UNIX_S: f64 = 315_964_800.0;
+
+/// The UTC instants (as seconds since the Unix epoch) of the leap seconds since the GPS epoch,
+/// GPS time is one more second ahead of UTC after each of them
+const LEAP_SECONDS_UNIX_S: [f64; 18] = [
+    362_793_600.0,   // 1981-07-01
+    394_329_600.0,   // 1982-07-01
+    425_865_600.0,   // 1983-07-01
+    489_024_000.0,   // 1985-07-01
+    567_993_600.0,   // 1988-01-01
+    631_152_000.0,   // 1990-01-01
+    662_688_000.0,   // 1991-01-01
+    709_948_800.0,   // 1992-07-01
+    741_484_800.0,   // 1993-07-01
+    773_020_800.0,   // 1994-07-01
+    820_454_400.0,   // 1996-01-01
+    867_715_200.0,   // 1997-07-01
+    915_148_800.0,   // 1999-01-01
+    1_136_073_600.0, // 2006-01-01
+    1_230_768_000.0, // 2009-01-01
+    1_341_100_800.0, // 2012-07-01
+    1_435_708_800.0, // 2015-07-01
+    1_483_228_800.0, // 2017-01-01
+];
+
+impl TimestampEpoch {
+    fn from_attr_str(epoch: &str) -> Option<Self> {
+        match epoch.trim().to_ascii_lowercase().as_str() {
+            "unix" | "utc" => Some(Self::Unix),
+            "gps" => Some(Self::Gps),
+            _ => None,
+        }
+    }
+
+    /// Converts nanoseconds since this epoch to UTC nanoseconds since the Unix epoch
+    fn to_unix_ns(self, timestamp_ns: f64) -> f64 {
+        match self {
+            Self::Unix => timestamp_ns,
+            Self::Gps => {
+                let gps_time_ns = GPS_EPOCH_UNIX_S * NANOSECONDS_PER_SECOND + timestamp_ns;
+                // Leap seconds are only subtracted once UTC has passed them, which is checked after
+                // subtracting the leap seconds before them
+                LEAP_SECONDS_UNIX_S
+                    .iter()
+                    .fold(gps_time_ns, |utc_ns, leap_s| {
+                        if utc_ns - NANOSECONDS_PER_SECOND >= leap_s * NANOSECONDS_PER_SECOND {
+                            utc_ns - NANOSECONDS_PER_SECOND
+                        } else {
+                            utc_ns
+                        }
+                    })
+            }
+        }
+    }
+}
+
+/// Finds the `name` dataset with `expected_len` timestamps in the file at `path` or one of the HDF5 files next to it.
+///
+/// Files of the same recording as `path`, i.e. with the same [`recording_prefix`], are searched before other files,
+/// and files are searched in order of their names, so a folder with several flights picks the same file every time.
+fn find_timestamp_dataset(
+    path: &Path,
+    name: &str,
+    expected_len: usize,
+) -> io::Result<(Dataset, PathBuf)> {
+    let find_in = |candidate: &Path| {
+        hdf5::File::open(candidate)
+            .and_then(|file| file.dataset(name))
+            .ok()
+            .filter(|dataset| dataset.size() == expected_len)
+    };
+    if let Some(dataset) = find_in(path) {
+        return Ok((dataset, path.to_path_buf()));
+    }
+    if let Some(dir) = path.parent().filter(|dir| dir.is_dir()) {
+        let mut siblings: Vec<PathBuf> = fs::read_dir(dir)?
+            .flatten()
+            .map(|entry| entry.path())
+            .filter(|sibling| sibling != path && crate::path_has_hdf_extension(sibling))
+            .collect();
+        let recording = recording_prefix(path);
+        siblings.sort_by_cached_key(|sibling| {
+            (
+                recording.is_none() || recording_prefix(sibling) != recording,
+                sibling.clone(),
+            )
+        });
+        for sibling in siblings {
+            if let Some(dataset) = find_in(&sibling) {
+                return Ok((dataset, sibling));
+            }
+        }
+    }
+    Err(io::Error::new(
+        io::ErrorKind::NotFound,
+        format!(
+            "No timestamp stream '{name}' with {expected_len} timestamps in {} or the HDF5 files next to it",
+            path.display()
+        ),
+    ))
+}
+
+/// The timestamp that the file names of a recording start with, e.g. `20240930_100137` of
+/// `20240930_100137_bifrost.h5`
+fn recording_prefix(path: &Path) -> Option<String> {
+    let file_name = path.file_name()?.to_str()?;
+    let prefix = file_name
+        .split(|c: char| !c.is_ascii_digit() && c != '_')
+        .next()?
+        .trim_end_matches('_');
+    (!prefix.is_empty()).then(|| prefix.to_owned())
+}
+
+fn synthetic_time_metadata(reason: String) -> [(String, String); 2] {
+    [
+        (BifrostStream::SYNTHETIC_TIME_KEY.into(), "true".into()),
+        ("Synthetic Time Reason".into(), reason),
+    ]
+}
+
+/// Generates one timestamp per second starting at 1. january current year, for files without a timestamp stream
+fn synthetic_gps_timestamps_ns(count: usize) -> Vec<f64> {
+    let start_ns = first_january_this_year()
+        .timestamp_nanos_opt()
+        .expect("timestamp as nanoseconds out of range")
+        .to_f64()
+        .expect("Failed converting timestamp to f64");
+    (0..count)
+        .map(|idx| start_ns + idx as f64 * NANOSECONDS_PER_SECOND)
+        .collect()
+}
+
+const NANOSECONDS_PER_SECOND: f64 = 1_000_000_000.0;
+
+/// The duration of each GPS timestamp period, i.e. the time until the next timestamp.
+///
+/// The last period has the same duration as the period before it, or one second if there's only a single timestamp.
+fn period_durations_ns(gps_timestamps_ns: &[f64]) -> Vec<f64> {
+    (0..gps_timestamps_ns.len())
+        .map(
+            |idx| match (gps_timestamps_ns.get(idx + 1), idx.checked_sub(1)) {
+                (Some(next), _) => next - gps_timestamps_ns[idx],
+                (None, Some(prev_idx)) => gps_timestamps_ns[idx] - gps_timestamps_ns[prev_idx],
+                (None, None) => NANOSECONDS_PER_SECOND,
+            },
+        )
+        .collect()
+}
+
+/// Spreads `samples_per_ts` samples evenly over each GPS timestamp period
+fn sample_timestamps_ns(gps_timestamps_ns: &[f64], samples_per_ts: usize) -> Vec<f64> {
+    gps_timestamps_ns
+        .iter()
+        .zip(period_durations_ns(gps_timestamps_ns))
+        .flat_map(|(gps_ts, duration)| {
+            let sample_step = duration / samples_per_ts as f64;
+            (0..samples_per_ts).map(move |sample_idx| gps_ts + sample_idx as f64 * sample_step)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use testresult::TestResult;
+
+    use super::super::fixture;
+    use super::*;
+
+    /// 2024-09-30T10:01:37Z, when the test data was recorded
+    const START_UNIX_S: i64 = 1_727_690_497;
+
+    const TEST_DATA: &str = "../../test_data/hdf5/bifrost_current/20240930_100137_bifrost.h5";
+
+    #[test]
+    fn test_read_all_streams() -> TestResult {
+        let streams = BifrostStream::read_all(TEST_DATA)?;
+        assert_eq!(streams.len(), 1);
+
+        let hm_current = &streams[0];
+        assert_eq!(hm_current.descriptive_name(), "TX Loop Current");
+        // One plot per value of the last selector axis, with the samples of the middle axis spread in time,
+        // followed by the peak, rise time and fall time plots of each value
+        assert_eq!(hm_current.raw_plots().len(), 2 + 2 * 3);
+        assert_eq!(hm_current.raw_plots()[0].name(), "TX Loop Current 0 [A]");
+        assert_eq!(hm_current.raw_plots()[0].points().len(), 50 * 303);
+        assert_eq!(
+            hm_current.raw_plots()[2].name(),
+            "TX Loop Current 0 peak [A]"
+        );
+        assert_eq!(hm_current.raw_plots()[2].points().len(), 50);
+
+        let waveform = hm_current.waveform().expect("Expected a waveform");
+        assert_eq!(waveform.period_count(), 50);
+        assert_eq!(waveform.samples_per_period(), 303);
+        assert!(hm_current.is_time_synthetic());
+        Ok(())
+    }
+
+    #[test]
+    fn test_sample_timestamps_are_spread_between_gps_timestamps() {
+        let gps_timestamps_ns = [0.0, 1000.0, 3000.0];
+        let sample_timestamps = sample_timestamps_ns(&gps_timestamps_ns, 2);
+        assert_eq!(
+            sample_timestamps,
+            [0.0, 500.0, 1000.0, 2000.0, 3000.0, 4000.0]
+        );
+    }
+
+    /// Writes a `temperature` stream with a timestamp per second from [`START_UNIX_S`] to a copy of the test data
+    fn copy_test_data_with_temperature(dir: &Path, timestamp_stream: &str) -> io::Result<PathBuf> {
+        let path = fixture::copy_test_data(dir, "20240930_100137_bifrost.h5")?;
+        let temperatures = ndarray::array![[20.0, 30.0], [21.0, 31.0], [22.0, 32.0]];
+        fixture::write_stream(
+            &hdf5::File::open_rw(&path)?,
+            "temperature",
+            &temperatures,
+            timestamp_stream,
+        )?;
+        Ok(path)
+    }
+
+    fn read_temperature(path: &Path) -> io::Result<BifrostStream> {
+        BifrostStream::read_all(path)?
+            .into_iter()
+            .find(|stream| stream.descriptive_name() == "temperature")
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "No temperature stream"))
+    }
+
+    #[test]
+    fn test_timestamps_are_read_from_the_same_file() -> TestResult {
+        let dir = tempfile::tempdir()?;
+        let path = copy_test_data_with_temperature(dir.path(), "temperature_time")?;
+        let start_s = START_UNIX_S as f64;
+        fixture::write_timestamps(
+            &hdf5::File::open_rw(&path)?,
+            "temperature_time",
+            &[start_s, start_s + 1.0, start_s + 2.0].map(|ts| ts * 1000.0),
+            "ms",
+        )?;
+
+        let temperature = read_temperature(&path)?;
+
+        assert!(!temperature.is_time_synthetic());
+        assert_eq!(
+            temperature.first_timestamp(),
+            DateTime::from_timestamp(START_UNIX_S, 0).unwrap()
+        );
+        let timestamps: Vec<f64> = temperature.raw_plots()[0]
+            .points()
+            .iter()
+            .map(|[ts, _]| *ts)
+            .collect();
+        assert_eq!(
+            timestamps,
+            [start_s, start_s + 1.0, start_s + 2.0].map(|ts| ts * NANOSECONDS_PER_SECOND)
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_gps_timestamps_are_read_from_a_sibling_file() -> TestResult {
+        let dir = tempfile::tempdir()?;
+        let path = copy_test_data_with_temperature(dir.path(), "gps_time")?;
+        let sibling_path = dir.path().join("gps.h5");
+        let sibling = hdf5::File::create(&sibling_path)?;
+        // GPS time was 18 leap seconds ahead of UTC when the test data was recorded
+        let start_gps_s = (START_UNIX_S as f64) - GPS_EPOCH_UNIX_S + 18.0;
+        fixture::write_timestamps(
+            &sibling,
+            "gps_time",
+            &[start_gps_s, start_gps_s + 1.0, start_gps_s + 2.0],
+            "s",
+        )?;
+        let gps_time = sibling.dataset("gps_time")?;
+        crate::util::write_string_attribute(&gps_time, "epoch", "gps")?;
+        drop(sibling);
+
+        let temperature = read_temperature(&path)?;
+
+        assert!(!temperature.is_time_synthetic());
+        assert_eq!(
+            temperature.first_timestamp(),
+            DateTime::from_timestamp(START_UNIX_S, 0).unwrap()
+        );
+        let metadata = temperature.metadata().unwrap_or_default();
+        let (_, source) = metadata
+            .iter()
+            .find(|(key, _)| key == "Timestamp Source")
+            .expect("Expected the timestamp source in the metadata");
+        assert!(source.contains("gps.h5"), "{source}");
+        Ok(())
+    }
+
+    #[test]
+    fn test_timestamps_are_read_from_the_sibling_file_of_the_same_recording_with_the_right_length(
+    ) -> TestResult {
+        let dir = tempfile::tempdir()?;
+        let path = copy_test_data_with_temperature(dir.path(), "gps_time")?;
+        let start_s = START_UNIX_S as f64;
+        for (file_name, timestamps) in [
+            // Another flight in the same folder, which is first by name
+            ("20240929_080000_gps.h5", vec![0.0, 1.0, 2.0]),
+            // The same recording, but not the timestamps of the stream
+            ("20240930_100137_a_gps.h5", vec![0.0, 1.0]),
+            (
+                "20240930_100137_b_gps.h5",
+                vec![start_s, start_s + 1.0, start_s + 2.0],
+            ),
+        ] {
+            let sibling = hdf5::File::create(dir.path().join(file_name))?;
+            fixture::write_timestamps(&sibling, "gps_time", &timestamps, "s")?;
+        }
+
+        let temperature = read_temperature(&path)?;
+
+        assert!(!temperature.is_time_synthetic());
+        assert_eq!(
+            temperature.first_timestamp(),
+            DateTime::from_timestamp(START_UNIX_S, 0).unwrap()
+        );
+        let metadata = temperature.metadata().unwrap_or_default();
+        assert!(metadata
+            .iter()
+            .any(|(key, source)| key == "Timestamp Source"
+                && source.contains("20240930_100137_b_gps.h5")));
+        Ok(())
+    }
+
+    #[test]
+    fn test_recording_prefix() {
+        assert_eq!(
+            recording_prefix(Path::new("dir/20240930_100137_bifrost.h5")).as_deref(),
+            Some("20240930_100137")
+        );
+        assert_eq!(recording_prefix(Path::new("bifrost.h5")), None);
+    }
+
+    #[test]
+    fn test_missing_timestamp_stream_falls_back_to_synthetic_time() -> TestResult {
+        let dir = tempfile::tempdir()?;
+        let path = copy_test_data_with_temperature(dir.path(), "missing_time")?;
+
+        let temperature = read_temperature(&path)?;
+
+        assert!(temperature.is_time_synthetic());
+        assert_eq!(temperature.first_timestamp(), first_january_this_year());
+        Ok(())
+    }
+
+    #[test]
+    fn test_gps_time_is_converted_to_utc_with_leap_seconds() {
+        let to_unix_s = |gps_s: f64| {
+            TimestampEpoch::Gps.to_unix_ns(gps_s * NANOSECONDS_PER_SECOND) / NANOSECONDS_PER_SECOND
+        };
+        assert_eq!(to_unix_s(0.0), GPS_EPOCH_UNIX_S);
+        // The last leap second was inserted at the end of 2016, after which GPS time is 18 seconds ahead of UTC
+        let leap_s = 1_483_228_800.0;
+        assert_eq!(to_unix_s(leap_s - GPS_EPOCH_UNIX_S + 18.0), leap_s);
+        assert_eq!(to_unix_s(leap_s - GPS_EPOCH_UNIX_S + 16.0), leap_s - 1.0);
+        assert_eq!(TimestampEpoch::Unix.to_unix_ns(42.0), 42.0);
+        assert_eq!(
+            TimestampEpoch::from_attr_str("GPS"),
+            Some(TimestampEpoch::Gps)
+        );
+        assert_eq!(TimestampEpoch::from_attr_str("tai"), None);
+    }
+}
@@ -1,4 +1,4 @@
-use std::collections::HashMap;
+use std::{collections::HashMap, io};
 
 use serde::{Deserialize, Serialize};
 
@@ -11,11 +11,53 @@ pub(crate) struct StreamDescriptor {
     data_type: String,
     timestamp_stream: String,
     axes: HashMap<String, Axis>,
+    #[serde(default)]
     converter: Converter,
+    #[serde(default)]
     aux_metadata: AuxMetadata,
 }
 
 impl StreamDescriptor {
+    pub(crate) fn description(&self) -> &str {
+        &self.description
+    }
+
+    pub(crate) fn unit(&self) -> &str {
+        &self.unit
+    }
+
+    /// The axes ordered by the dimension of the dataset they describe
+    ///
+    /// # Errors
+    ///
+    /// If the axes are not numbered `0..N`
+    pub(crate) fn ordered_axes(&self) -> io::Result<Vec<&Axis>> {
+        (0..self.axes.len())
+            .map(|dim| {
+                self.axes.get(&dim.to_string()).ok_or_else(|| {
+                    io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        format!(
+                            "Stream '{}' has no axis for dimension {dim}",
+                            self.stream_id
+                        ),
+                    )
+                })
+            })
+            .collect()
+    }
+
+    /// Applies the calibration from the [`AuxMetadata`] to a raw value, regardless of the converter
+    pub(crate) fn calibrate(&self, raw: f64) -> f64 {
+        raw * self.aux_metadata.cal_scale + self.aux_metadata.cal_offset
+    }
+
+    /// Name of the dataset with the timestamps of this stream, [`None`] if the stream has no timestamps
+    pub(crate) fn timestamp_stream(&self) -> Option<&str> {
+        let timestamp_stream = self.timestamp_stream.trim();
+        (!timestamp_stream.is_empty()).then_some(timestamp_stream)
+    }
+
     /// Flattens the [`StreamDescriptor`] to a list of key-value pairs
     pub(crate) fn to_metadata(&self) -> Vec<(String, String)> {
         let mut metadata = Vec::new();
@@ -48,15 +90,45 @@ impl StreamDescriptor {
     }
 }
 
+/// The kind of an [`Axis`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum AxisClass {
+    /// The time axis, one entry per timestamp in the timestamp stream
+    Primary,
+    /// Selects between values, such as a sample within a period or a polarity
+    Selector,
+}
+
 #[derive(Debug, Clone, Deserialize, Serialize)]
-struct Axis {
+pub(crate) struct Axis {
     classname: String,
+    #[serde(default)]
     description: String,
+    #[serde(default)]
     values: Vec<i32>,
+    #[serde(default)]
     unit: String,
 }
 
 impl Axis {
+    pub(crate) fn class(&self) -> io::Result<AxisClass> {
+        match self.classname.as_str() {
+            "Primary" => Ok(AxisClass::Primary),
+            "Selector" => Ok(AxisClass::Selector),
+            other => Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("Unsupported axis class: {other}"),
+            )),
+        }
+    }
+
+    /// The value selected by `index`, which is the index itself if the axis doesn't list its values
+    pub(crate) fn value(&self, index: usize) -> String {
+        self.values
+            .get(index)
+            .map_or_else(|| index.to_string(), ToString::to_string)
+    }
+
     fn to_metadata(&self) -> Vec<(String, String)> {
         vec![
             ("classname".to_owned(), self.classname.clone()),
@@ -72,6 +144,14 @@ struct Converter {
     classname: String,
 }
 
+impl Default for Converter {
+    fn default() -> Self {
+        Self {
+            classname: "Unity".to_owned(),
+        }
+    }
+}
+
 impl Converter {
     fn to_metadata(&self) -> Vec<(String, String)> {
         vec![("classname".to_owned(), self.classname.clone())]
@@ -84,6 +164,15 @@ struct AuxMetadata {
     cal_scale: f64,
 }
 
+impl Default for AuxMetadata {
+    fn default() -> Self {
+        Self {
+            cal_offset: 0.0,
+            cal_scale: 1.0,
+        }
+    }
+}
+
 impl AuxMetadata {
     fn to_metadata(&self) -> Vec<(String, String)> {
         vec![
@@ -456,4 +545,31 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn test_ordered_axes() -> TestResult {
+        let stream_descriptor: StreamDescriptor = toml::from_str(TEST_TOML_STR)?;
+        let classes = stream_descriptor
+            .ordered_axes()?
+            .iter()
+            .map(|axis| axis.class())
+            .collect::<io::Result<Vec<_>>>()?;
+        assert_eq!(
+            classes,
+            [AxisClass::Primary, AxisClass::Selector, AxisClass::Selector]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_calibrate() -> TestResult {
+        let mut stream_descriptor: StreamDescriptor = toml::from_str(TEST_TOML_STR)?;
+        // The calibration applies to streams with the Unity converter as well
+        assert_eq!(stream_descriptor.calibrate(400.0), 2.0);
+
+        stream_descriptor.converter.classname = "Linear".to_owned();
+        stream_descriptor.aux_metadata.cal_offset = 1.0;
+        assert_eq!(stream_descriptor.calibrate(400.0), 3.0);
+        Ok(())
+    }
 }
@@ -0,0 +1,208 @@
+use getset::{CopyGetters, Getters};
+use serde::{Deserialize, Serialize};
+
+/// Statistics of the samples of a single period of a [`Waveform`] channel
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize, CopyGetters)]
+#[getset(get_copy = "pub")]
+pub struct PeriodStats {
+    /// The sample with the largest magnitude
+    peak: f64,
+    /// Time from the magnitude first reaching 10% of the peak until it reaches 90% of the peak
+    rise_time_ns: Option<f64>,
+    /// Time from the magnitude last being at 90% of the peak until it drops below 10% of the peak
+    fall_time_ns: Option<f64>,
+}
+
+impl PeriodStats {
+    const LOW_THRESHOLD: f64 = 0.1;
+    const HIGH_THRESHOLD: f64 = 0.9;
+
+    pub fn from_samples(samples: &[f64], sample_step_ns: f64) -> Self {
+        let peak = samples
+            .iter()
+            .copied()
+            .filter(|s| s.is_finite())
+            .max_by(|a, b| a.abs().total_cmp(&b.abs()))
+            .unwrap_or(f64::NAN);
+        let peak_magnitude = peak.abs();
+        if !peak_magnitude.is_normal() {
+            return Self {
+                peak,
+                rise_time_ns: None,
+                fall_time_ns: None,
+            };
+        }
+        let low = peak_magnitude * Self::LOW_THRESHOLD;
+        let high = peak_magnitude * Self::HIGH_THRESHOLD;
+        let magnitudes: Vec<f64> = samples.iter().map(|s| s.abs()).collect();
+
+        let rise_time_ns = magnitudes
+            .iter()
+            .position(|m| *m >= low)
+            .and_then(|low_idx| {
+                magnitudes[low_idx..]
+                    .iter()
+                    .position(|m| *m >= high)
+                    .map(|samples_to_high| samples_to_high as f64 * sample_step_ns)
+            });
+        let fall_time_ns = magnitudes
+            .iter()
+            .rposition(|m| *m >= high)
+            .and_then(|high_idx| {
+                magnitudes[high_idx..]
+                    .iter()
+                    .position(|m| *m < low)
+                    .map(|samples_to_low| samples_to_low as f64 * sample_step_ns)
+            });
+        Self {
+            peak,
+            rise_time_ns,
+            fall_time_ns,
+        }
+    }
+}
+
+/// A channel of a [`Waveform`], e.g. a polarity of the TX loop current
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Getters)]
+#[getset(get = "pub")]
+pub struct WaveformChannel {
+    name: String,
+    /// The samples of all periods after each other
+    samples: Vec<f64>,
+    /// Statistics of each period
+    stats: Vec<PeriodStats>,
+}
+
+/// A stream that is sampled in periods, with one period per GPS timestamp, such as the TX loop current.
+///
+/// Keeps the samples of each period apart, such that the periods can be compared to each other
+/// rather than only viewed as one long line.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Getters, CopyGetters)]
+pub struct Waveform {
+    #[getset(get = "pub")]
+    name: String,
+    #[getset(get = "pub")]
+    unit: String,
+    #[getset(get_copy = "pub")]
+    samples_per_period: usize,
+    /// The GPS timestamp at the start of each period
+    #[getset(get = "pub")]
+    period_timestamps_ns: Vec<f64>,
+    /// The duration of each period, i.e. the time until the next GPS timestamp
+    #[getset(get = "pub")]
+    period_durations_ns: Vec<f64>,
+    #[getset(get = "pub")]
+    channels: Vec<WaveformChannel>,
+}
+
+impl Waveform {
+    /// Creates a waveform from the samples of each channel, where the samples of all periods are after each other
+    pub fn new(
+        name: String,
+        unit: String,
+        period_timestamps_ns: Vec<f64>,
+        period_durations_ns: Vec<f64>,
+        channels: Vec<(String, Vec<f64>)>,
+    ) -> Self {
+        let samples_per_period = channels
+            .first()
+            .map_or(0, |(_, samples)| samples.len())
+            .checked_div(period_timestamps_ns.len())
+            .unwrap_or(0);
+        let channels = channels
+            .into_iter()
+            .map(|(name, samples)| {
+                let stats = if samples_per_period == 0 {
+                    vec![]
+                } else {
+                    samples
+                        .chunks_exact(samples_per_period)
+                        .zip(&period_durations_ns)
+                        .map(|(period, duration)| {
+                            PeriodStats::from_samples(period, duration / samples_per_period as f64)
+                        })
+                        .collect()
+                };
+                WaveformChannel {
+                    name,
+                    samples,
+                    stats,
+                }
+            })
+            .collect();
+        Self {
+            name,
+            unit,
+            samples_per_period,
+            period_timestamps_ns,
+            period_durations_ns,
+            channels,
+        }
+    }
+
+    pub fn period_count(&self) -> usize {
+        self.period_timestamps_ns.len()
+    }
+
+    /// The samples of the `period` of the `channel`
+    ///
+    /// # Panics
+    ///
+    /// If the channel or period is out of bounds
+    pub fn period(&self, channel: usize, period: usize) -> &[f64] {
+        let start = period * self.samples_per_period;
+        &self.channels[channel].samples[start..start + self.samples_per_period]
+    }
+
+    /// The time of each sample since the start of the `period`
+    pub fn sample_offsets_ns(&self, period: usize) -> impl Iterator<Item = f64> + '_ {
+        let sample_step = self.period_durations_ns[period] / self.samples_per_period as f64;
+        (0..self.samples_per_period).map(move |idx| idx as f64 * sample_step)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn test_period_stats() {
+        // Ramps up over 4 samples, holds, and turns off in 2 samples
+        let samples = [0.0, 2.5, 5.0, 7.5, 10.0, 10.0, 10.0, 5.0, 0.0, 0.0];
+        let stats = PeriodStats::from_samples(&samples, 100.0);
+        assert_eq!(stats.peak(), 10.0);
+        // 10% is first reached at 2.5 (idx 1) and 90% at 10.0 (idx 4)
+        assert_eq!(stats.rise_time_ns(), Some(300.0));
+        // 90% is last seen at idx 6 and the magnitude is below 10% at idx 8
+        assert_eq!(stats.fall_time_ns(), Some(200.0));
+    }
+
+    #[test]
+    fn test_period_stats_negative_polarity() {
+        let samples = [0.0, -10.0, -10.0];
+        let stats = PeriodStats::from_samples(&samples, 1.0);
+        assert_eq!(stats.peak(), -10.0);
+        assert_eq!(stats.rise_time_ns(), Some(0.0));
+        assert_eq!(stats.fall_time_ns(), None);
+    }
+
+    #[test]
+    fn test_waveform_periods() {
+        let waveform = Waveform::new(
+            "Current".to_owned(),
+            "A".to_owned(),
+            vec![0.0, 1000.0],
+            vec![1000.0, 1000.0],
+            vec![("+".to_owned(), vec![1.0, 2.0, 3.0, 4.0])],
+        );
+        assert_eq!(waveform.samples_per_period(), 2);
+        assert_eq!(waveform.period(0, 1), [3.0, 4.0]);
+        assert_eq!(waveform.channels()[0].stats()[1].peak(), 4.0);
+        assert_eq!(
+            waveform.sample_offsets_ns(1).collect::<Vec<_>>(),
+            [0.0, 500.0]
+        );
+    }
+}
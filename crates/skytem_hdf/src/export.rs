@@ -0,0 +1,331 @@
+//! Export of plotted logs to HDF5 files that can be processed by other tools and opened in the viewer again.
+//!
+//! The file has a group per log with the metadata of the log as attributes, and a `(n, 2)` dataset per plot
+//! with the timestamp (nanoseconds since the Unix epoch) in the first column and the value in the second.
+//! HDF5 doesn't keep the order of attributes, so the names of the metadata attributes are also stored in order
+//! in the `metadata_order` attribute.
+use std::{io, path::Path};
+
+use chrono::{DateTime, SecondsFormat, Utc};
+use hdf5::Group;
+use log_if::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::generic::{expected_range, read_attributes};
+use crate::util::{
+    read_string_array_attribute, write_string_array_attribute, write_string_attribute,
+};
+
+/// Attribute on the root of the file that marks it as an export, the value is the version of the layout
+const EXPORT_MARKER_ATTRIBUTE: &str = "plotinator3000_export";
+const EXPORT_VERSION: &str = "1";
+const DESCRIPTIVE_NAME_ATTRIBUTE: &str = "descriptive_name";
+const FIRST_TIMESTAMP_ATTRIBUTE: &str = "first_timestamp";
+const METADATA_ORDER_ATTRIBUTE: &str = "metadata_order";
+const PLOT_NAME_ATTRIBUTE: &str = "name";
+const EXPECTED_RANGE_ATTRIBUTE: &str = "expected_range";
+const COLUMNS_ATTRIBUTE: &str = "columns";
+const COLUMNS: &str = "timestamp_ns, value";
+
+/// A log that is exported to, or read from, an HDF5 export file
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExportedLog {
+    descriptive_name: String,
+    first_timestamp: DateTime<Utc>,
+    raw_plots: Vec<RawPlot>,
+    metadata: Vec<(String, String)>,
+}
+
+impl ExportedLog {
+    pub fn new(
+        descriptive_name: String,
+        first_timestamp: DateTime<Utc>,
+        raw_plots: Vec<RawPlot>,
+        metadata: Vec<(String, String)>,
+    ) -> Self {
+        Self {
+            descriptive_name,
+            first_timestamp,
+            raw_plots,
+            metadata,
+        }
+    }
+
+    /// Returns true if the file at `path` is an HDF5 file written by [`write_logs`]
+    pub fn is_export(path: &Path) -> bool {
+        hdf5::File::open(path)
+            .and_then(|file| file.attr_names())
+            .is_ok_and(|names| names.iter().any(|name| name == EXPORT_MARKER_ATTRIBUTE))
+    }
+
+    /// Reads all the logs of an HDF5 file written by [`write_logs`]
+    pub fn read_all(path: &Path) -> io::Result<Vec<Self>> {
+        if !Self::is_export(path) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("{} is not an exported HDF5 file", path.display()),
+            ));
+        }
+        let file = hdf5::File::open(path)?;
+        let mut groups = file.groups()?;
+        groups.sort_by_key(|group| group.name());
+        groups.iter().map(Self::from_group).collect()
+    }
+
+    fn from_group(group: &Group) -> io::Result<Self> {
+        let mut descriptive_name = group.name().trim_start_matches('/').to_owned();
+        let mut first_timestamp = None;
+        let mut metadata = vec![];
+        for (name, value) in read_attributes(group)? {
+            match name.as_str() {
+                DESCRIPTIVE_NAME_ATTRIBUTE => descriptive_name = value,
+                FIRST_TIMESTAMP_ATTRIBUTE => {
+                    first_timestamp = Some(DateTime::parse_from_rfc3339(&value).map_err(|e| {
+                        io::Error::new(
+                            io::ErrorKind::InvalidData,
+                            format!("Invalid first timestamp '{value}' of {descriptive_name}: {e}"),
+                        )
+                    })?);
+                }
+                METADATA_ORDER_ATTRIBUTE => (),
+                _ => metadata.push((name, value)),
+            }
+        }
+        if group
+            .attr_names()?
+            .iter()
+            .any(|name| name == METADATA_ORDER_ATTRIBUTE)
+        {
+            let order = read_string_array_attribute(&group.attr(METADATA_ORDER_ATTRIBUTE)?)?;
+            // Stable sort to keep any attributes missing from the order in their current order at the end
+            metadata.sort_by_key(|(name, _)| {
+                order
+                    .iter()
+                    .position(|ordered| ordered == name)
+                    .unwrap_or(order.len())
+            });
+        }
+
+        let mut datasets = group.datasets()?;
+        datasets.sort_by_key(|dataset| dataset.name());
+        let mut raw_plots = Vec::with_capacity(datasets.len());
+        for dataset in datasets {
+            let points: Vec<[f64; 2]> = dataset
+                .read_2d::<f64>()?
+                .rows()
+                .into_iter()
+                .filter_map(|row| Some([*row.first()?, *row.get(1)?]))
+                .collect();
+            let attributes = read_attributes(&dataset)?;
+            let attribute = |name: &str| {
+                attributes
+                    .iter()
+                    .find_map(|(key, value)| (key == name).then_some(value.as_str()))
+            };
+            let name = attribute(PLOT_NAME_ATTRIBUTE).map_or_else(
+                || {
+                    dataset
+                        .name()
+                        .rsplit('/')
+                        .next()
+                        .unwrap_or_default()
+                        .to_owned()
+                },
+                ToOwned::to_owned,
+            );
+            let expected_range = attribute(EXPECTED_RANGE_ATTRIBUTE)
+                .and_then(parse_expected_range)
+                .unwrap_or_else(|| expected_range(&points));
+            raw_plots.push(RawPlot::new(name, points, expected_range));
+        }
+
+        let first_timestamp = first_timestamp.map_or_else(
+            || {
+                DateTime::from_timestamp_nanos(
+                    raw_plots
+                        .iter()
+                        .filter_map(|plot| plot.points().first())
+                        .map(|[ts, _]| *ts)
+                        .reduce(f64::min)
+                        .unwrap_or_default() as i64,
+                )
+            },
+            |ts| ts.to_utc(),
+        );
+        Ok(Self {
+            descriptive_name,
+            first_timestamp,
+            raw_plots,
+            metadata,
+        })
+    }
+}
+
+impl Plotable for ExportedLog {
+    fn raw_plots(&self) -> &[RawPlot] {
+        &self.raw_plots
+    }
+
+    fn first_timestamp(&self) -> DateTime<Utc> {
+        self.first_timestamp
+    }
+
+    fn descriptive_name(&self) -> &str {
+        &self.descriptive_name
+    }
+
+    fn labels(&self) -> Option<&[PlotLabels]> {
+        None
+    }
+
+    fn metadata(&self) -> Option<Vec<(String, String)>> {
+        Some(self.metadata.clone())
+    }
+}
+
+/// Writes the `logs` to a new HDF5 file at `path`, replacing the file if it exists.
+///
+/// Groups and datasets are named by their index followed by the name of the log or plot, which keeps them
+/// unique and in order. The unmodified names are stored in the attributes.
+pub fn write_logs<P: Plotable>(path: &Path, logs: &[P]) -> io::Result<()> {
+    let file = hdf5::File::create(path)?;
+    write_string_attribute(&file, EXPORT_MARKER_ATTRIBUTE, EXPORT_VERSION)?;
+    let log_index_width = logs.len().to_string().len();
+    for (log_idx, log) in logs.iter().enumerate() {
+        let group = file.create_group(&format!(
+            "{log_idx:0log_index_width$} {}",
+            hdf5_object_name(log.descriptive_name())
+        ))?;
+        write_string_attribute(&group, DESCRIPTIVE_NAME_ATTRIBUTE, log.descriptive_name())?;
+        write_string_attribute(
+            &group,
+            FIRST_TIMESTAMP_ATTRIBUTE,
+            &log.first_timestamp()
+                .to_rfc3339_opts(SecondsFormat::Nanos, true),
+        )?;
+        let mut attribute_names = vec![
+            DESCRIPTIVE_NAME_ATTRIBUTE.to_owned(),
+            FIRST_TIMESTAMP_ATTRIBUTE.to_owned(),
+            METADATA_ORDER_ATTRIBUTE.to_owned(),
+        ];
+        let mut metadata_order = vec![];
+        for (key, value) in log.metadata().unwrap_or_default() {
+            let name = unique_name(&attribute_names, &key);
+            write_string_attribute(&group, &name, &value)?;
+            attribute_names.push(name.clone());
+            metadata_order.push(name);
+        }
+        // Zero sized attributes are avoided as not all tools handle them
+        if !metadata_order.is_empty() {
+            write_string_array_attribute(&group, METADATA_ORDER_ATTRIBUTE, &metadata_order)?;
+        }
+
+        let plot_index_width = log.raw_plots().len().to_string().len();
+        for (plot_idx, raw_plot) in log.raw_plots().iter().enumerate() {
+            let data = ndarray::Array2::from(raw_plot.points().to_vec());
+            let dataset = group.new_dataset_builder().with_data(&data).create(
+                format!(
+                    "{plot_idx:0plot_index_width$} {}",
+                    hdf5_object_name(raw_plot.name())
+                )
+                .as_str(),
+            )?;
+            write_string_attribute(&dataset, PLOT_NAME_ATTRIBUTE, raw_plot.name())?;
+            write_string_attribute(
+                &dataset,
+                EXPECTED_RANGE_ATTRIBUTE,
+                &format!("{:?}", raw_plot.expected_range()),
+            )?;
+            write_string_attribute(&dataset, COLUMNS_ATTRIBUTE, COLUMNS)?;
+        }
+    }
+    Ok(())
+}
+
+/// Replaces the characters that are not allowed in the name of an HDF5 group or dataset
+fn hdf5_object_name(name: &str) -> String {
+    name.replace('/', "_")
+}
+
+/// Appends a counter to `name` if it is already among the `taken` names
+fn unique_name(taken: &[String], name: &str) -> String {
+    let mut unique = name.to_owned();
+    let mut counter = 1;
+    while taken.contains(&unique) {
+        counter += 1;
+        unique = format!("{name} ({counter})");
+    }
+    unique
+}
+
+fn parse_expected_range(range: &str) -> Option<ExpectedPlotRange> {
+    match range {
+        "Percentage" => Some(ExpectedPlotRange::Percentage),
+        "OneToOneHundred" => Some(ExpectedPlotRange::OneToOneHundred),
+        "Thousands" => Some(ExpectedPlotRange::Thousands),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+    use testresult::TestResult;
+
+    use super::*;
+
+    #[test]
+    fn test_export_round_trip() -> TestResult {
+        let first_timestamp = DateTime::from_timestamp_nanos(1_700_000_000_123_456_789);
+        let logs = [
+            ExportedLog::new(
+                "Mbed PID v2".to_owned(),
+                first_timestamp,
+                vec![
+                    RawPlot::new(
+                        "RPM".to_owned(),
+                        vec![[1.0, 2500.0], [2.0, 2600.0]],
+                        ExpectedPlotRange::Thousands,
+                    ),
+                    RawPlot::new(
+                        "Duty cycle/PWM".to_owned(),
+                        vec![[1.0, 0.5], [2.0, 0.6]],
+                        ExpectedPlotRange::Percentage,
+                    ),
+                ],
+                vec![
+                    ("Project Version".to_owned(), "2.3.2".to_owned()),
+                    ("Config values".to_owned(), String::new()),
+                    ("Config values".to_owned(), "again".to_owned()),
+                    ("Setpoint".to_owned(), "1500".to_owned()),
+                    ("Alpha".to_owned(), "0.5".to_owned()),
+                ],
+            ),
+            ExportedLog::new("Empty".to_owned(), first_timestamp, vec![], vec![]),
+        ];
+        let dir = tempfile::tempdir()?;
+        let path = dir.path().join("export.h5");
+        write_logs(&path, &logs)?;
+        assert!(ExportedLog::is_export(&path));
+
+        let read = ExportedLog::read_all(&path)?;
+
+        assert_eq!(read.len(), 2);
+        assert_eq!(read[0].descriptive_name(), "Mbed PID v2");
+        assert_eq!(read[0].first_timestamp(), first_timestamp);
+        assert_eq!(read[0].raw_plots(), logs[0].raw_plots());
+        assert_eq!(
+            read[0].metadata().unwrap_or_default(),
+            [
+                ("Project Version".to_owned(), "2.3.2".to_owned()),
+                ("Config values".to_owned(), String::new()),
+                ("Config values (2)".to_owned(), "again".to_owned()),
+                ("Setpoint".to_owned(), "1500".to_owned()),
+                ("Alpha".to_owned(), "0.5".to_owned()),
+            ]
+        );
+        assert_eq!(read[1].descriptive_name(), "Empty");
+        assert!(read[1].raw_plots().is_empty());
+        Ok(())
+    }
+}
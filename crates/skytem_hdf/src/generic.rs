@@ -0,0 +1,446 @@
+//! Browse the structure of any HDF5 file and plot the datasets that the user selects.
+//!
+//! This is the fallback for HDF5 files that are not recognized as one of the specific formats like [`crate::bifrost`].
+
+use std::{
+    io,
+    path::{Path, PathBuf},
+};
+
+use chrono::{DateTime, Utc};
+use getset::Getters;
+use hdf5::{types::TypeDescriptor, Dataset, Group, Location};
+use log_if::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::util::{first_january_this_year, read_any_attribute_to_string};
+
+/// Reads all attributes of an HDF5 object as name/value pairs.
+///
+/// Attributes that cannot be read are included with the reason as the value, such that they still show up when browsing.
+pub(crate) fn read_attributes(location: &Location) -> hdf5::Result<Vec<(String, String)>> {
+    let mut attributes = vec![];
+    for name in location.attr_names()? {
+        let value = location
+            .attr(&name)
+            .and_then(|attr| read_any_attribute_to_string(&attr))
+            .unwrap_or_else(|e| format!("<{e}>"));
+        attributes.push((name, value));
+    }
+    Ok(attributes)
+}
+
+/// Describes a dataset in an HDF5 file without reading its data
+#[derive(Debug, Clone, PartialEq, Eq, Getters)]
+#[getset(get = "pub")]
+pub struct HdfDatasetInfo {
+    /// Full path of the dataset in the file, e.g. `/group/dataset`
+    path: String,
+    shape: Vec<usize>,
+    dtype: String,
+    /// Whether the values are integers or floats that can be read as [`f64`]
+    numeric: bool,
+    attributes: Vec<(String, String)>,
+}
+
+impl HdfDatasetInfo {
+    fn from_dataset(dataset: &Dataset) -> hdf5::Result<Self> {
+        let (dtype, numeric) = match dataset.dtype()?.to_descriptor() {
+            Ok(descriptor) => {
+                let numeric = matches!(
+                    descriptor,
+                    TypeDescriptor::Integer(_)
+                        | TypeDescriptor::Unsigned(_)
+                        | TypeDescriptor::Float(_)
+                );
+                (descriptor.to_string(), numeric)
+            }
+            Err(e) => (format!("<{e}>"), false),
+        };
+        Ok(Self {
+            path: dataset.name(),
+            shape: dataset.shape(),
+            dtype,
+            numeric,
+            attributes: read_attributes(dataset)?,
+        })
+    }
+
+    /// Name of the dataset without the path of the group it is in
+    pub fn name(&self) -> &str {
+        self.path.rsplit('/').next().unwrap_or(&self.path)
+    }
+
+    /// Number of entries along the first dimension, which is the time axis when plotting
+    pub fn len(&self) -> usize {
+        self.shape.first().copied().unwrap_or(0)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Whether the dataset can be plotted, i.e. it is a numeric 1D or 2D dataset
+    pub fn is_plottable(&self) -> bool {
+        self.numeric && matches!(self.shape.len(), 1 | 2) && !self.is_empty()
+    }
+
+    /// Whether the dataset can be used as the timestamps of other datasets, i.e. it is a numeric 1D dataset
+    pub fn is_timestamp_candidate(&self) -> bool {
+        self.numeric && self.shape.len() == 1 && !self.is_empty()
+    }
+}
+
+/// Describes a group in an HDF5 file along with all the groups and datasets it contains
+#[derive(Debug, Clone, PartialEq, Eq, Getters)]
+#[getset(get = "pub")]
+pub struct HdfGroupInfo {
+    /// Full path of the group in the file, `/` for the root group
+    path: String,
+    attributes: Vec<(String, String)>,
+    groups: Vec<HdfGroupInfo>,
+    datasets: Vec<HdfDatasetInfo>,
+}
+
+impl HdfGroupInfo {
+    fn from_group(group: &Group) -> hdf5::Result<Self> {
+        let mut groups = group
+            .groups()?
+            .iter()
+            .map(Self::from_group)
+            .collect::<hdf5::Result<Vec<_>>>()?;
+        groups.sort_by(|a, b| a.path.cmp(&b.path));
+        let mut datasets = group
+            .datasets()?
+            .iter()
+            .map(HdfDatasetInfo::from_dataset)
+            .collect::<hdf5::Result<Vec<_>>>()?;
+        datasets.sort_by(|a, b| a.path.cmp(&b.path));
+        Ok(Self {
+            path: group.name(),
+            attributes: read_attributes(group)?,
+            groups,
+            datasets,
+        })
+    }
+
+    /// Name of the group without the path of its parent group
+    pub fn name(&self) -> &str {
+        self.path.rsplit('/').next().unwrap_or(&self.path)
+    }
+
+    /// All datasets in this group and its subgroups
+    pub fn all_datasets(&self) -> Vec<&HdfDatasetInfo> {
+        let mut datasets: Vec<&HdfDatasetInfo> = self.datasets.iter().collect();
+        for group in &self.groups {
+            datasets.extend(group.all_datasets());
+        }
+        datasets
+    }
+}
+
+/// The structure of an HDF5 file, i.e. all of its groups, datasets and attributes
+#[derive(Debug, Clone, PartialEq, Eq, Getters)]
+#[getset(get = "pub")]
+pub struct HdfFileInfo {
+    path: PathBuf,
+    root: HdfGroupInfo,
+}
+
+impl HdfFileInfo {
+    pub fn from_path<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        let hdf5_file = hdf5::File::open(&path)?;
+        let root = HdfGroupInfo::from_group(&hdf5_file)?;
+        Ok(Self {
+            path: path.as_ref().to_path_buf(),
+            root,
+        })
+    }
+
+    /// Name of the file
+    pub fn file_name(&self) -> String {
+        self.path.file_name().map_or_else(
+            || self.path.display().to_string(),
+            |name| name.to_string_lossy().into_owned(),
+        )
+    }
+
+    /// Finds the dataset at `dataset_path` if it exists
+    pub fn dataset(&self, dataset_path: &str) -> Option<&HdfDatasetInfo> {
+        self.root
+            .all_datasets()
+            .into_iter()
+            .find(|d| d.path == dataset_path)
+    }
+}
+
+/// Unit of the values in a timestamp dataset, the values are the time since the Unix epoch in this unit
+#[derive(
+    Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize, Serialize, strum_macros::Display,
+)]
+pub enum TimestampUnit {
+    #[strum(to_string = "ns")]
+    Nanoseconds,
+    #[strum(to_string = "µs")]
+    Microseconds,
+    #[strum(to_string = "ms")]
+    Milliseconds,
+    #[default]
+    #[strum(to_string = "s")]
+    Seconds,
+}
+
+impl TimestampUnit {
+    pub const ALL: [Self; 4] = [
+        Self::Nanoseconds,
+        Self::Microseconds,
+        Self::Milliseconds,
+        Self::Seconds,
+    ];
+
+    /// Parses a unit as it is commonly written in HDF5 `unit` attributes, e.g. `"ms"` or `"seconds"`
+    pub fn from_unit_str(unit: &str) -> Option<Self> {
+        match unit.trim().to_ascii_lowercase().as_str() {
+            "ns" | "nanoseconds" => Some(Self::Nanoseconds),
+            "us" | "µs" | "microseconds" => Some(Self::Microseconds),
+            "ms" | "milliseconds" => Some(Self::Milliseconds),
+            "s" | "sec" | "seconds" => Some(Self::Seconds),
+            _ => None,
+        }
+    }
+
+    /// The factor to multiply a value of this unit with to get nanoseconds
+    pub fn to_ns_factor(self) -> f64 {
+        match self {
+            Self::Nanoseconds => 1.0,
+            Self::Microseconds => 1e3,
+            Self::Milliseconds => 1e6,
+            Self::Seconds => 1e9,
+        }
+    }
+}
+
+/// The datasets to plot from an HDF5 file and how to place them in time
+#[derive(Debug, Clone, PartialEq)]
+pub struct HdfPlotSelection {
+    /// Paths of the 1D/2D numeric datasets to plot
+    pub datasets: Vec<String>,
+    /// Path of a 1D numeric dataset with a timestamp for each entry along the first dimension of the plotted datasets
+    pub timestamp: Option<String>,
+    pub timestamp_unit: TimestampUnit,
+    /// Time between entries in seconds if there is no timestamp dataset
+    pub sample_period_s: f64,
+}
+
+impl Default for HdfPlotSelection {
+    fn default() -> Self {
+        Self {
+            datasets: vec![],
+            timestamp: None,
+            timestamp_unit: TimestampUnit::default(),
+            sample_period_s: 1.0,
+        }
+    }
+}
+
+/// Datasets from any HDF5 file, plotted as selected by the user
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GenericHdf {
+    descriptive_name: String,
+    first_timestamp: DateTime<Utc>,
+    raw_plots: Vec<RawPlot>,
+    metadata: Vec<(String, String)>,
+}
+
+impl GenericHdf {
+    /// Reads the datasets in the `selection` from the file described by `file_info`.
+    ///
+    /// A 1D dataset becomes one plot, a 2D dataset becomes one plot per column.
+    ///
+    /// # Errors
+    ///
+    /// If nothing is selected, a selected dataset is not plottable, or the timestamp dataset
+    /// does not have the same length as a plotted dataset.
+    pub fn from_selection(
+        file_info: &HdfFileInfo,
+        selection: &HdfPlotSelection,
+    ) -> io::Result<Self> {
+        if selection.datasets.is_empty() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "No datasets selected",
+            ));
+        }
+        let hdf5_file = hdf5::File::open(file_info.path())?;
+
+        let (timestamps_ns, first_timestamp) = read_selected_timestamps(&hdf5_file, selection)?;
+        let start_ns = first_timestamp
+            .timestamp_nanos_opt()
+            .expect("timestamp as nanoseconds out of range") as f64;
+        let sample_period_ns = selection.sample_period_s * 1e9;
+        let timestamp_of = |idx: usize| -> f64 {
+            timestamps_ns
+                .as_ref()
+                .map_or(start_ns + idx as f64 * sample_period_ns, |ts| ts[idx])
+        };
+
+        let mut raw_plots = vec![];
+        let mut metadata = vec![("File".to_owned(), file_info.file_name())];
+        if let Some(ts_path) = &selection.timestamp {
+            metadata.push(("Timestamps".to_owned(), ts_path.clone()));
+        }
+        for dataset_path in &selection.datasets {
+            let info = file_info
+                .dataset(dataset_path)
+                .filter(|info| info.is_plottable())
+                .ok_or_else(|| {
+                    io::Error::new(
+                        io::ErrorKind::InvalidInput,
+                        format!("'{dataset_path}' is not a plottable dataset"),
+                    )
+                })?;
+            if let Some(ts) = &timestamps_ns {
+                if ts.len() != info.len() {
+                    return Err(io::Error::new(
+                        io::ErrorKind::InvalidInput,
+                        format!(
+                            "'{dataset_path}' has {} entries but there are {} timestamps",
+                            info.len(),
+                            ts.len()
+                        ),
+                    ));
+                }
+            }
+            let values: ndarray::Array2<f64> = if info.shape().len() == 1 {
+                let values = hdf5_file.dataset(dataset_path)?.read_1d::<f64>()?;
+                values.insert_axis(ndarray::Axis(1))
+            } else {
+                hdf5_file.dataset(dataset_path)?.read_2d::<f64>()?
+            };
+            for (col_idx, column) in values.columns().into_iter().enumerate() {
+                let name = if values.ncols() == 1 {
+                    info.name().to_owned()
+                } else {
+                    format!("{}[{col_idx}]", info.name())
+                };
+                let points: Vec<[f64; 2]> = column
+                    .iter()
+                    .enumerate()
+                    .map(|(idx, val)| [timestamp_of(idx), *val])
+                    .collect();
+                let expected_range = expected_range(&points);
+                raw_plots.push(RawPlot::new(name, points, expected_range));
+            }
+            for (key, value) in info.attributes() {
+                metadata.push((format!("{}.{key}", info.path()), value.clone()));
+            }
+        }
+
+        Ok(Self {
+            descriptive_name: file_info.file_name(),
+            first_timestamp,
+            raw_plots,
+            metadata,
+        })
+    }
+}
+
+/// Reads the selected timestamp dataset as nanoseconds since the Unix epoch, along with the first timestamp.
+///
+/// Without a timestamp dataset there are no timestamps and the first timestamp is 1. january current year.
+fn read_selected_timestamps(
+    hdf5_file: &hdf5::File,
+    selection: &HdfPlotSelection,
+) -> io::Result<(Option<Vec<f64>>, DateTime<Utc>)> {
+    let Some(ts_path) = &selection.timestamp else {
+        return Ok((None, first_january_this_year()));
+    };
+    let ts_factor = selection.timestamp_unit.to_ns_factor();
+    let timestamps_ns: Vec<f64> = hdf5_file
+        .dataset(ts_path)?
+        .read_raw::<f64>()?
+        .into_iter()
+        .map(|ts| ts * ts_factor)
+        .collect();
+    let first_timestamp = timestamps_ns
+        .first()
+        .map(|ts| DateTime::from_timestamp_nanos(*ts as i64))
+        .ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("Timestamp dataset '{ts_path}' is empty"),
+            )
+        })?;
+    Ok((Some(timestamps_ns), first_timestamp))
+}
+
+/// Picks the [`ExpectedPlotRange`] from the largest absolute value of the points
+pub(crate) fn expected_range(points: &[[f64; 2]]) -> ExpectedPlotRange {
+    let max_abs = points
+        .iter()
+        .map(|[_, val]| val.abs())
+        .filter(|val| val.is_finite())
+        .fold(0.0, f64::max);
+    if max_abs <= 100.0 {
+        ExpectedPlotRange::OneToOneHundred
+    } else {
+        ExpectedPlotRange::Thousands
+    }
+}
+
+impl Plotable for GenericHdf {
+    fn raw_plots(&self) -> &[RawPlot] {
+        &self.raw_plots
+    }
+
+    fn first_timestamp(&self) -> DateTime<Utc> {
+        self.first_timestamp
+    }
+
+    fn descriptive_name(&self) -> &str {
+        &self.descriptive_name
+    }
+
+    fn labels(&self) -> Option<&[PlotLabels]> {
+        None
+    }
+
+    fn metadata(&self) -> Option<Vec<(String, String)>> {
+        Some(self.metadata.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use testresult::TestResult;
+
+    use super::*;
+
+    const TEST_DATA: &str = "../../test_data/hdf5/bifrost_current/20240930_100137_bifrost.h5";
+
+    #[test]
+    fn test_browse_file() -> TestResult {
+        let file_info = HdfFileInfo::from_path(TEST_DATA)?;
+        assert_eq!(file_info.root().path(), "/");
+
+        let hm_current = file_info
+            .dataset("/hm_current")
+            .expect("Expected the hm_current dataset");
+        assert_eq!(hm_current.shape(), &vec![50, 303, 2]);
+        assert_eq!(hm_current.dtype(), "float32");
+        assert!(!hm_current.is_plottable());
+        assert!(hm_current
+            .attributes()
+            .contains(&("description".to_owned(), "TX Loop Current".to_owned())));
+        Ok(())
+    }
+
+    #[test]
+    fn test_plot_selection_requires_datasets() -> TestResult {
+        let file_info = HdfFileInfo::from_path(TEST_DATA)?;
+        let err = GenericHdf::from_selection(&file_info, &HdfPlotSelection::default())
+            .expect_err("Expected an error when nothing is selected");
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+        Ok(())
+    }
+}
@@ -4,6 +4,22 @@ pub(crate) mod util;
 #[cfg(not(target_arch = "wasm32"))]
 pub mod bifrost;
 
+#[cfg(not(target_arch = "wasm32"))]
+pub mod generic;
+
+#[cfg(not(target_arch = "wasm32"))]
+pub mod export;
+
+/// The signature at the start of the superblock of an HDF5 file
+pub const HDF5_SIGNATURE: [u8; 8] = *b"\x89HDF\r\n\x1a\n";
+
+/// The superblock (and thereby the signature) is at offset 0, or after a user block at a power of two from 512.
+///
+/// User blocks larger than 512 KiB are not considered.
+const HDF5_SIGNATURE_OFFSETS: [usize; 12] = [
+    0, 512, 1024, 2048, 4096, 8192, 16384, 32768, 65536, 131_072, 262_144, 524_288,
+];
+
 // File extensions we recognize as hdf files.
 const POSSIBLE_HDF_EXTENSIONS_CASE_INSENSITIVE: [&str; 3] = ["h5", "hdf", "hdf5"];
 
@@ -19,3 +35,67 @@ pub fn path_has_hdf_extension(path: &std::path::Path) -> bool {
     }
     false
 }
+
+/// Returns true if the HDF5 signature is found at one of the offsets where the superblock can be located in `buf`
+pub fn is_hdf5(buf: &[u8]) -> bool {
+    HDF5_SIGNATURE_OFFSETS.iter().any(|offset| {
+        buf.get(*offset..offset + HDF5_SIGNATURE.len())
+            .is_some_and(|bytes| bytes == HDF5_SIGNATURE)
+    })
+}
+
+/// Returns true if the file at `path` has the HDF5 signature regardless of the file extension
+///
+/// Only the bytes where the signature can be located are read.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn path_has_hdf5_signature(path: &std::path::Path) -> std::io::Result<bool> {
+    use std::io::{Read, Seek, SeekFrom};
+    let mut file = std::fs::File::open(path)?;
+    let file_len = file.metadata()?.len();
+    let mut signature = [0; HDF5_SIGNATURE.len()];
+    for offset in HDF5_SIGNATURE_OFFSETS {
+        let offset = offset as u64;
+        if offset + HDF5_SIGNATURE.len() as u64 > file_len {
+            break;
+        }
+        file.seek(SeekFrom::Start(offset))?;
+        file.read_exact(&mut signature)?;
+        if signature == HDF5_SIGNATURE {
+            return Ok(true);
+        }
+    }
+    Ok(false)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_hdf5() {
+        let mut buf = vec![0; 1024];
+        assert!(!is_hdf5(&buf));
+        assert!(!is_hdf5(&HDF5_SIGNATURE[..4]));
+        assert!(is_hdf5(&HDF5_SIGNATURE));
+
+        // After a user block
+        buf[512..520].copy_from_slice(&HDF5_SIGNATURE);
+        assert!(is_hdf5(&buf));
+
+        // Not at a valid superblock offset
+        buf.rotate_right(1);
+        assert!(!is_hdf5(&buf));
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    #[test]
+    fn test_path_has_hdf5_signature() -> testresult::TestResult {
+        let bifrost =
+            std::path::Path::new("../../test_data/hdf5/bifrost_current/20240930_100137_bifrost.h5");
+        assert!(path_has_hdf5_signature(bifrost)?);
+        assert!(!path_has_hdf5_signature(std::path::Path::new(
+            "Cargo.toml"
+        ))?);
+        Ok(())
+    }
+}
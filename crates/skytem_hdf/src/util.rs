@@ -1,6 +1,7 @@
+use chrono::{DateTime, Datelike, NaiveDate, NaiveDateTime, NaiveTime, Utc};
 use hdf5::{
     types::{IntSize, TypeDescriptor, VarLenAscii, VarLenUnicode},
-    Attribute,
+    Attribute, Location,
 };
 
 /// Reads an HDF5 attribute's value as a HDF5 string type and returns it as a native [`String`].
@@ -40,6 +41,50 @@ pub fn read_string_attribute(attr: &Attribute) -> hdf5::Result<String> {
     }
 }
 
+/// Writes `value` as a variable-length UTF-8 string attribute, null characters are removed since HDF5 strings can't contain them
+pub(crate) fn write_string_attribute(
+    location: &Location,
+    name: &str,
+    value: &str,
+) -> hdf5::Result<()> {
+    let value: VarLenUnicode = value
+        .replace('\0', "")
+        .parse()
+        .map_err(|e: hdf5::types::StringError| hdf5::Error::from(e.to_string()))?;
+    location
+        .new_attr::<VarLenUnicode>()
+        .shape(())
+        .create(name)?
+        .write_scalar(&value)
+}
+
+/// Writes `values` as a one dimensional attribute of variable-length UTF-8 strings, null characters are removed
+pub(crate) fn write_string_array_attribute(
+    location: &Location,
+    name: &str,
+    values: &[String],
+) -> hdf5::Result<()> {
+    let values = values
+        .iter()
+        .map(|value| value.replace('\0', "").parse())
+        .collect::<Result<Vec<VarLenUnicode>, _>>()
+        .map_err(|e: hdf5::types::StringError| hdf5::Error::from(e.to_string()))?;
+    location
+        .new_attr::<VarLenUnicode>()
+        .shape(values.len())
+        .create(name)?
+        .write(&values)
+}
+
+/// Reads a one dimensional attribute of variable-length UTF-8 strings
+pub(crate) fn read_string_array_attribute(attr: &Attribute) -> hdf5::Result<Vec<String>> {
+    Ok(attr
+        .read_1d::<VarLenUnicode>()?
+        .iter()
+        .map(|value| value.as_str().to_owned())
+        .collect())
+}
+
 /// Reads an HDF5 attribute's value and converts it to a native [`String`].
 pub fn read_any_attribute_to_string(attr: &Attribute) -> hdf5::Result<String> {
     // Get the data type descriptor for the attribute
@@ -113,3 +158,17 @@ pub fn read_any_attribute_to_string(attr: &Attribute) -> hdf5::Result<String> {
         ))),
     }
 }
+
+/// Returns midnight of the 1. january in the current year.
+///
+/// Used as the starting time of data that does not contain any timestamps, hopefully it will be obvious
+/// to the user that it is an auto generated timestamp and not the real one.
+pub fn first_january_this_year() -> DateTime<Utc> {
+    let now = chrono::offset::Local::now();
+    let first_january_this_year = NaiveDate::from_ymd_opt(now.year(), 1, 1).expect("Invalid date");
+    NaiveDateTime::new(
+        first_january_this_year,
+        NaiveTime::from_hms_opt(0, 0, 0).expect("Invalid time"),
+    )
+    .and_utc()
+}
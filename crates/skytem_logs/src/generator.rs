@@ -16,6 +16,7 @@ pub struct GeneratorLog {
     /// timestamps in nanoseconds since the epoch
     timestamps_ns: Vec<f64>,
     all_plots_raw: Vec<RawPlot>,
+    diagnostics: Vec<ParseDiagnostic>,
 }
 
 impl GeneratorLog {
@@ -50,30 +51,24 @@ impl Parseable for GeneratorLog {
         GeneratorLogEntry::is_line_valid_generator_log_entry(&line)
     }
 
+    /// The generator log has no header so detection relies on the first line looking like an entry
+    fn detect(buf: &[u8]) -> Confidence {
+        if Self::is_buf_valid(buf) {
+            Confidence::Medium
+        } else {
+            Confidence::None
+        }
+    }
+
+    fn parse_diagnostics(&self) -> &[ParseDiagnostic] {
+        &self.diagnostics
+    }
+
     fn from_reader(reader: &mut impl io::BufRead) -> io::Result<(Self, usize)> {
-        let mut entries = Vec::new();
-        let mut total_bytes_read = 0;
-
-        // Read the buffer in chunks and handle the line parsing
-        loop {
-            let mut line = String::new();
-            let bytes_read = reader.read_line(&mut line)?;
-
-            // If we didn't read any bytes, we're done
-            if bytes_read == 0 {
-                break;
-            }
-
-            // Create a Cursor from the line string to pass to from_reader
-            let line_bytes = line.as_bytes();
-            match GeneratorLogEntry::from_reader(&mut io::Cursor::new(line_bytes)) {
-                Ok((entry, _)) => {
-                    total_bytes_read += bytes_read;
-                    entries.push(entry);
-                }
-                Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => break,
-                Err(e) => log::warn!("Failed parsing generator log entry: {e}... Continuing"),
-            }
+        let (entries, total_bytes_read, diagnostics): (Vec<GeneratorLogEntry>, usize, _) =
+            parse_to_vec_with_diagnostics(reader, ResyncStrategy::NextLine);
+        for d in &diagnostics {
+            log::warn!("Failed parsing generator log entry: {d}... Continuing");
         }
 
         let mut power_vals: Vec<f64> = Vec::with_capacity(entries.len());
@@ -94,6 +89,7 @@ impl Parseable for GeneratorLog {
                 power: power_vals,
                 all_plots_raw,
                 timestamps_ns,
+                diagnostics,
             },
             total_bytes_read,
         ))
@@ -494,6 +490,12 @@ mod tests {
         let (genlog, bytes_read) = GeneratorLog::from_reader(&mut readable)?;
         assert_eq!(genlog.entries.len(), 1);
         assert!(bytes_read < valid_line_then_invalid_as_bytes.len());
+
+        let diagnostics = genlog.parse_diagnostics();
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].line(), Some(2));
+        assert_eq!(diagnostics[0].byte_offset(), bytes_read);
+        assert_eq!(diagnostics[0].kind(), ParseErrorKind::InvalidData);
         Ok(())
     }
 }
@@ -5,6 +5,7 @@ use log_if::prelude::*;
 pub mod generator;
 pub mod mbed_motor_control;
 pub mod navsys;
+pub mod user_format;
 pub mod util;
 
 /// Parse the unique description string from a 128-byte array
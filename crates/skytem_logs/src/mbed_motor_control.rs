@@ -1,4 +1,5 @@
 pub mod mbed_config;
 pub mod mbed_header;
 pub mod pid;
+pub mod schema;
 pub mod status;
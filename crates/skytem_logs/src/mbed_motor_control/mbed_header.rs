@@ -25,6 +25,18 @@ pub const SIZEOF_GIT_REPO_STATUS: usize = size_of::<GitRepoStatusData>();
 pub type StartupTimestamp = [u8; 20];
 pub const SIZEOF_STARTUP_TIMESTAMP: usize = size_of::<StartupTimestamp>();
 
+/// The versions of the Mbed logs that have a handwritten parser, other versions are parsed with a bundled schema
+const HANDWRITTEN_VERSIONS: [u16; 2] = [1, 2];
+
+/// Returns whether the version following the unique description in `content` has a handwritten parser
+pub(crate) fn is_handwritten_version(content: &[u8]) -> bool {
+    content
+        .get(SIZEOF_UNIQ_DESC..SIZEOF_UNIQ_DESC + 2)
+        .is_some_and(|version| {
+            HANDWRITTEN_VERSIONS.contains(&u16::from_le_bytes([version[0], version[1]]))
+        })
+}
+
 pub trait MbedMotorControlLogHeader: GitMetadata + Sized + Display + Send + Sync + Clone {
     /// Size of the header type in bytes if represented in raw binary
     const VERSION: u16;
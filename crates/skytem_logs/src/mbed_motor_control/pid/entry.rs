@@ -16,6 +16,11 @@ pub struct PidLogEntry {
     pub first_valid_rpm_count: u32,
 }
 
+impl PidLogEntry {
+    /// Size in bytes of an entry as it is stored in the log
+    pub(crate) const RAW_SIZE: usize = 24;
+}
+
 impl LogEntry for PidLogEntry {
     fn from_reader(reader: &mut impl io::BufRead) -> io::Result<(Self, usize)> {
         // Start with 0 bytes read
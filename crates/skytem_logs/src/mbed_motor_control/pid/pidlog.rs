@@ -10,7 +10,7 @@ use std::{
 use crate::{
     mbed_motor_control::{
         mbed_config::MbedConfig,
-        mbed_header::{MbedMotorControlLogHeader, SIZEOF_UNIQ_DESC},
+        mbed_header::{is_handwritten_version, MbedMotorControlLogHeader, SIZEOF_UNIQ_DESC},
     },
     parse_unique_description,
 };
@@ -24,6 +24,7 @@ pub struct PidLog {
     timestamps_ns: Vec<f64>,
     all_plots_raw: Vec<RawPlot>,
     startup_timestamp: DateTime<Utc>,
+    diagnostics: Vec<ParseDiagnostic>,
 }
 
 impl PidLog {
@@ -60,6 +61,11 @@ impl Parseable for PidLog {
 
         let unique_description = &content[..SIZEOF_UNIQ_DESC];
         parse_unique_description(unique_description) == super::UNIQUE_DESCRIPTION
+            && is_handwritten_version(content)
+    }
+
+    fn parse_diagnostics(&self) -> &[ParseDiagnostic] {
+        &self.diagnostics
     }
 
     fn from_reader(reader: &mut impl io::BufRead) -> io::Result<(Self, usize)> {
@@ -76,7 +82,9 @@ impl Parseable for PidLog {
             .timestamp_nanos_opt()
             .expect("timestamp as nanoseconds out of range")
             as f64;
-        let (vec_of_entries, entries_bytes_read): (Vec<PidLogEntry>, usize) = parse_to_vec(reader);
+        let (vec_of_entries, entries_bytes_read, mut diagnostics): (Vec<PidLogEntry>, usize, _) =
+            parse_to_vec_with_diagnostics(reader, ResyncStrategy::SkipBytes(PidLogEntry::RAW_SIZE));
+        offset_diagnostics(&mut diagnostics, total_bytes_read, 0, startup_timestamp_ns);
         let timestamps_ns: Vec<f64> = vec_of_entries
             .iter()
             .map(|e| startup_timestamp_ns + e.timestamp_ns())
@@ -150,6 +158,7 @@ impl Parseable for PidLog {
                 timestamps_ns,
                 all_plots_raw,
                 startup_timestamp,
+                diagnostics,
             },
             total_bytes_read,
         ))
@@ -266,6 +275,17 @@ mod tests {
     const TEST_DATA_V2: &str =
         "../../test_data/mbed_motor_control/v2/20241014_080729/pid_20241014_080729_00.bin";
 
+    #[test]
+    fn test_test_data_has_no_diagnostics() -> TestResult {
+        // The logs are padded and end in the middle of an entry, which is not a parse failure
+        for path in [TEST_DATA_V1, TEST_DATA_V2] {
+            let data = fs::read(path)?;
+            let (log, _) = PidLog::from_reader(&mut data.as_slice())?;
+            assert_eq!(log.parse_diagnostics(), [], "{path}");
+        }
+        Ok(())
+    }
+
     #[test]
     fn test_deserialize_v1() -> TestResult {
         let data = fs::read(TEST_DATA_V1)?;
@@ -0,0 +1,504 @@
+//! Declarative description of Mbed-style binary logs.
+//!
+//! An Mbed-style log starts with a header consisting of a 128 byte unique description and a `u16` version,
+//! followed by the fields declared in the schema header. The header is followed by fixed size entries with the fields
+//! declared in the schema entry. Plots are declared as mappings from an entry field to a [`RawPlot`].
+//!
+//! Entries that have a value outside the `valid` range of a field are skipped and reported as [`ParseDiagnostic`]s,
+//! while a partial entry at the end of the log is padding and ignored.
+//!
+//! Example of a schema in TOML:
+//!
+//! ```toml
+//! name = "Mbed PID v3"
+//! unique_description = "MBED-MOTOR-CONTROL-PID-LOG-2024"
+//! version = 3
+//! endianness = "little"
+//!
+//! [header]
+//! startup_timestamp = "startup_timestamp"
+//! fields = [
+//!     { name = "project_version", type = "str", len = 32, label = "Project Version" },
+//!     { name = "startup_timestamp", type = "str", len = 20 },
+//!     { name = "kp", type = "f32", label = "Kp" },
+//! ]
+//!
+//! [entry]
+//! timestamp = "timestamp_ms"
+//! timestamp_unit = "ms"
+//! fields = [
+//!     { name = "timestamp_ms", type = "u32" },
+//!     { name = "rpm", type = "f32", valid = [0, 20000] },
+//! ]
+//!
+//! [[plots]]
+//! name = "RPM"
+//! field = "rpm"
+//! range = "Thousands"
+//! ```
+
+use std::io;
+
+use byteorder::{BigEndian, ByteOrder, LittleEndian, ReadBytesExt};
+use log_if::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::parse_unique_description;
+
+use super::mbed_header::SIZEOF_UNIQ_DESC;
+
+pub mod log;
+
+pub use log::SchemaLog;
+
+/// Schemas bundled with the application, a new version of an Mbed log is supported by adding its schema here.
+///
+/// Versions that also have a handwritten parser are parsed by the handwritten parser.
+const BUNDLED_SCHEMAS: [&str; 1] = [include_str!("../../schemas/mbed_pid_v2.toml")];
+
+/// The [`MbedLogSchema`]s bundled with the application
+pub fn bundled_schemas() -> Vec<MbedLogSchema> {
+    BUNDLED_SCHEMAS
+        .iter()
+        .map(|toml_str| MbedLogSchema::from_toml(toml_str).expect("bundled schemas are valid"))
+        .collect()
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Endianness {
+    #[default]
+    Little,
+    Big,
+}
+
+/// The type of a field as it is stored in the log
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FieldType {
+    U8,
+    U16,
+    U32,
+    U64,
+    I8,
+    I16,
+    I32,
+    I64,
+    F32,
+    F64,
+    /// A nul-padded string of `len` bytes
+    Str,
+    /// `len` bytes that are not interpreted, e.g. padding or reserved space
+    Bytes,
+}
+
+impl FieldType {
+    /// Size in bytes of a numeric type, [`None`] for types where the size is declared with `len`
+    fn numeric_size(self) -> Option<usize> {
+        match self {
+            Self::U8 | Self::I8 => Some(1),
+            Self::U16 | Self::I16 => Some(2),
+            Self::U32 | Self::I32 | Self::F32 => Some(4),
+            Self::U64 | Self::I64 | Self::F64 => Some(8),
+            Self::Str | Self::Bytes => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct FieldDef {
+    name: String,
+    #[serde(rename = "type")]
+    ty: FieldType,
+    /// Length in bytes of `str` and `bytes` fields
+    #[serde(default)]
+    len: Option<usize>,
+    /// Header fields with a label are shown in the metadata of the log
+    #[serde(default)]
+    label: Option<String>,
+    /// Inclusive range of valid values of a numeric field, a value outside of it is reported as corrupt data
+    #[serde(default)]
+    valid: Option<[f64; 2]>,
+}
+
+impl FieldDef {
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn field_type(&self) -> FieldType {
+        self.ty
+    }
+
+    pub fn label(&self) -> Option<&str> {
+        self.label.as_deref()
+    }
+
+    /// Size of the field in bytes as it is stored in the log
+    pub fn size(&self) -> usize {
+        self.ty
+            .numeric_size()
+            .unwrap_or(self.len.unwrap_or_default())
+    }
+
+    /// Returns an error if the value is outside the valid range of the field
+    fn check_valid(&self, value: &FieldValue) -> io::Result<()> {
+        match (self.valid, value.as_f64()) {
+            (Some([min, max]), Some(num)) if !(min..=max).contains(&num) => Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!(
+                    "'{}' is {num}, outside of the valid range [{min}, {max}]",
+                    self.name
+                ),
+            )),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// A value read from a field
+#[derive(Debug, Clone, PartialEq)]
+pub enum FieldValue {
+    Number(f64),
+    Str(String),
+    Bytes(Vec<u8>),
+}
+
+impl FieldValue {
+    pub fn as_f64(&self) -> Option<f64> {
+        match self {
+            Self::Number(n) => Some(*n),
+            Self::Str(_) | Self::Bytes(_) => None,
+        }
+    }
+}
+
+impl std::fmt::Display for FieldValue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Number(n) => write!(f, "{n}"),
+            Self::Str(s) => write!(f, "{s}"),
+            Self::Bytes(b) => write!(f, "{b:02X?}"),
+        }
+    }
+}
+
+/// The header fields following the unique description and version
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct HeaderSchema {
+    /// Name of the `str` field containing the startup timestamp of the log
+    startup_timestamp: String,
+    #[serde(default = "default_startup_timestamp_format")]
+    startup_timestamp_format: String,
+    fields: Vec<FieldDef>,
+}
+
+fn default_startup_timestamp_format() -> String {
+    "%Y-%m-%dT%H:%M:%S".to_owned()
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TimeUnit {
+    Ns,
+    Us,
+    #[default]
+    Ms,
+    S,
+}
+
+impl TimeUnit {
+    pub fn to_ns_factor(self) -> f64 {
+        match self {
+            Self::Ns => 1.0,
+            Self::Us => 1_000.0,
+            Self::Ms => 1_000_000.0,
+            Self::S => 1_000_000_000.0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct EntrySchema {
+    /// Name of the field containing the timestamp of the entry relative to the startup timestamp
+    timestamp: String,
+    #[serde(default)]
+    timestamp_unit: TimeUnit,
+    fields: Vec<FieldDef>,
+}
+
+/// Maps an entry field to a plot
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct PlotMapping {
+    pub(crate) name: String,
+    pub(crate) field: String,
+    pub(crate) range: ExpectedPlotRange,
+    /// The field value is multiplied by the scale before plotting
+    #[serde(default = "default_scale")]
+    pub(crate) scale: f64,
+}
+
+fn default_scale() -> f64 {
+    1.0
+}
+
+/// Describes an Mbed-style binary log such that it can be parsed without writing a parser for it
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct MbedLogSchema {
+    name: String,
+    unique_description: String,
+    version: u16,
+    #[serde(default)]
+    endianness: Endianness,
+    header: HeaderSchema,
+    entry: EntrySchema,
+    plots: Vec<PlotMapping>,
+}
+
+impl MbedLogSchema {
+    /// Deserialize and validate a schema from TOML
+    pub fn from_toml(toml_str: &str) -> io::Result<Self> {
+        let schema: Self = toml::from_str(toml_str)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e.to_string()))?;
+        schema.validate()?;
+        Ok(schema)
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn unique_description(&self) -> &str {
+        &self.unique_description
+    }
+
+    pub fn version(&self) -> u16 {
+        self.version
+    }
+
+    /// Size in bytes of a single entry
+    pub fn entry_size(&self) -> usize {
+        self.entry.fields.iter().map(FieldDef::size).sum()
+    }
+
+    /// Returns whether the buffer starts with the unique description and version of the schema
+    pub fn is_buf_valid(&self, buf: &[u8]) -> bool {
+        if buf.len() < SIZEOF_UNIQ_DESC + 2 {
+            return false;
+        }
+        let (unique_description, version) = buf.split_at(SIZEOF_UNIQ_DESC);
+        let version = match self.endianness {
+            Endianness::Little => LittleEndian::read_u16(version),
+            Endianness::Big => BigEndian::read_u16(version),
+        };
+        parse_unique_description(unique_description) == self.unique_description
+            && version == self.version
+    }
+
+    pub(crate) fn validate(&self) -> io::Result<()> {
+        let invalid = |msg: String| {
+            Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("Invalid schema '{}': {msg}", self.name),
+            ))
+        };
+        for field in self.header.fields.iter().chain(&self.entry.fields) {
+            if field.ty.numeric_size().is_none() && field.len.is_none() {
+                return invalid(format!("'{}' is missing a length", field.name));
+            }
+            if field.valid.is_some() && field.ty.numeric_size().is_none() {
+                return invalid(format!(
+                    "'{}' has a valid range but is not numeric",
+                    field.name
+                ));
+            }
+        }
+        match self.header_field(&self.header.startup_timestamp) {
+            Some(f) if f.ty == FieldType::Str => (),
+            Some(_) => {
+                return invalid(format!(
+                    "startup timestamp '{}' is not a str field",
+                    self.header.startup_timestamp
+                ))
+            }
+            None => {
+                return invalid(format!(
+                    "no header field named '{}'",
+                    self.header.startup_timestamp
+                ))
+            }
+        }
+        for field_name in
+            std::iter::once(&self.entry.timestamp).chain(self.plots.iter().map(|p| &p.field))
+        {
+            match self.entry_field_index(field_name) {
+                Some(idx) if self.entry.fields[idx].ty.numeric_size().is_some() => (),
+                Some(_) => return invalid(format!("entry field '{field_name}' is not numeric")),
+                None => return invalid(format!("no entry field named '{field_name}'")),
+            }
+        }
+        if self.entry_size() == 0 {
+            return invalid("entries have a size of 0 bytes".to_owned());
+        }
+        Ok(())
+    }
+
+    fn header_field(&self, name: &str) -> Option<&FieldDef> {
+        self.header.fields.iter().find(|f| f.name == name)
+    }
+
+    fn entry_field_index(&self, name: &str) -> Option<usize> {
+        self.entry.fields.iter().position(|f| f.name == name)
+    }
+
+    /// Read a field according to the endianness of the schema
+    fn read_field(&self, reader: &mut impl io::Read, field: &FieldDef) -> io::Result<FieldValue> {
+        let value = match self.endianness {
+            Endianness::Little => read_field::<LittleEndian>(reader, field),
+            Endianness::Big => read_field::<BigEndian>(reader, field),
+        }?;
+        field.check_valid(&value)?;
+        Ok(value)
+    }
+}
+
+fn read_field<B: ByteOrder>(
+    reader: &mut impl io::Read,
+    field: &FieldDef,
+) -> io::Result<FieldValue> {
+    let num = match field.ty {
+        FieldType::U8 => reader.read_u8()?.into(),
+        FieldType::U16 => reader.read_u16::<B>()?.into(),
+        FieldType::U32 => reader.read_u32::<B>()?.into(),
+        FieldType::U64 => reader.read_u64::<B>()? as f64,
+        FieldType::I8 => reader.read_i8()?.into(),
+        FieldType::I16 => reader.read_i16::<B>()?.into(),
+        FieldType::I32 => reader.read_i32::<B>()?.into(),
+        FieldType::I64 => reader.read_i64::<B>()? as f64,
+        FieldType::F32 => reader.read_f32::<B>()?.into(),
+        FieldType::F64 => reader.read_f64::<B>()?,
+        FieldType::Str | FieldType::Bytes => {
+            let mut buf = vec![0; field.size()];
+            reader.read_exact(&mut buf)?;
+            return Ok(if field.ty == FieldType::Str {
+                FieldValue::Str(
+                    String::from_utf8_lossy(&buf)
+                        .trim_end_matches(char::from(0))
+                        .to_owned(),
+                )
+            } else {
+                FieldValue::Bytes(buf)
+            });
+        }
+    };
+    Ok(FieldValue::Number(num))
+}
+
+/// Registers an [`MbedLogSchema`] as a [`LogFormat`]
+#[derive(Debug, Clone)]
+pub struct MbedSchemaFormat {
+    schema: MbedLogSchema,
+    description: String,
+}
+
+impl MbedSchemaFormat {
+    pub fn new(schema: MbedLogSchema) -> Self {
+        let description = format!("{} v{} (schema)", schema.unique_description, schema.version);
+        Self {
+            schema,
+            description,
+        }
+    }
+
+    pub fn schema(&self) -> &MbedLogSchema {
+        &self.schema
+    }
+}
+
+impl LogFormat for MbedSchemaFormat {
+    fn name(&self) -> &str {
+        &self.schema.name
+    }
+
+    fn description(&self) -> &str {
+        &self.description
+    }
+
+    fn detect(&self, buf: &[u8]) -> Confidence {
+        if self.schema.is_buf_valid(buf) {
+            Confidence::High
+        } else {
+            Confidence::None
+        }
+    }
+
+    fn parse(&self, buf: &[u8]) -> io::Result<ParsedLog> {
+        let (log, parsed_bytes) = SchemaLog::from_reader(&self.schema, &mut io::Cursor::new(buf))?;
+        let diagnostics = log.parse_diagnostics().to_vec();
+        Ok(ParsedLog {
+            log: Box::new(log),
+            parsed_bytes,
+            total_bytes: buf.len(),
+            diagnostics,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use testresult::TestResult;
+
+    pub(super) const PID_V2_SCHEMA: &str = BUNDLED_SCHEMAS[0];
+
+    #[test]
+    fn test_deserialize_pid_v2_schema() -> TestResult {
+        let schema = MbedLogSchema::from_toml(PID_V2_SCHEMA)?;
+        assert_eq!(schema.version(), 2);
+        assert_eq!(
+            schema.unique_description(),
+            super::super::pid::UNIQUE_DESCRIPTION
+        );
+        assert_eq!(schema.entry_size(), 24);
+        Ok(())
+    }
+
+    #[test]
+    fn test_bundled_schemas_are_valid_and_unique() {
+        let schemas = bundled_schemas();
+        for (idx, schema) in schemas.iter().enumerate() {
+            assert!(
+                !schemas[..idx]
+                    .iter()
+                    .any(|other| other.name() == schema.name()
+                        || (other.unique_description() == schema.unique_description()
+                            && other.version() == schema.version())),
+                "'{}' is bundled twice",
+                schema.name()
+            );
+        }
+    }
+
+    #[test]
+    fn test_invalid_schema_is_rejected() {
+        let missing_plot_field = PID_V2_SCHEMA.replace(r#"field = "rpm""#, r#"field = "rpms""#);
+        let err = MbedLogSchema::from_toml(&missing_plot_field).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        assert!(err.to_string().contains("rpms"), "{err}");
+    }
+
+    #[test]
+    fn test_read_big_endian_field() -> TestResult {
+        let field = FieldDef {
+            name: "val".to_owned(),
+            ty: FieldType::I16,
+            len: None,
+            label: None,
+            valid: None,
+        };
+        let val = read_field::<BigEndian>(&mut [0xFF, 0xFE].as_slice(), &field)?;
+        assert_eq!(val, FieldValue::Number(-2.0));
+        Ok(())
+    }
+}
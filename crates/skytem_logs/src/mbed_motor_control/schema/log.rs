@@ -0,0 +1,272 @@
+use std::io;
+
+use chrono::{DateTime, Utc};
+use log_if::prelude::*;
+
+use crate::{mbed_motor_control::mbed_header::SIZEOF_UNIQ_DESC, util::timestamp_from_raw};
+
+use super::{FieldValue, MbedLogSchema};
+
+/// A log parsed according to an [`MbedLogSchema`]
+#[derive(Debug, Clone)]
+pub struct SchemaLog {
+    descriptive_name: String,
+    startup_timestamp: DateTime<Utc>,
+    metadata: Vec<(String, String)>,
+    raw_plots: Vec<RawPlot>,
+    diagnostics: Vec<ParseDiagnostic>,
+}
+
+impl SchemaLog {
+    /// Parse a log described by the `schema` and return it along with the number of bytes read
+    pub fn from_reader(
+        schema: &MbedLogSchema,
+        reader: &mut impl io::BufRead,
+    ) -> io::Result<(Self, usize)> {
+        let mut unique_description_and_version = [0; SIZEOF_UNIQ_DESC + 2];
+        reader.read_exact(&mut unique_description_and_version)?;
+        if !schema.is_buf_valid(&unique_description_and_version) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("Not a valid '{}'", schema.name),
+            ));
+        }
+        let mut total_bytes_read = unique_description_and_version.len();
+
+        let mut metadata = vec![];
+        let mut startup_timestamp = None;
+        for field in &schema.header.fields {
+            let value = schema.read_field(reader, field)?;
+            total_bytes_read += field.size();
+            if field.name == schema.header.startup_timestamp {
+                let FieldValue::Str(ts) = &value else {
+                    unreachable!("startup timestamp is validated to be a str field");
+                };
+                startup_timestamp = Some(
+                    timestamp_from_raw(ts.as_bytes(), &schema.header.startup_timestamp_format)
+                        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?
+                        .and_utc(),
+                );
+            }
+            if let Some(label) = field.label() {
+                metadata.push((label.to_owned(), value.to_string()));
+            }
+        }
+        let startup_timestamp =
+            startup_timestamp.expect("startup timestamp field is validated to be in the header");
+        metadata.push((
+            "Startup Timestamp".to_owned(),
+            startup_timestamp.naive_utc().to_string(),
+        ));
+
+        let startup_timestamp_ns = startup_timestamp
+            .timestamp_nanos_opt()
+            .expect("timestamp as nanoseconds out of range")
+            as f64;
+        let (columns, entries_bytes_read, mut diagnostics) = read_entry_columns(schema, reader);
+        offset_diagnostics(&mut diagnostics, total_bytes_read, 0, startup_timestamp_ns);
+        total_bytes_read += entries_bytes_read;
+
+        let raw_plots = build_raw_plots(schema, &columns, startup_timestamp_ns);
+
+        Ok((
+            Self {
+                descriptive_name: schema.name.clone(),
+                startup_timestamp,
+                metadata,
+                raw_plots,
+                diagnostics,
+            },
+            total_bytes_read,
+        ))
+    }
+
+    /// The entries that were skipped because they ended early or were corrupt
+    pub fn parse_diagnostics(&self) -> &[ParseDiagnostic] {
+        &self.diagnostics
+    }
+}
+
+/// Read all entries and return the values of each field as columns, indexed like the entry fields.
+///
+/// Entries that fail to parse are skipped and reported as [`ParseDiagnostic`]s relative to the start of the entries.
+fn read_entry_columns(
+    schema: &MbedLogSchema,
+    reader: &mut impl io::BufRead,
+) -> (Vec<Vec<f64>>, usize, Vec<ParseDiagnostic>) {
+    let entry_size = schema.entry_size();
+    let timestamp_idx = schema
+        .entry_field_index(&schema.entry.timestamp)
+        .expect("timestamp field is validated to be in the entry");
+    let to_ns = schema.entry.timestamp_unit.to_ns_factor();
+    let (entries, bytes_read, diagnostics) = parse_with_diagnostics(
+        reader,
+        ResyncStrategy::SkipBytes(entry_size),
+        |raw_entry| {
+            let entry = schema
+                .entry
+                .fields
+                .iter()
+                // Non-numeric fields like padding are read to advance the reader but have no value
+                .map(|field| {
+                    Ok(schema
+                        .read_field(raw_entry, field)?
+                        .as_f64()
+                        .unwrap_or(f64::NAN))
+                })
+                .collect::<io::Result<Vec<f64>>>()?;
+            Ok((entry, entry_size))
+        },
+        |entry| entry[timestamp_idx] * to_ns,
+    );
+
+    let mut columns: Vec<Vec<f64>> = schema
+        .entry
+        .fields
+        .iter()
+        .map(|_| Vec::with_capacity(entries.len()))
+        .collect();
+    for entry in entries {
+        for (column, value) in columns.iter_mut().zip(entry) {
+            column.push(value);
+        }
+    }
+    (columns, bytes_read, diagnostics)
+}
+
+fn build_raw_plots(
+    schema: &MbedLogSchema,
+    columns: &[Vec<f64>],
+    startup_timestamp_ns: f64,
+) -> Vec<RawPlot> {
+    let timestamp_column = &columns[schema
+        .entry_field_index(&schema.entry.timestamp)
+        .expect("timestamp field is validated to be in the entry")];
+    let to_ns = schema.entry.timestamp_unit.to_ns_factor();
+    schema
+        .plots
+        .iter()
+        .map(|plot| {
+            let column = &columns[schema
+                .entry_field_index(&plot.field)
+                .expect("plot fields are validated to be in the entry")];
+            let points = timestamp_column
+                .iter()
+                .zip(column)
+                .map(|(ts, val)| [startup_timestamp_ns + ts * to_ns, val * plot.scale])
+                .collect();
+            RawPlot::new(plot.name.clone(), points, plot.range)
+        })
+        .collect()
+}
+
+impl Plotable for SchemaLog {
+    fn raw_plots(&self) -> &[RawPlot] {
+        &self.raw_plots
+    }
+
+    fn first_timestamp(&self) -> DateTime<Utc> {
+        self.startup_timestamp
+    }
+
+    fn descriptive_name(&self) -> &str {
+        &self.descriptive_name
+    }
+
+    fn labels(&self) -> Option<&[PlotLabels]> {
+        None
+    }
+
+    fn metadata(&self) -> Option<Vec<(String, String)>> {
+        Some(self.metadata.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mbed_motor_control::{pid::pidlog::PidLog, schema::FieldDef};
+    use pretty_assertions::assert_eq;
+    use testresult::TestResult;
+
+    const TEST_DATA: &str =
+        "../../test_data/mbed_motor_control/v2/20241014_080729/pid_20241014_080729_00.bin";
+
+    #[test]
+    fn test_schema_log_matches_handwritten_pid_log() -> TestResult {
+        let schema = MbedLogSchema::from_toml(super::super::tests::PID_V2_SCHEMA)?;
+        let data = std::fs::read(TEST_DATA)?;
+        let (schema_log, schema_bytes_read) =
+            SchemaLog::from_reader(&schema, &mut data.as_slice())?;
+        let (pid_log, pid_bytes_read) = PidLog::from_reader(&mut data.as_slice())?;
+
+        assert_eq!(schema_bytes_read, pid_bytes_read);
+        assert_eq!(schema_log.first_timestamp(), pid_log.first_timestamp());
+        assert_eq!(schema_log.raw_plots(), pid_log.raw_plots());
+
+        let metadata = schema_log.metadata().unwrap();
+        assert!(metadata.contains(&("Project Version".to_owned(), "2.3.2".to_owned())));
+        assert!(metadata.contains(&("Kp".to_owned(), "3".to_owned())));
+        Ok(())
+    }
+
+    fn header_size(schema: &MbedLogSchema) -> usize {
+        SIZEOF_UNIQ_DESC
+            + 2
+            + schema
+                .header
+                .fields
+                .iter()
+                .map(FieldDef::size)
+                .sum::<usize>()
+    }
+
+    #[test]
+    fn test_partial_entry_at_the_end_is_not_reported() -> TestResult {
+        let schema = MbedLogSchema::from_toml(super::super::tests::PID_V2_SCHEMA)?;
+        // The test data ends in the middle of an entry, like any log that is padded to a block size
+        let data = std::fs::read(TEST_DATA)?;
+        let partial_entry_len = (data.len() - header_size(&schema)) % schema.entry_size();
+        assert_ne!(partial_entry_len, 0);
+
+        let (schema_log, bytes_read) = SchemaLog::from_reader(&schema, &mut data.as_slice())?;
+
+        assert_eq!(bytes_read, data.len() - partial_entry_len);
+        assert_eq!(schema_log.parse_diagnostics(), []);
+        Ok(())
+    }
+
+    #[test]
+    fn test_entry_outside_valid_range_is_skipped() -> TestResult {
+        let schema = MbedLogSchema::from_toml(&super::super::tests::PID_V2_SCHEMA.replace(
+            r#"{ name = "rpm", type = "f32" }"#,
+            r#"{ name = "rpm", type = "f32", valid = [0, 100000] }"#,
+        ))?;
+        let mut data = std::fs::read(TEST_DATA)?;
+        let corrupt_entry_offset = header_size(&schema) + 3 * schema.entry_size();
+        // The RPM follows the u32 timestamp
+        data[corrupt_entry_offset + 4..corrupt_entry_offset + 8]
+            .copy_from_slice(&(-1.0_f32).to_le_bytes());
+
+        let (schema_log, _) = SchemaLog::from_reader(&schema, &mut data.as_slice())?;
+        let (pid_log, _) = PidLog::from_reader(&mut data.as_slice())?;
+
+        let [diagnostic] = schema_log.parse_diagnostics() else {
+            panic!("Expected a diagnostic for the corrupt entry");
+        };
+        assert_eq!(diagnostic.kind(), ParseErrorKind::InvalidData);
+        assert_eq!(diagnostic.byte_offset(), corrupt_entry_offset);
+        assert_eq!(diagnostic.skipped_bytes(), schema.entry_size());
+        assert!(
+            diagnostic.message().contains("rpm"),
+            "{}",
+            diagnostic.message()
+        );
+        // The entries after the corrupt entry are still parsed
+        assert_eq!(
+            schema_log.raw_plots()[0].points().len(),
+            pid_log.raw_plots()[0].points().len() - 1
+        );
+        Ok(())
+    }
+}
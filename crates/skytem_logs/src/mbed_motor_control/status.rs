@@ -1,4 +1,4 @@
-mod entry;
+pub mod entry;
 mod header;
 pub mod statuslog;
 
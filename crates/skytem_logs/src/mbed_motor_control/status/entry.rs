@@ -54,6 +54,11 @@ impl fmt::Display for StatusLogEntry {
     }
 }
 
+impl StatusLogEntry {
+    /// Size in bytes of an entry as it is stored in the log
+    pub(crate) const RAW_SIZE: usize = 18;
+}
+
 impl LogEntry for StatusLogEntry {
     fn from_reader(reader: &mut impl io::BufRead) -> io::Result<(Self, usize)> {
         // Start with 0 bytes read
@@ -10,7 +10,7 @@ use std::{
 use crate::{
     mbed_motor_control::{
         mbed_config::MbedConfig,
-        mbed_header::{MbedMotorControlLogHeader, SIZEOF_UNIQ_DESC},
+        mbed_header::{is_handwritten_version, MbedMotorControlLogHeader, SIZEOF_UNIQ_DESC},
     },
     parse_unique_description,
 };
@@ -25,6 +25,7 @@ pub struct StatusLog {
     labels: Vec<PlotLabels>,
     all_plots_raw: Vec<RawPlot>,
     startup_timestamp: DateTime<Utc>,
+    diagnostics: Vec<ParseDiagnostic>,
 }
 
 impl StatusLog {
@@ -117,13 +118,23 @@ impl Parseable for StatusLog {
 
         let unique_description = &content[..SIZEOF_UNIQ_DESC];
         parse_unique_description(unique_description) == super::UNIQUE_DESCRIPTION
+            && is_handwritten_version(content)
+    }
+
+    fn parse_diagnostics(&self) -> &[ParseDiagnostic] {
+        &self.diagnostics
     }
 
     fn from_reader(reader: &mut impl io::BufRead) -> io::Result<(Self, usize)> {
         let mut total_bytes_read: usize = 0;
         let (header, bytes_read) = StatusLogHeader::from_reader(reader)?;
         total_bytes_read += bytes_read;
-        let (vec_of_entries, entry_bytes_read): (Vec<StatusLogEntry>, usize) = parse_to_vec(reader);
+        let header_bytes_read = total_bytes_read;
+        let (vec_of_entries, entry_bytes_read, mut diagnostics): (Vec<StatusLogEntry>, usize, _) =
+            parse_to_vec_with_diagnostics(
+                reader,
+                ResyncStrategy::SkipBytes(StatusLogEntry::RAW_SIZE),
+            );
         total_bytes_read += entry_bytes_read;
         let startup_timestamp = match header {
             StatusLogHeader::V1(h) => h.startup_timestamp(),
@@ -135,6 +146,7 @@ impl Parseable for StatusLog {
             .timestamp_nanos_opt()
             .expect("timestamp as nanoseconds out of range")
             as f64;
+        offset_diagnostics(&mut diagnostics, header_bytes_read, 0, startup_timestamp_ns);
         let timestamp_ns: Vec<f64> = vec_of_entries
             .iter()
             .map(|e| startup_timestamp_ns + e.timestamp_ns())
@@ -167,6 +179,7 @@ impl Parseable for StatusLog {
                 timestamp_ns,
                 all_plots_raw,
                 startup_timestamp,
+                diagnostics,
             },
             total_bytes_read,
         ))
@@ -312,6 +325,17 @@ mod tests {
 
     use crate::{mbed_motor_control::status::entry::MotorState, parse_and_display_log_entries};
 
+    #[test]
+    fn test_test_data_has_no_diagnostics() -> TestResult {
+        // The logs are padded and end in the middle of an entry, which is not a parse failure
+        for path in [TEST_DATA_V1, TEST_DATA_V2] {
+            let data = fs::read(path)?;
+            let (log, _) = StatusLog::from_reader(&mut data.as_slice())?;
+            assert_eq!(log.parse_diagnostics(), [], "{path}");
+        }
+        Ok(())
+    }
+
     #[test]
     fn test_deserialize_v1() -> TestResult {
         let data = fs::read(TEST_DATA_V1)?;
@@ -18,6 +18,7 @@ pub struct NavSysSps {
     header: NavSysSpsHeader,
     entries: Vec<NavSysSpsEntry>,
     raw_plots: Vec<RawPlot>,
+    diagnostics: Vec<ParseDiagnostic>,
 }
 
 impl NavSysSps {
@@ -324,8 +325,15 @@ impl Parseable for NavSysSps {
         let (header, bytes_read) = NavSysSpsHeader::from_reader(reader)?;
         total_bytes_read += bytes_read;
 
-        let (entries, bytes_read) = parse_to_vec(reader);
-        total_bytes_read += bytes_read;
+        let (entries, entries_bytes_read, mut diagnostics) =
+            parse_to_vec_with_diagnostics(reader, ResyncStrategy::NextLine);
+        offset_diagnostics(
+            &mut diagnostics,
+            total_bytes_read,
+            NavSysSpsHeader::LINE_COUNT,
+            0.0,
+        );
+        total_bytes_read += entries_bytes_read;
 
         let raw_plots = Self::build_raw_plots(&entries);
 
@@ -334,6 +342,7 @@ impl Parseable for NavSysSps {
                 header,
                 entries,
                 raw_plots,
+                diagnostics,
             },
             total_bytes_read,
         ))
@@ -343,6 +352,10 @@ impl Parseable for NavSysSps {
         let mut reader = BufReader::new(buf);
         NavSysSpsHeader::from_reader(&mut reader).is_ok()
     }
+
+    fn parse_diagnostics(&self) -> &[ParseDiagnostic] {
+        &self.diagnostics
+    }
 }
 
 impl GitMetadata for NavSysSps {
@@ -17,6 +17,9 @@ pub(crate) struct NavSysSpsHeader {
 }
 
 impl NavSysSpsHeader {
+    /// Number of lines that make up the header
+    pub(crate) const LINE_COUNT: usize = 11;
+
     pub(crate) fn version(&self) -> u32 {
         self.version
     }
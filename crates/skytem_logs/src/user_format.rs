@@ -0,0 +1,92 @@
+//! Format definitions that are loaded at runtime, such that ad hoc logs can be plotted without recompiling.
+//!
+//! A definition is a TOML file where `kind` selects how the log is laid out:
+//!
+//! - `kind = "mbed"`: A binary log with an Mbed-style header, described by an [`MbedLogSchema`]
+//! - `kind = "text"`: A text log with one delimited entry per line, described by a [`TextLogSchema`]
+
+use std::io;
+
+use log_if::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::mbed_motor_control::schema::{MbedLogSchema, MbedSchemaFormat};
+
+pub mod text;
+
+pub use text::{TextLogFormat, TextLogSchema};
+
+/// File extension of format definition files
+pub const FORMAT_DEFINITION_EXTENSION: &str = "toml";
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(tag = "kind", rename_all = "lowercase")]
+pub enum UserFormatDefinition {
+    Mbed(MbedLogSchema),
+    Text(TextLogSchema),
+}
+
+impl UserFormatDefinition {
+    /// Deserialize and validate a format definition from TOML
+    pub fn from_toml(toml_str: &str) -> io::Result<Self> {
+        let definition: Self = toml::from_str(toml_str)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e.to_string()))?;
+        match &definition {
+            Self::Mbed(schema) => schema.validate()?,
+            Self::Text(schema) => schema.validate()?,
+        }
+        Ok(definition)
+    }
+
+    pub fn name(&self) -> &str {
+        match self {
+            Self::Mbed(schema) => schema.name(),
+            Self::Text(schema) => schema.name(),
+        }
+    }
+
+    /// Register the format such that matching content is detected and parsed by the `registry`, replacing a
+    /// definition of the same name. Content that a built-in format is as confident about is still parsed by the
+    /// built-in format.
+    ///
+    /// # Errors
+    /// If a built-in format has the same name
+    pub fn register(self, registry: &mut FormatRegistry) -> io::Result<()> {
+        match self {
+            Self::Mbed(schema) => registry.register_user_defined(MbedSchemaFormat::new(schema)),
+            Self::Text(schema) => registry.register_user_defined(TextLogFormat::new(schema)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use testresult::TestResult;
+
+    #[test]
+    fn test_mbed_definition() -> TestResult {
+        let toml_str = format!(
+            "kind = \"mbed\"\n{}",
+            include_str!("../schemas/mbed_pid_v2.toml")
+        );
+        let definition = UserFormatDefinition::from_toml(&toml_str)?;
+        assert_eq!(definition.name(), "Mbed PID v2");
+
+        let mut registry = FormatRegistry::new();
+        definition.register(&mut registry)?;
+        let data = std::fs::read(
+            "../../test_data/mbed_motor_control/v2/20241014_080729/pid_20241014_080729_00.bin",
+        )?;
+        let parsed = registry.parse(&data)?;
+        assert_eq!(parsed.log.descriptive_name(), "Mbed PID v2");
+        Ok(())
+    }
+
+    #[test]
+    fn test_unknown_kind_is_rejected() {
+        let err = UserFormatDefinition::from_toml("kind = \"csv\"\nname = \"test\"").unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    }
+}
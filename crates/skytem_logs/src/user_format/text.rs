@@ -0,0 +1,415 @@
+use std::io;
+
+use chrono::{DateTime, NaiveDateTime, Utc};
+use log_if::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    mbed_motor_control::schema::{PlotMapping, TimeUnit},
+    parse_unique_description,
+};
+
+/// Where to find the timestamp of a text log entry
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct TextTimestamp {
+    column: String,
+    /// If the column is a number, the unit of the time since the epoch
+    #[serde(default)]
+    unit: TimeUnit,
+    /// If set, the column is parsed as a date/time with this format, e.g. `"%Y-%m-%d %H:%M:%S%.f"`
+    #[serde(default)]
+    format: Option<String>,
+}
+
+impl TextTimestamp {
+    fn parse_ns(&self, field: &str) -> io::Result<f64> {
+        let invalid = |e: String| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("Invalid timestamp '{field}': {e}"),
+            )
+        };
+        if let Some(fmt) = &self.format {
+            let ts = NaiveDateTime::parse_from_str(field, fmt)
+                .map_err(|e| invalid(e.to_string()))?
+                .and_utc()
+                .timestamp_nanos_opt()
+                .ok_or_else(|| invalid("out of range".to_owned()))?;
+            Ok(ts as f64)
+        } else {
+            let ts: f64 = field.parse().map_err(|e| invalid(format!("{e}")))?;
+            Ok(ts * self.unit.to_ns_factor())
+        }
+    }
+}
+
+fn default_delimiter() -> String {
+    ",".to_owned()
+}
+
+/// Describes a text log with one entry per line, where the fields of an entry are separated by a delimiter
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct TextLogSchema {
+    name: String,
+    /// If set, the first line of the log has to start with this string for the log to be recognized.
+    ///
+    /// The line is read like the unique description of the Mbed logs, so null padding and trailing whitespace
+    /// on either side are ignored.
+    #[serde(default)]
+    magic: Option<String>,
+    #[serde(default = "default_delimiter")]
+    delimiter: String,
+    /// Number of lines before the first entry, e.g. a line with the magic string and a line with column names
+    #[serde(default)]
+    skip_lines: usize,
+    /// Names of the columns in the order they appear in an entry
+    columns: Vec<String>,
+    timestamp: TextTimestamp,
+    plots: Vec<PlotMapping>,
+}
+
+impl TextLogSchema {
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub(crate) fn validate(&self) -> io::Result<()> {
+        let invalid = |msg: String| {
+            Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("Invalid schema '{}': {msg}", self.name),
+            ))
+        };
+        if self.delimiter.is_empty() {
+            return invalid("the delimiter is empty".to_owned());
+        }
+        if self.plots.is_empty() {
+            return invalid("no plots are declared".to_owned());
+        }
+        for column in
+            std::iter::once(&self.timestamp.column).chain(self.plots.iter().map(|p| &p.field))
+        {
+            if self.column_index(column).is_none() {
+                return invalid(format!("no column named '{column}'"));
+            }
+        }
+        Ok(())
+    }
+
+    fn column_index(&self, name: &str) -> Option<usize> {
+        self.columns.iter().position(|c| c == name)
+    }
+
+    /// Split a line into its fields, fails if it doesn't have a field for every column
+    fn split_line<'l>(&self, line: &'l str) -> io::Result<Vec<&'l str>> {
+        let fields: Vec<&str> = line
+            .trim_end()
+            .split(&self.delimiter)
+            .map(str::trim)
+            .collect();
+        if fields.len() == self.columns.len() {
+            Ok(fields)
+        } else {
+            Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!(
+                    "Expected {} fields, got {}",
+                    self.columns.len(),
+                    fields.len()
+                ),
+            ))
+        }
+    }
+
+    fn detect(&self, buf: &[u8]) -> Confidence {
+        if let Some(magic) = &self.magic {
+            let first_line = buf.split(|b| *b == b'\n').next().unwrap_or_default();
+            return if parse_unique_description(first_line)
+                .trim_end()
+                .starts_with(magic.trim_end())
+            {
+                Confidence::High
+            } else {
+                Confidence::None
+            };
+        }
+        let Ok(content) = std::str::from_utf8(buf) else {
+            return Confidence::None;
+        };
+        // Without a magic string, the best we can do is check that the first entry looks right
+        let Some(first_entry) = content.lines().nth(self.skip_lines) else {
+            return Confidence::None;
+        };
+        let ts_idx = self
+            .column_index(&self.timestamp.column)
+            .expect("timestamp column is validated");
+        match self.split_line(first_entry) {
+            Ok(fields) if self.timestamp.parse_ns(fields[ts_idx]).is_ok() => Confidence::Low,
+            _ => Confidence::None,
+        }
+    }
+}
+
+/// A log parsed according to a [`TextLogSchema`]
+#[derive(Debug, Clone)]
+pub struct TextLog {
+    descriptive_name: String,
+    first_timestamp: DateTime<Utc>,
+    raw_plots: Vec<RawPlot>,
+    diagnostics: Vec<ParseDiagnostic>,
+}
+
+impl TextLog {
+    /// Parse the content and return the log along with the number of bytes that were parsed
+    pub fn from_str(schema: &TextLogSchema, content: &str) -> io::Result<(Self, usize)> {
+        let ts_idx = schema
+            .column_index(&schema.timestamp.column)
+            .expect("timestamp column is validated");
+        let plot_indices: Vec<usize> = schema
+            .plots
+            .iter()
+            .map(|p| {
+                schema
+                    .column_index(&p.field)
+                    .expect("plot column is validated")
+            })
+            .collect();
+        let mut plot_points: Vec<Vec<[f64; 2]>> = vec![vec![]; schema.plots.len()];
+        let mut diagnostics: Vec<ParseDiagnostic> = vec![];
+        let mut parsed_bytes = 0;
+        let mut byte_offset = 0;
+        let mut last_timestamp_ns = None;
+
+        for (line_idx, line) in content.split_inclusive('\n').enumerate() {
+            let line_offset = byte_offset;
+            byte_offset += line.len();
+            if line_idx < schema.skip_lines {
+                parsed_bytes += line.len();
+                continue;
+            }
+            if line.trim().is_empty() {
+                parsed_bytes += line.len();
+                continue;
+            }
+            let entry = schema.split_line(line).and_then(|fields| {
+                let ts = schema.timestamp.parse_ns(fields[ts_idx])?;
+                let values = plot_indices
+                    .iter()
+                    .map(|&idx| {
+                        fields[idx].parse::<f64>().map_err(|e| {
+                            io::Error::new(
+                                io::ErrorKind::InvalidData,
+                                format!("Invalid value '{}': {e}", fields[idx]),
+                            )
+                        })
+                    })
+                    .collect::<io::Result<Vec<f64>>>()?;
+                Ok((ts, values))
+            });
+            match entry {
+                Ok((ts, values)) => {
+                    for ((points, value), plot) in
+                        plot_points.iter_mut().zip(values).zip(&schema.plots)
+                    {
+                        points.push([ts, value * plot.scale]);
+                    }
+                    last_timestamp_ns = Some(ts);
+                    parsed_bytes += line.len();
+                }
+                Err(e) => {
+                    let mut diagnostic = ParseDiagnostic::new(line_offset, Some(line_idx + 1), &e);
+                    if let Some(ts) = last_timestamp_ns {
+                        diagnostic = diagnostic.with_timestamp_ns(ts);
+                    }
+                    diagnostics.push(diagnostic);
+                }
+            }
+        }
+
+        let first_timestamp_ns = plot_points
+            .first()
+            .and_then(|points| points.first())
+            .map(|[ts, _]| *ts)
+            .ok_or_else(|| {
+                io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("No valid entries in '{}'", schema.name),
+                )
+            })?;
+        let raw_plots = schema
+            .plots
+            .iter()
+            .zip(plot_points)
+            .map(|(plot, points)| RawPlot::new(plot.name.clone(), points, plot.range))
+            .collect();
+
+        Ok((
+            Self {
+                descriptive_name: schema.name.clone(),
+                first_timestamp: DateTime::from_timestamp_nanos(first_timestamp_ns as i64),
+                raw_plots,
+                diagnostics,
+            },
+            parsed_bytes,
+        ))
+    }
+}
+
+impl Plotable for TextLog {
+    fn raw_plots(&self) -> &[RawPlot] {
+        &self.raw_plots
+    }
+
+    fn first_timestamp(&self) -> DateTime<Utc> {
+        self.first_timestamp
+    }
+
+    fn descriptive_name(&self) -> &str {
+        &self.descriptive_name
+    }
+
+    fn labels(&self) -> Option<&[PlotLabels]> {
+        None
+    }
+
+    fn metadata(&self) -> Option<Vec<(String, String)>> {
+        None
+    }
+}
+
+/// Registers a [`TextLogSchema`] as a [`LogFormat`]
+#[derive(Debug, Clone)]
+pub struct TextLogFormat {
+    schema: TextLogSchema,
+    description: String,
+}
+
+impl TextLogFormat {
+    pub fn new(schema: TextLogSchema) -> Self {
+        let description = format!("Text log with columns: {}", schema.columns.join(", "));
+        Self {
+            schema,
+            description,
+        }
+    }
+}
+
+impl LogFormat for TextLogFormat {
+    fn name(&self) -> &str {
+        &self.schema.name
+    }
+
+    fn description(&self) -> &str {
+        &self.description
+    }
+
+    fn detect(&self, buf: &[u8]) -> Confidence {
+        self.schema.detect(buf)
+    }
+
+    fn parse(&self, buf: &[u8]) -> io::Result<ParsedLog> {
+        let content =
+            std::str::from_utf8(buf).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        let (log, parsed_bytes) = TextLog::from_str(&self.schema, content)?;
+        let diagnostics = log.diagnostics.clone();
+        Ok(ParsedLog {
+            log: Box::new(log),
+            parsed_bytes,
+            total_bytes: buf.len(),
+            diagnostics,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::user_format::UserFormatDefinition;
+    use pretty_assertions::assert_eq;
+    use testresult::TestResult;
+
+    const TEST_BENCH_DEFINITION: &str = r##"
+kind = "text"
+name = "Test bench"
+magic = "# TEST BENCH"
+delimiter = ";"
+skip_lines = 2
+columns = ["time", "temperature", "voltage"]
+timestamp = { column = "time", unit = "s" }
+
+[[plots]]
+name = "Temperature"
+field = "temperature"
+range = "OneToOneHundred"
+
+[[plots]]
+name = "Voltage [mV]"
+field = "voltage"
+range = "Thousands"
+scale = 1000.0
+"##;
+
+    const TEST_BENCH_LOG: &str = "# TEST BENCH\n\
+        time;temperature;voltage\n\
+        1700000000;20.5;1.5\n\
+        1700000001;21.0;oops\n\
+        1700000002;21.5;1.6\n";
+
+    fn schema() -> TextLogSchema {
+        let UserFormatDefinition::Text(schema) =
+            UserFormatDefinition::from_toml(TEST_BENCH_DEFINITION).unwrap()
+        else {
+            panic!("Expected a text definition");
+        };
+        schema
+    }
+
+    #[test]
+    fn test_parse_text_log() -> TestResult {
+        let schema = schema();
+        assert_eq!(schema.detect(TEST_BENCH_LOG.as_bytes()), Confidence::High);
+
+        let (log, parsed_bytes) = TextLog::from_str(&schema, TEST_BENCH_LOG)?;
+        assert_eq!(
+            parsed_bytes,
+            TEST_BENCH_LOG.len() - "1700000001;21.0;oops\n".len()
+        );
+        assert_eq!(log.first_timestamp().timestamp(), 1_700_000_000);
+
+        let temperature = log.raw_plots()[0].points();
+        assert_eq!(temperature.len(), 2);
+        assert_eq!(temperature[1], [1_700_000_002.0 * 1e9, 21.5]);
+        assert_eq!(log.raw_plots()[1].points()[0][1], 1500.0);
+
+        assert_eq!(log.diagnostics.len(), 1);
+        assert_eq!(log.diagnostics[0].line(), Some(4));
+        assert_eq!(
+            log.diagnostics[0].timestamp_ns(),
+            Some(1_700_000_000.0 * 1e9)
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_detect_magic_ignores_padding() {
+        let schema = schema();
+        assert_eq!(
+            schema.detect(b"# TEST BENCH\0\0\0\r\ntime;temperature;voltage\r\n"),
+            Confidence::High
+        );
+        assert_eq!(schema.detect(b"# TEST BENCH  \n"), Confidence::High);
+        assert_eq!(schema.detect(b"# TEST\n BENCH\n"), Confidence::None);
+        assert_eq!(schema.detect(b" # TEST BENCH\n"), Confidence::None);
+    }
+
+    #[test]
+    fn test_detect_without_magic() {
+        let mut schema = schema();
+        schema.magic = None;
+        assert_eq!(schema.detect(TEST_BENCH_LOG.as_bytes()), Confidence::Low);
+        assert_eq!(
+            schema.detect(b"# TEST BENCH\nnot;an;entry\n"),
+            Confidence::None
+        );
+    }
+}
@@ -12,9 +12,15 @@ use supported_formats::{LoadedFiles, SupportedFormat};
 
 mod dropped_files;
 mod file_dialog;
+#[cfg(feature = "hdf")]
+#[cfg(not(target_arch = "wasm32"))]
+mod hdf_browser;
 
 pub mod supported_formats;
 mod util;
+#[cfg(feature = "hdf")]
+#[cfg(not(target_arch = "wasm32"))]
+mod waveform_view;
 
 /// if a log is loaded from content that exceeds this many unparsed bytes:
 /// - Show a toasts warning notification
@@ -31,6 +37,7 @@ pub const WARN_ON_UNPARSED_BYTES_THRESHOLD: usize = 128;
 pub struct App {
     #[serde(skip)]
     toasts: Toasts,
+    #[serde(skip)]
     loaded_files: LoadedFiles,
     plot: LogPlotUi,
     font_size: Option<f32>,
@@ -43,6 +50,16 @@ pub struct App {
     #[cfg(not(target_arch = "wasm32"))]
     #[serde(skip)]
     native_file_dialog: fd::native::NativeFileDialog,
+
+    #[cfg(feature = "hdf")]
+    #[cfg(not(target_arch = "wasm32"))]
+    #[serde(skip)]
+    hdf_browsers: Vec<hdf_browser::HdfBrowser>,
+
+    #[cfg(feature = "hdf")]
+    #[cfg(not(target_arch = "wasm32"))]
+    #[serde(skip)]
+    waveform_viewers: Vec<waveform_view::WaveformViewer>,
 }
 
 impl Default for App {
@@ -59,6 +76,13 @@ impl Default for App {
 
             #[cfg(not(target_arch = "wasm32"))]
             native_file_dialog: fd::native::NativeFileDialog::default(),
+
+            #[cfg(feature = "hdf")]
+            #[cfg(not(target_arch = "wasm32"))]
+            hdf_browsers: Vec::new(),
+            #[cfg(feature = "hdf")]
+            #[cfg(not(target_arch = "wasm32"))]
+            waveform_viewers: Vec::new(),
         }
     }
 }
@@ -77,11 +101,30 @@ impl App {
 
         // Load previous app state (if any).
         // Note that you must enable the `persistence` feature for this to work.
-        if let Some(storage) = cc.storage {
-            return eframe::get_value(storage, eframe::APP_KEY).unwrap_or_default();
-        }
+        #[cfg_attr(target_arch = "wasm32", allow(unused_mut))]
+        let mut app: Self = cc
+            .storage
+            .and_then(|storage| eframe::get_value(storage, eframe::APP_KEY))
+            .unwrap_or_default();
+
+        #[cfg(not(target_arch = "wasm32"))]
+        app.load_user_formats();
+
+        app
+    }
 
-        Default::default()
+    /// Register the user format definitions found in the user formats directory
+    #[cfg(not(target_arch = "wasm32"))]
+    fn load_user_formats(&mut self) {
+        let Some(dir) = supported_formats::user_formats_dir().filter(|dir| dir.is_dir()) else {
+            return;
+        };
+        for e in self.loaded_files.load_user_formats(&dir) {
+            log::error!("Failed loading user format: {e}");
+            self.toasts
+                .error(format!("Failed loading user format: {e}"))
+                .duration(Some(Duration::from_secs(30)));
+        }
     }
 
     fn configure_text_styles(ctx: &egui::Context, font_size: f32) {
@@ -120,6 +163,36 @@ impl eframe::App for App {
         }
 
         Self::configure_text_styles(ctx, self.font_size.unwrap_or_default());
+        self.show_top_panel(ctx);
+
+        egui::CentralPanel::default().show(ctx, |ui| {
+            self.notify_if_formats_registered();
+            notify_if_logs_added(&mut self.toasts, self.loaded_files.loaded());
+            let loaded_files = self.loaded_files.take_loaded_files();
+            self.plot.ui(ui, &loaded_files, &mut self.toasts);
+            if self.plot.plot_count() == 0 {
+                // Display the message when plots are shown
+                util::draw_empty_state(ui);
+            }
+
+            if let Err(e) = handle_dropped_files(ctx, &mut self.loaded_files) {
+                self.error_message = Some(e.to_string());
+            }
+            #[cfg(feature = "hdf")]
+            #[cfg(not(target_arch = "wasm32"))]
+            self.show_hdf_windows(ctx, &loaded_files);
+
+            self.show_error(ui);
+            ui.with_layout(egui::Layout::bottom_up(egui::Align::LEFT), |ui| {
+                egui::warn_if_debug_build(ui);
+            });
+        });
+        self.toasts.show(ctx);
+    }
+}
+
+impl App {
+    fn show_top_panel(&mut self, ctx: &egui::Context) {
         egui::TopBottomPanel::top("top_panel").show(ctx, |ui| {
             egui::menu::bar(ui, |ui| {
                 if ui
@@ -138,8 +211,11 @@ impl eframe::App for App {
                             .info("All loaded logs removed...")
                             .duration(Some(std::time::Duration::from_secs(3)));
                     }
-                    self.loaded_files = LoadedFiles::default();
+                    self.loaded_files.clear();
                     self.plot = LogPlotUi::default();
+                    #[cfg(feature = "hdf")]
+                    #[cfg(not(target_arch = "wasm32"))]
+                    self.waveform_viewers.clear();
                 }
                 if ui
                     .button(RichText::new(format!(
@@ -153,6 +229,11 @@ impl eframe::App for App {
                     #[cfg(not(target_arch = "wasm32"))]
                     self.native_file_dialog.open();
                 }
+                #[cfg(feature = "hdf")]
+                #[cfg(not(target_arch = "wasm32"))]
+                self.show_hdf_export_button(ui);
+                #[cfg(not(target_arch = "wasm32"))]
+                self.show_parquet_export_button(ui);
                 ui.label(RichText::new(regular::TEXT_T));
                 if let Some(ref mut font_size) = self.font_size {
                     if ui
@@ -178,30 +259,106 @@ impl eframe::App for App {
                 collapsible_instructions(ui);
             });
         });
+    }
 
-        egui::CentralPanel::default().show(ctx, |ui| {
-            notify_if_logs_added(&mut self.toasts, self.loaded_files.loaded());
-            self.plot
-                .ui(ui, &self.loaded_files.take_loaded_files(), &mut self.toasts);
-            if self.plot.plot_count() == 0 {
-                // Display the message when plots are shown
-                util::draw_empty_state(ui);
-            }
+    /// Shows a button for exporting the loaded logs to an HDF5 file chosen by the user
+    #[cfg(feature = "hdf")]
+    #[cfg(not(target_arch = "wasm32"))]
+    fn show_hdf_export_button(&mut self, ui: &mut egui::Ui) {
+        let export_button =
+            egui::Button::new(RichText::new(format!("{} Export HDF5", regular::EXPORT)));
+        if !ui
+            .add_enabled(self.plot.plot_count() > 0, export_button)
+            .on_disabled_hover_text("No loaded plots to export")
+            .clicked()
+        {
+            return;
+        }
+        let Some(path) = rfd::FileDialog::new()
+            .add_filter("HDF5", &["h5", "hdf5"])
+            .set_file_name("plotinator3000_export.h5")
+            .save_file()
+        else {
+            return;
+        };
+        match self.plot.export_hdf(&path) {
+            Ok(()) => self
+                .toasts
+                .success(format!("Exported loaded logs to {}", path.display())),
+            Err(e) => self
+                .toasts
+                .error(format!("Failed exporting to {}: {e}", path.display())),
+        }
+        .duration(Some(Duration::from_secs(5)));
+    }
 
-            if let Err(e) = handle_dropped_files(ctx, &mut self.loaded_files) {
-                self.error_message = Some(e.to_string());
+    /// Shows a button for exporting each loaded log to a Parquet file in a directory chosen by the user
+    #[cfg(not(target_arch = "wasm32"))]
+    fn show_parquet_export_button(&mut self, ui: &mut egui::Ui) {
+        let export_button =
+            egui::Button::new(RichText::new(format!("{} Export Parquet", regular::EXPORT)));
+        if !ui
+            .add_enabled(self.plot.plot_count() > 0, export_button)
+            .on_disabled_hover_text("No loaded plots to export")
+            .clicked()
+        {
+            return;
+        }
+        let Some(dir) = rfd::FileDialog::new().pick_folder() else {
+            return;
+        };
+        match self.plot.export_parquet(&dir) {
+            Ok(count) => self.toasts.success(format!(
+                "Exported {count} log(s) to Parquet files in {}",
+                dir.display()
+            )),
+            Err(e) => self
+                .toasts
+                .error(format!("Failed exporting to {}: {e}", dir.display())),
+        }
+        .duration(Some(Duration::from_secs(5)));
+    }
+
+    /// Opens a browser for each HDF5 file of an unrecognized format and a waveform viewer for each newly loaded
+    /// file that is sampled in periods, and shows the open windows
+    #[cfg(feature = "hdf")]
+    #[cfg(not(target_arch = "wasm32"))]
+    fn show_hdf_windows(&mut self, ctx: &egui::Context, loaded_files: &[SupportedFormat]) {
+        self.hdf_browsers.extend(
+            self.loaded_files
+                .take_hdf_files_to_browse()
+                .into_iter()
+                .map(hdf_browser::HdfBrowser::new),
+        );
+        for browser in &mut self.hdf_browsers {
+            if let Some(hdf) = browser.show(ctx) {
+                self.loaded_files.add_generic_hdf(hdf);
             }
+        }
+        self.hdf_browsers.retain(hdf_browser::HdfBrowser::is_open);
+
+        self.waveform_viewers
+            .extend(loaded_files.iter().filter_map(|file| {
+                file.waveform().map(|waveform| {
+                    waveform_view::WaveformViewer::new(file.descriptive_name(), waveform.clone())
+                })
+            }));
+        for viewer in &mut self.waveform_viewers {
+            viewer.show(ctx);
+        }
+        self.waveform_viewers
+            .retain(waveform_view::WaveformViewer::is_open);
+    }
 
-            self.show_error(ui);
-            ui.with_layout(egui::Layout::bottom_up(egui::Align::LEFT), |ui| {
-                egui::warn_if_debug_build(ui);
-            });
-        });
-        self.toasts.show(ctx);
+    /// Displays a toasts notification with the name of each user format definition that was registered
+    fn notify_if_formats_registered(&mut self) {
+        for name in self.loaded_files.take_registered_formats() {
+            self.toasts
+                .info(format!("Registered format: {name}"))
+                .duration(Some(Duration::from_secs(5)));
+        }
     }
-}
 
-impl App {
     fn show_error(&mut self, ui: &egui::Ui) {
         if let Some(error) = self.error_message.clone() {
             egui::Window::new(RichText::new("⚠").size(40.0).color(Color32::RED))
@@ -248,6 +405,14 @@ fn collapsible_instructions(ui: &mut egui::Ui) {
             ui.label("Zoom with ctrl + scroll.");
         }
         ui.label("Reset view with double-click.");
+        ui.label("Load a format definition (.toml) to plot logs of a custom format.");
+        #[cfg(not(target_arch = "wasm32"))]
+        if let Some(dir) = supported_formats::user_formats_dir() {
+            ui.label(format!(
+                "Format definitions in '{}' are loaded at startup.",
+                dir.display()
+            ));
+        }
     });
 }
 
@@ -285,6 +450,16 @@ fn notify_if_logs_added(toasts: &mut Toasts, logs: &[SupportedFormat]) {
                 ))
                         .duration(Some(Duration::from_secs(30)));
                 }
+                if !parse_info.diagnostics().is_empty() {
+                    toasts
+                        .warning(format!(
+                            "{count} corrupt region{s} skipped while parsing {log_name}\nSee the log settings for details",
+                            count = parse_info.diagnostics().len(),
+                            s = if parse_info.diagnostics().len() == 1 { "" } else { "s" },
+                            log_name = l.descriptive_name(),
+                        ))
+                        .duration(Some(Duration::from_secs(30)));
+                }
             }
         }
     }
@@ -18,7 +18,7 @@ pub(crate) fn handle_dropped_files(
     }) {
         for dfile in dropped_files {
             if let Some(content) = dfile.bytes.as_ref() {
-                loaded_files.parse_raw_buffer(content)?;
+                loaded_files.parse_raw_buffer(&dfile.name, content)?;
             } else if let Some(path) = &dfile.path {
                 loaded_files.parse_path(path)?;
             }
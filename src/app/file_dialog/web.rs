@@ -38,7 +38,7 @@ impl WebFileDialog {
     pub(crate) fn poll_received_files(&self, loaded_files: &mut LoadedFiles) -> io::Result<()> {
         if let Ok(file_web_content) = self.file_receiver.try_recv() {
             log::debug!("Received file: {}", file_web_content.name);
-            loaded_files.parse_raw_buffer(&file_web_content.contents)?;
+            loaded_files.parse_raw_buffer(&file_web_content.name, &file_web_content.contents)?;
         }
         Ok(())
     }
@@ -0,0 +1,171 @@
+use egui::RichText;
+use egui_phosphor::regular;
+use skytem_hdf::generic::{
+    GenericHdf, HdfDatasetInfo, HdfFileInfo, HdfGroupInfo, HdfPlotSelection, TimestampUnit,
+};
+
+/// A window for browsing the groups, datasets and attributes of an HDF5 file that is not one of the
+/// recognized HDF5 formats, and choosing which datasets to plot.
+#[derive(Debug)]
+pub(crate) struct HdfBrowser {
+    file: HdfFileInfo,
+    selection: HdfPlotSelection,
+    open: bool,
+    error: Option<String>,
+}
+
+impl HdfBrowser {
+    pub(crate) fn new(file: HdfFileInfo) -> Self {
+        Self {
+            file,
+            selection: HdfPlotSelection::default(),
+            open: true,
+            error: None,
+        }
+    }
+
+    pub(crate) fn is_open(&self) -> bool {
+        self.open
+    }
+
+    /// Shows the browser window and returns the selected datasets if the user chose to plot them.
+    ///
+    /// The window closes once the datasets are plotted.
+    pub(crate) fn show(&mut self, ctx: &egui::Context) -> Option<GenericHdf> {
+        let mut plotted = None;
+        let mut open = self.open;
+        egui::Window::new(format!(
+            "{} {}",
+            regular::TREE_STRUCTURE,
+            self.file.file_name()
+        ))
+        .id(egui::Id::new(self.file.path()))
+        .open(&mut open)
+        .show(ctx, |ui| {
+            egui::ScrollArea::vertical()
+                .max_height(ui.ctx().screen_rect().height() * 0.6)
+                .show(ui, |ui| {
+                    show_group(ui, self.file.root(), &mut self.selection);
+                });
+            ui.separator();
+            show_time_settings(ui, &mut self.selection);
+            ui.separator();
+            if let Some(error) = &self.error {
+                ui.colored_label(ui.visuals().error_fg_color, error);
+            }
+            let plot_button = egui::Button::new(format!(
+                "{} Plot {} dataset(s)",
+                regular::CHART_LINE,
+                self.selection.datasets.len()
+            ));
+            if ui
+                .add_enabled(!self.selection.datasets.is_empty(), plot_button)
+                .clicked()
+            {
+                match GenericHdf::from_selection(&self.file, &self.selection) {
+                    Ok(hdf) => plotted = Some(hdf),
+                    Err(e) => self.error = Some(e.to_string()),
+                }
+            }
+        });
+        self.open = open && plotted.is_none();
+        plotted
+    }
+}
+
+fn show_group(ui: &mut egui::Ui, group: &HdfGroupInfo, selection: &mut HdfPlotSelection) {
+    show_attributes(ui, group.path(), group.attributes());
+    for dataset in group.datasets() {
+        show_dataset(ui, dataset, selection);
+    }
+    for subgroup in group.groups() {
+        egui::CollapsingHeader::new(format!("{} {}", regular::FOLDER, subgroup.name()))
+            .id_salt(subgroup.path())
+            .show(ui, |ui| show_group(ui, subgroup, selection));
+    }
+}
+
+fn show_dataset(ui: &mut egui::Ui, dataset: &HdfDatasetInfo, selection: &mut HdfPlotSelection) {
+    ui.horizontal(|ui| {
+        let mut selected = selection.datasets.contains(dataset.path());
+        if ui
+            .add_enabled(
+                dataset.is_plottable(),
+                egui::Checkbox::new(&mut selected, dataset.name()),
+            )
+            .on_disabled_hover_text("Only numeric 1D and 2D datasets can be plotted")
+            .changed()
+        {
+            if selected {
+                selection.datasets.push(dataset.path().clone());
+            } else {
+                selection.datasets.retain(|path| path != dataset.path());
+            }
+        }
+        ui.label(RichText::new(format!("{:?} {}", dataset.shape(), dataset.dtype())).weak());
+        if dataset.is_timestamp_candidate() {
+            let is_timestamp = selection.timestamp.as_ref() == Some(dataset.path());
+            if ui
+                .selectable_label(is_timestamp, regular::CLOCK)
+                .on_hover_text("Use as timestamps of the plotted datasets")
+                .clicked()
+            {
+                selection.timestamp = if is_timestamp {
+                    None
+                } else {
+                    Some(dataset.path().clone())
+                };
+            }
+        }
+    });
+    ui.indent(dataset.path(), |ui| {
+        show_attributes(ui, dataset.path(), dataset.attributes());
+    });
+}
+
+fn show_attributes(ui: &mut egui::Ui, path: &str, attributes: &[(String, String)]) {
+    if attributes.is_empty() {
+        return;
+    }
+    egui::CollapsingHeader::new(format!("Attributes ({})", attributes.len()))
+        .id_salt((path, "attributes"))
+        .show(ui, |ui| {
+            egui::Grid::new((path, "attributes_grid"))
+                .striped(true)
+                .show(ui, |ui| {
+                    for (name, value) in attributes {
+                        ui.label(name);
+                        ui.label(value);
+                        ui.end_row();
+                    }
+                });
+        });
+}
+
+fn show_time_settings(ui: &mut egui::Ui, selection: &mut HdfPlotSelection) {
+    ui.horizontal(|ui| {
+        if let Some(timestamp) = &selection.timestamp {
+            ui.label(format!("{} Timestamps: {timestamp}", regular::CLOCK));
+            egui::ComboBox::from_id_salt("hdf_browser_timestamp_unit")
+                .selected_text(selection.timestamp_unit.to_string())
+                .show_ui(ui, |ui| {
+                    for unit in TimestampUnit::ALL {
+                        ui.selectable_value(&mut selection.timestamp_unit, unit, unit.to_string());
+                    }
+                })
+                .response
+                .on_hover_text("Unit of the time since the Unix epoch");
+        } else {
+            ui.label(format!(
+                "{} No timestamps selected, time between entries:",
+                regular::CLOCK
+            ));
+            ui.add(
+                egui::DragValue::new(&mut selection.sample_period_s)
+                    .speed(0.001)
+                    .range(1e-9..=f64::MAX)
+                    .suffix(" s"),
+            );
+        }
+    });
+}
@@ -1,19 +1,7 @@
 use log_if::prelude::*;
-use logs::{
-    parse_info::{ParseInfo, ParsedBytes, TotalBytes},
-    SupportedLog,
-};
-use serde::{Deserialize, Serialize};
-use skytem_logs::{
-    generator::GeneratorLog,
-    mbed_motor_control::{pid::pidlog::PidLog, status::statuslog::StatusLog},
-    navsys::NavSysSps,
-};
-use std::{
-    fs,
-    io::{self, BufReader},
-    path::Path,
-};
+use logs::{parse_info::ParseInfo, SupportedLog};
+use skytem_logs::user_format::UserFormatDefinition;
+use std::{fs, io, path::Path};
 
 #[cfg(feature = "hdf")]
 #[cfg(not(target_arch = "wasm32"))]
@@ -21,10 +9,13 @@ mod hdf;
 pub(crate) mod logs;
 mod util;
 
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) use util::user_formats_dir;
+
 /// Represents a supported format, which can be any of the supported format types.
 ///
 /// This simply serves to encapsulate all the supported format in a single type
-#[derive(Debug, Clone, Deserialize, Serialize)]
+#[derive(Debug)]
 #[allow(
     clippy::large_enum_variant,
     reason = "This enum is only created once when parsing an added file for the first time so optimizing memory for an instance of this enum is a waste of effort"
@@ -37,155 +28,32 @@ pub enum SupportedFormat {
     HDF(hdf::SupportedHdfFormat),
 }
 
-impl From<(PidLog, ParseInfo)> for SupportedFormat {
-    fn from(value: (PidLog, ParseInfo)) -> Self {
-        Self::Log(SupportedLog::from(value))
-    }
-}
-
-impl From<(StatusLog, ParseInfo)> for SupportedFormat {
-    fn from(value: (StatusLog, ParseInfo)) -> Self {
-        Self::Log(SupportedLog::from(value))
-    }
-}
-
-impl From<(GeneratorLog, ParseInfo)> for SupportedFormat {
-    fn from(value: (GeneratorLog, ParseInfo)) -> Self {
-        Self::Log(SupportedLog::from(value))
-    }
-}
-
-impl From<(NavSysSps, ParseInfo)> for SupportedFormat {
-    fn from(value: (NavSysSps, ParseInfo)) -> Self {
-        Self::Log(SupportedLog::from(value))
-    }
-}
-
 impl SupportedFormat {
-    /// Attempts to parse a log from raw content.
+    /// Attempts to parse a log from raw content with the formats in the `registry`.
     ///
     /// This is how content is made available in a browser.
-    fn parse_from_buf(content: &[u8]) -> io::Result<Self> {
-        let total_bytes = content.len();
-        log::debug!("Parsing content of length: {total_bytes}");
-        let log: Self = if let Ok((pidlog, read_bytes)) = PidLog::try_from_buf(content) {
-            log::debug!("Read: {read_bytes} bytes");
-            (
-                pidlog,
-                ParseInfo::new(ParsedBytes(read_bytes), TotalBytes(total_bytes)),
-            )
-                .into()
-        } else if let Ok((statuslog, read_bytes)) = StatusLog::try_from_buf(content) {
-            (
-                statuslog,
-                ParseInfo::new(ParsedBytes(read_bytes), TotalBytes(read_bytes)),
-            )
-                .into()
-        } else if let Ok((genlog, read_bytes)) = GeneratorLog::try_from_buf(content) {
-            (
-                genlog,
-                ParseInfo::new(ParsedBytes(read_bytes), TotalBytes(total_bytes)),
-            )
-                .into()
-        } else if let Ok((navsyssps_log, read_bytes)) = NavSysSps::try_from_buf(content) {
-            (
-                navsyssps_log,
-                ParseInfo::new(ParsedBytes(read_bytes), TotalBytes(total_bytes)),
-            )
-                .into()
-        } else {
+    fn parse_from_buf(registry: &FormatRegistry, content: &[u8]) -> io::Result<Self> {
+        log::debug!("Parsing content of length: {}", content.len());
+        if skytem_hdf::is_hdf5(content) {
             return Err(io::Error::new(
-                io::ErrorKind::InvalidData,
-                "Unrecognized format",
+                io::ErrorKind::InvalidInput,
+                "Recognized the content as an HDF5 file. HDF5 files are only supported on the native version with the HDF feature",
             ));
-        };
+        }
+        let parsed = registry.parse(content)?;
+        log::debug!("Read: {} bytes", parsed.parsed_bytes);
+        let log = Self::Log(parsed.into());
         log::debug!("Got: {}", log.descriptive_name());
         Ok(log)
     }
 
     /// Attempts to parse a log from a file path.
     ///
-    /// This is how it is made available on native.
-    fn parse_from_path(path: &Path) -> io::Result<Self> {
-        let file = fs::File::open(path)?;
-        let total_bytes = file.metadata()?.len() as usize;
-        log::debug!("Parsing content of length: {total_bytes}");
-
-        let mut reader = BufReader::new(file);
-        let log: Self = if util::path_has_hdf_extension(path) {
-            Self::parse_hdf_from_path(path)?
-        } else if PidLog::file_is_valid(path) {
-            let (log, parsed_bytes) = PidLog::from_reader(&mut reader)?;
-            log::debug!("Read: {parsed_bytes} bytes");
-            (
-                log,
-                ParseInfo::new(ParsedBytes(parsed_bytes), TotalBytes(total_bytes)),
-            )
-                .into()
-        } else if StatusLog::file_is_valid(path) {
-            let (log, parsed_bytes) = StatusLog::from_reader(&mut reader)?;
-            (
-                log,
-                ParseInfo::new(ParsedBytes(parsed_bytes), TotalBytes(total_bytes)),
-            )
-                .into()
-        } else if GeneratorLog::file_is_generator_log(path).unwrap_or(false) {
-            let (log, parsed_bytes) = GeneratorLog::from_reader(&mut reader)?;
-            (
-                log,
-                ParseInfo::new(ParsedBytes(parsed_bytes), TotalBytes(total_bytes)),
-            )
-                .into()
-        } else if NavSysSps::file_is_valid(path) {
-            let (log, parsed_bytes) = NavSysSps::from_reader(&mut reader)?;
-            (
-                log,
-                ParseInfo::new(ParsedBytes(parsed_bytes), TotalBytes(total_bytes)),
-            )
-                .into()
-        } else {
-            return Err(io::Error::new(
-                io::ErrorKind::InvalidData,
-                "Unrecognized format",
-            ));
-        };
-        log::debug!("Got: {}", log.descriptive_name());
-        Ok(log)
-    }
-
-    #[cfg(feature = "hdf")]
-    #[cfg(not(target_arch = "wasm32"))]
-    fn parse_hdf_from_path(path: &Path) -> io::Result<Self> {
-        use skytem_hdf::bifrost::BifrostLoopCurrent;
-        // Attempt to parse it has an hdf file
-        if let Ok(bifrost_loop_current) = BifrostLoopCurrent::from_path(path) {
-            Ok(Self::HDF(bifrost_loop_current.into()))
-        } else {
-            Err(io::Error::new(
-                io::ErrorKind::InvalidInput,
-                "Unrecognized HDF file",
-            ))
-        }
-    }
-
-    #[cfg(not(feature = "hdf"))]
-    #[cfg(not(target_arch = "wasm32"))]
-    fn parse_hdf_from_path(path: &Path) -> io::Result<Self> {
-        Err(io::Error::new(
-            io::ErrorKind::InvalidInput,
-            format!(
-                "Recognized '{}' as an HDF file. But the HDF feature is turned off.",
-                path.display()
-            ),
-        ))
-    }
-
-    #[cfg(target_arch = "wasm32")]
-    fn parse_hdf_from_path(path: &Path) -> io::Result<Self> {
-        Err(io::Error::new(
-            io::ErrorKind::InvalidInput,
-            format!("Recognized '{}' as an HDF file. HDF files are only supported on the native version", path.display()),
-        ))
+    /// This is how it is made available on native. HDF5 files are handled by [`LoadedFiles::parse_hdf_file`].
+    fn parse_from_path(registry: &FormatRegistry, path: &Path) -> io::Result<Self> {
+        let content = fs::read(path)?;
+        Self::parse_from_buf(registry, &content)
+            .map_err(|e| io::Error::new(e.kind(), format!("{}: {e}", path.display())))
     }
 
     /// Returns [`None`] if there's no meaningful parsing information such as with HDF5 files.
@@ -193,7 +61,7 @@ impl SupportedFormat {
         clippy::unnecessary_wraps,
         reason = "HDF files are not supported on web (yet?) and the lint is triggered when compiling for web since then only logs are supported which always have parse info"
     )]
-    pub fn parse_info(&self) -> Option<ParseInfo> {
+    pub fn parse_info(&self) -> Option<&ParseInfo> {
         match self {
             Self::Log(l) => Some(l.parse_info()),
             #[cfg(feature = "hdf")]
@@ -201,6 +69,16 @@ impl SupportedFormat {
             Self::HDF(_) => None,
         }
     }
+
+    /// The samples of each period, if the format is sampled in periods such as the Bifrost TX loop current
+    #[cfg(feature = "hdf")]
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn waveform(&self) -> Option<&skytem_hdf::bifrost::Waveform> {
+        match self {
+            Self::Log(_) => None,
+            Self::HDF(hdf) => hdf.waveform(),
+        }
+    }
 }
 
 impl Plotable for SupportedFormat {
@@ -252,9 +130,38 @@ impl Plotable for SupportedFormat {
 }
 
 /// Contains all supported logs in a single vector.
-#[derive(Default, Deserialize, Serialize)]
 pub struct LoadedFiles {
+    registry: FormatRegistry,
     loaded: Vec<SupportedFormat>,
+    /// Names of user format definitions registered since they were last taken
+    registered_formats: Vec<String>,
+    /// HDF5 files that are not of a recognized format, the user picks what to plot from them
+    #[cfg(feature = "hdf")]
+    #[cfg(not(target_arch = "wasm32"))]
+    hdf_files_to_browse: Vec<skytem_hdf::generic::HdfFileInfo>,
+    /// Temporary directories with HDF5 files that were loaded from memory, e.g. from a zip archive.
+    ///
+    /// HDF5 files are read by path, and browsed files are read again when plotting, so the directories
+    /// are kept for as long as the app runs.
+    #[cfg(feature = "hdf")]
+    #[cfg(not(target_arch = "wasm32"))]
+    hdf_temp_dirs: Vec<tempfile::TempDir>,
+}
+
+impl Default for LoadedFiles {
+    fn default() -> Self {
+        Self {
+            registry: logs::builtin_registry(),
+            loaded: vec![],
+            registered_formats: vec![],
+            #[cfg(feature = "hdf")]
+            #[cfg(not(target_arch = "wasm32"))]
+            hdf_files_to_browse: vec![],
+            #[cfg(feature = "hdf")]
+            #[cfg(not(target_arch = "wasm32"))]
+            hdf_temp_dirs: vec![],
+        }
+    }
 }
 
 impl LoadedFiles {
@@ -268,6 +175,67 @@ impl LoadedFiles {
         self.loaded.drain(..).collect()
     }
 
+    /// Drop the loaded files that weren't taken yet, registered user formats are kept
+    pub(crate) fn clear(&mut self) {
+        self.loaded.clear();
+        #[cfg(feature = "hdf")]
+        #[cfg(not(target_arch = "wasm32"))]
+        self.hdf_files_to_browse.clear();
+    }
+
+    /// Take the names of all user format definitions registered since the last call
+    pub(crate) fn take_registered_formats(&mut self) -> Vec<String> {
+        self.registered_formats.drain(..).collect()
+    }
+
+    /// Take all HDF5 files that were opened but not recognized, such that the user can browse them
+    #[cfg(feature = "hdf")]
+    #[cfg(not(target_arch = "wasm32"))]
+    pub(crate) fn take_hdf_files_to_browse(&mut self) -> Vec<skytem_hdf::generic::HdfFileInfo> {
+        self.hdf_files_to_browse.drain(..).collect()
+    }
+
+    /// Add the datasets the user selected from an HDF5 file to the loaded files
+    #[cfg(feature = "hdf")]
+    #[cfg(not(target_arch = "wasm32"))]
+    pub(crate) fn add_generic_hdf(&mut self, hdf: skytem_hdf::generic::GenericHdf) {
+        self.loaded.push(SupportedFormat::HDF(hdf.into()));
+    }
+
+    /// Parse a user format definition and register it such that matching content is parsed with it
+    pub(crate) fn register_user_format(&mut self, definition: &str) -> io::Result<()> {
+        self.register_definition(UserFormatDefinition::from_toml(definition)?)
+    }
+
+    fn register_definition(&mut self, definition: UserFormatDefinition) -> io::Result<()> {
+        log::info!("Registering user format: {}", definition.name());
+        let name = definition.name().to_owned();
+        definition.register(&mut self.registry)?;
+        self.registered_formats.push(name);
+        Ok(())
+    }
+
+    /// Register all user format definitions in `dir`, returns an error for each definition that failed to register
+    #[cfg(not(target_arch = "wasm32"))]
+    pub(crate) fn load_user_formats(&mut self, dir: &Path) -> Vec<io::Error> {
+        let mut errors = vec![];
+        let entries = match fs::read_dir(dir) {
+            Ok(entries) => entries,
+            Err(e) => return vec![e],
+        };
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if util::path_is_format_definition(&path) {
+                if let Err(e) = fs::read_to_string(&path)
+                    .and_then(|definition| self.register_user_format(&definition))
+                {
+                    errors.push(io::Error::new(e.kind(), format!("{}: {e}", path.display())));
+                }
+            }
+        }
+        errors
+    }
+
     pub(crate) fn parse_path(&mut self, path: &Path) -> io::Result<()> {
         if path.is_dir() {
             self.parse_directory(path)?;
@@ -275,16 +243,147 @@ impl LoadedFiles {
             #[cfg(not(target_arch = "wasm32"))]
             self.parse_zip_file(path)?;
         } else {
-            self.loaded.push(SupportedFormat::parse_from_path(path)?);
+            self.parse_file(path)?;
         }
         Ok(())
     }
 
-    pub(crate) fn parse_raw_buffer(&mut self, buf: &[u8]) -> io::Result<()> {
-        self.loaded.push(SupportedFormat::parse_from_buf(buf)?);
+    /// Parse content that is not read from a path, such as dropped files on web. The `name` is the file name of
+    /// the content if it is known.
+    #[cfg_attr(
+        any(not(feature = "hdf"), target_arch = "wasm32"),
+        allow(unused_variables, reason = "The name is only used for HDF5 files")
+    )]
+    pub(crate) fn parse_raw_buffer(&mut self, name: &str, buf: &[u8]) -> io::Result<()> {
+        // Content without a path could also be a user format definition
+        if let Some(definition) = std::str::from_utf8(buf)
+            .ok()
+            .and_then(|content| UserFormatDefinition::from_toml(content).ok())
+        {
+            return self.register_definition(definition);
+        }
+        #[cfg(feature = "hdf")]
+        #[cfg(not(target_arch = "wasm32"))]
+        if skytem_hdf::is_hdf5(buf) {
+            let file_name = Path::new(name)
+                .file_name()
+                .map_or_else(|| "dropped.h5".into(), std::path::PathBuf::from);
+            return self.parse_hdf_buffers(vec![(file_name, buf.to_vec())]);
+        }
+        self.loaded
+            .push(SupportedFormat::parse_from_buf(&self.registry, buf)?);
         Ok(())
     }
 
+    fn parse_file(&mut self, path: &Path) -> io::Result<()> {
+        if util::path_is_format_definition(path) {
+            return self.register_user_format(&fs::read_to_string(path)?);
+        }
+        if util::path_is_hdf(path) {
+            return self.parse_hdf_file(path);
+        }
+        self.loaded
+            .push(SupportedFormat::parse_from_path(&self.registry, path)?);
+        Ok(())
+    }
+
+    /// Parse an HDF5 file of a recognized format, or open it for browsing if the format is not recognized
+    ///
+    /// Bifrost files are loaded with one log for the loop current and one log per other stream in the file, and
+    /// files exported from the viewer are loaded with one log per exported log.
+    #[cfg(feature = "hdf")]
+    #[cfg(not(target_arch = "wasm32"))]
+    fn parse_hdf_file(&mut self, path: &Path) -> io::Result<()> {
+        use skytem_hdf::{bifrost, export::ExportedLog};
+        if ExportedLog::is_export(path) {
+            self.loaded.extend(
+                ExportedLog::read_all(path)?
+                    .into_iter()
+                    .map(|log| SupportedFormat::HDF(log.into())),
+            );
+        } else if let Some((loop_current, streams)) = bifrost::read_streams(path)
+            .ok()
+            .filter(|(loop_current, streams)| loop_current.is_some() || !streams.is_empty())
+        {
+            self.loaded.extend(
+                loop_current
+                    .map(|loop_current| SupportedFormat::HDF(loop_current.into()))
+                    .into_iter()
+                    .chain(
+                        streams
+                            .into_iter()
+                            .map(|stream| SupportedFormat::HDF(stream.into())),
+                    ),
+            );
+        } else {
+            self.hdf_files_to_browse
+                .push(skytem_hdf::generic::HdfFileInfo::from_path(path)?);
+        }
+        Ok(())
+    }
+
+    #[cfg(not(feature = "hdf"))]
+    #[cfg(not(target_arch = "wasm32"))]
+    #[allow(clippy::unused_self, reason = "Mirrors the HDF feature version")]
+    fn parse_hdf_file(&self, path: &Path) -> io::Result<()> {
+        Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!(
+                "Recognized '{}' as an HDF file. But the HDF feature is turned off.",
+                path.display()
+            ),
+        ))
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    #[allow(clippy::unused_self, reason = "Mirrors the native version")]
+    fn parse_hdf_file(&self, path: &Path) -> io::Result<()> {
+        Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("Recognized '{}' as an HDF file. HDF files are only supported on the native version", path.display()),
+        ))
+    }
+
+    /// Parse HDF5 files from memory by writing them to a temporary directory, since HDF5 files are read by path.
+    ///
+    /// The files are written at their `relative_path` in the same directory, such that files that refer to
+    /// each other, e.g. Bifrost timestamp streams in sibling files, are found.
+    ///
+    /// # Errors
+    /// With the errors of all the files that failed to load, after loading the rest of the files
+    #[cfg(feature = "hdf")]
+    #[cfg(not(target_arch = "wasm32"))]
+    fn parse_hdf_buffers(&mut self, files: Vec<(std::path::PathBuf, Vec<u8>)>) -> io::Result<()> {
+        let dir = tempfile::tempdir()?;
+        let mut paths = Vec::with_capacity(files.len());
+        for (relative_path, contents) in files {
+            let path = dir.path().join(&relative_path);
+            if let Some(parent) = path.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            fs::write(&path, contents)?;
+            paths.push((relative_path, path));
+        }
+        // Keep the directory even if parsing fails, as some of the files may have been loaded or opened for browsing
+        self.hdf_temp_dirs.push(dir);
+        let errors: Vec<String> = paths
+            .into_iter()
+            .filter_map(|(relative_path, path)| {
+                self.parse_hdf_file(&path)
+                    .err()
+                    .map(|e| format!("{}: {e}", relative_path.display()))
+            })
+            .collect();
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("Failed loading HDF5 files:\n{}", errors.join("\n")),
+            ))
+        }
+    }
+
     fn parse_directory(&mut self, path: &Path) -> io::Result<()> {
         for entry in fs::read_dir(path)? {
             let entry = entry?;
@@ -296,11 +395,8 @@ impl LoadedFiles {
             } else if is_zip_file(&path) {
                 #[cfg(not(target_arch = "wasm32"))]
                 self.parse_zip_file(&path)?;
-            } else {
-                match SupportedFormat::parse_from_path(&path) {
-                    Ok(l) => self.loaded.push(l),
-                    Err(e) => log::warn!("{e}"),
-                }
+            } else if let Err(e) = self.parse_file(&path) {
+                log::warn!("{e}");
             }
         }
         Ok(())
@@ -311,16 +407,29 @@ impl LoadedFiles {
         let file = fs::File::open(path)?;
         let mut archive = zip::ZipArchive::new(file)?;
 
+        #[cfg(feature = "hdf")]
+        let mut hdf_files = vec![];
         for i in 0..archive.len() {
             let mut file = archive.by_index(i)?;
             if file.is_file() {
                 let mut contents = Vec::new();
                 io::Read::read_to_end(&mut file, &mut contents)?;
-                if let Ok(log) = SupportedFormat::parse_from_buf(&contents) {
+                #[cfg(feature = "hdf")]
+                if skytem_hdf::is_hdf5(&contents) {
+                    if let Some(relative_path) = file.enclosed_name() {
+                        hdf_files.push((relative_path, contents));
+                    }
+                    continue;
+                }
+                if let Ok(log) = SupportedFormat::parse_from_buf(&self.registry, &contents) {
                     self.loaded.push(log);
                 }
             }
         }
+        #[cfg(feature = "hdf")]
+        if !hdf_files.is_empty() {
+            self.parse_hdf_buffers(hdf_files)?;
+        }
         Ok(())
     }
 }
@@ -333,12 +442,23 @@ fn is_zip_file(path: &Path) -> bool {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use skytem_logs::mbed_motor_control::{pid::pidlog::PidLog, status::statuslog::StatusLog};
+    use testresult::TestResult;
+
     const TEST_DATA_STATUS: &str =
         "test_data/mbed_motor_control/v1/20240926_121708/status_20240926_121708_00.bin";
 
     const TEST_DATA_PID: &str =
         "test_data/mbed_motor_control/v1/20240926_121708/pid_20240926_121708_00.bin";
 
+    const TEST_DATA_STATUS_V2: &str =
+        "test_data/mbed_motor_control/v2/20241014_080729/status_20241014_080729_00.bin";
+
+    const TEST_DATA_PID_V2: &str =
+        "test_data/mbed_motor_control/v2/20241014_080729/pid_20241014_080729_00.bin";
+
+    const TEST_DATA_GENERATOR: &str = "test_data/generator/20230124_134738_Gen.log";
+
     #[test]
     fn test_supported_logs_dyn_vec() {
         let data = fs::read(TEST_DATA_STATUS).unwrap();
@@ -351,4 +471,148 @@ mod tests {
         let v: Vec<Box<dyn Plotable>> = vec![Box::new(status_log), Box::new(pidlog)];
         assert_eq!(v.len(), 2);
     }
+
+    #[test]
+    fn test_builtin_registry_detects_test_data_unambiguously() -> TestResult {
+        let registry = logs::builtin_registry();
+        for (path, expected_format) in [
+            (TEST_DATA_STATUS, StatusLog::DESCRIPTIVE_NAME),
+            (TEST_DATA_PID, PidLog::DESCRIPTIVE_NAME),
+            (TEST_DATA_STATUS_V2, StatusLog::DESCRIPTIVE_NAME),
+            (TEST_DATA_PID_V2, PidLog::DESCRIPTIVE_NAME),
+            (
+                TEST_DATA_GENERATOR,
+                skytem_logs::generator::GeneratorLog::DESCRIPTIVE_NAME,
+            ),
+        ] {
+            let data = fs::read(path)?;
+            let log_if::registry::Detection::Match(format) = registry.detect(&data) else {
+                panic!("Expected exactly one format to match {path}");
+            };
+            assert_eq!(format.name(), expected_format);
+            let log = SupportedFormat::parse_from_buf(&registry, &data)?;
+            assert!(!log.raw_plots().is_empty());
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_user_format_definitions_dont_make_builtin_formats_ambiguous() -> TestResult {
+        // A definition of the same format as the built-in PID log, registered twice
+        let definition = format!(
+            "kind = \"mbed\"\n{}",
+            fs::read_to_string("crates/skytem_logs/schemas/mbed_pid_v2.toml")?
+                .replace(r#"name = "Mbed PID v2""#, r#"name = "User PID v2""#)
+        );
+        let mut loaded_files = LoadedFiles::default();
+        loaded_files.register_user_format(&definition)?;
+        loaded_files.register_user_format(&definition)?;
+        assert_eq!(
+            loaded_files.registry.formats().count(),
+            logs::builtin_registry().formats().count() + 1
+        );
+
+        let data = fs::read(TEST_DATA_PID_V2)?;
+        let log_if::registry::Detection::Match(format) = loaded_files.registry.detect(&data) else {
+            panic!("Expected the built-in format to match");
+        };
+        assert_eq!(format.name(), PidLog::DESCRIPTIVE_NAME);
+        loaded_files.parse_raw_buffer("pid.bin", &data)?;
+        assert_eq!(loaded_files.take_loaded_files().len(), 1);
+        Ok(())
+    }
+
+    #[test]
+    fn test_bundled_schemas_are_registered() -> TestResult {
+        let registry = logs::builtin_registry();
+        for schema in skytem_logs::mbed_motor_control::schema::bundled_schemas() {
+            assert!(
+                registry
+                    .formats()
+                    .any(|format| format.name() == schema.name()),
+                "'{}' is not registered",
+                schema.name()
+            );
+        }
+
+        // The handwritten parser doesn't claim versions it can't parse, those are left to the bundled schemas
+        let mut data = fs::read(TEST_DATA_PID_V2)?;
+        data[128..130].copy_from_slice(&3_u16.to_le_bytes());
+        assert!(matches!(
+            registry.detect(&data),
+            log_if::registry::Detection::Unrecognized
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn test_parquet_export_is_loaded_by_builtin_registry() -> TestResult {
+        let registry = logs::builtin_registry();
+        let generator_log =
+            SupportedFormat::parse_from_buf(&registry, &fs::read(TEST_DATA_GENERATOR)?)?;
+        let mut parquet = vec![];
+        columnar::write_parquet(&mut parquet, &generator_log)?;
+
+        let log = SupportedFormat::parse_from_buf(&registry, &parquet)?;
+
+        assert_eq!(log.descriptive_name(), generator_log.descriptive_name());
+        assert_eq!(log.raw_plots().len(), generator_log.raw_plots().len());
+        assert_eq!(log.first_timestamp(), generator_log.first_timestamp());
+        Ok(())
+    }
+
+    #[cfg(feature = "hdf")]
+    #[cfg(not(target_arch = "wasm32"))]
+    #[test]
+    fn test_parse_hdf_from_buffer() -> TestResult {
+        let data = fs::read("test_data/hdf5/bifrost_current/20240930_100137_bifrost.h5")?;
+        let mut loaded_files = LoadedFiles::default();
+        loaded_files.parse_raw_buffer("bifrost.h5", &data)?;
+        let loaded = loaded_files.take_loaded_files();
+        assert_eq!(loaded.len(), 1);
+        assert_eq!(loaded[0].descriptive_name(), "TX Loop Current");
+        Ok(())
+    }
+
+    #[cfg(feature = "hdf")]
+    #[cfg(not(target_arch = "wasm32"))]
+    #[test]
+    fn test_hdf_files_in_zip_archive_are_loaded_past_a_corrupt_file() -> TestResult {
+        let mut corrupt = skytem_hdf::HDF5_SIGNATURE.to_vec();
+        corrupt.resize(1024, 0);
+        let dir = tempfile::tempdir()?;
+        let zip_path = dir.path().join("field_data.zip");
+        let mut zip = zip::ZipWriter::new(fs::File::create(&zip_path)?);
+        let options = zip::write::SimpleFileOptions::default()
+            .compression_method(zip::CompressionMethod::Stored);
+        zip.start_file("a_corrupt.h5", options)?;
+        io::Write::write_all(&mut zip, &corrupt)?;
+        zip.start_file("b_bifrost.h5", options)?;
+        io::Write::write_all(
+            &mut zip,
+            &fs::read("test_data/hdf5/bifrost_current/20240930_100137_bifrost.h5")?,
+        )?;
+        zip.finish()?;
+
+        let mut loaded_files = LoadedFiles::default();
+        let err = loaded_files
+            .parse_path(&zip_path)
+            .expect_err("Expected the corrupt file to be reported");
+
+        assert!(err.to_string().contains("a_corrupt.h5"), "{err}");
+        assert!(!err.to_string().contains("b_bifrost.h5"), "{err}");
+        let loaded = loaded_files.take_loaded_files();
+        assert_eq!(loaded.len(), 1);
+        assert_eq!(loaded[0].descriptive_name(), "TX Loop Current");
+        Ok(())
+    }
+
+    #[test]
+    fn test_hdf_content_is_not_parsed_as_a_log() {
+        let mut content = skytem_hdf::HDF5_SIGNATURE.to_vec();
+        content.resize(1024, 0);
+        let err = SupportedFormat::parse_from_buf(&logs::builtin_registry(), &content)
+            .expect_err("Expected HDF5 content to be rejected");
+        assert!(err.to_string().contains("HDF5"));
+    }
 }
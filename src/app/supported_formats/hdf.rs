@@ -1,7 +1,11 @@
 use chrono::{DateTime, Utc};
 use log_if::prelude::*;
 use serde::{Deserialize, Serialize};
-use skytem_hdf::bifrost::BifrostLoopCurrent;
+use skytem_hdf::{
+    bifrost::{BifrostLoopCurrent, BifrostStream, Waveform},
+    export::ExportedLog,
+    generic::GenericHdf,
+};
 
 /// Represents a supported HDF format, which can be any of the supported HDF format types.
 ///
@@ -9,6 +13,20 @@ use skytem_hdf::bifrost::BifrostLoopCurrent;
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub enum SupportedHdfFormat {
     BifrostLoopCurrent(BifrostLoopCurrent),
+    BifrostStream(BifrostStream),
+    Generic(GenericHdf),
+    Exported(ExportedLog),
+}
+
+impl SupportedHdfFormat {
+    /// The samples of each period, for formats that are sampled in periods
+    pub fn waveform(&self) -> Option<&Waveform> {
+        match self {
+            Self::BifrostLoopCurrent(hdf) => hdf.waveform(),
+            Self::BifrostStream(hdf) => hdf.waveform(),
+            Self::Generic(_) | Self::Exported(_) => None,
+        }
+    }
 }
 
 impl From<BifrostLoopCurrent> for SupportedHdfFormat {
@@ -17,34 +35,67 @@ impl From<BifrostLoopCurrent> for SupportedHdfFormat {
     }
 }
 
+impl From<BifrostStream> for SupportedHdfFormat {
+    fn from(value: BifrostStream) -> Self {
+        Self::BifrostStream(value)
+    }
+}
+
+impl From<GenericHdf> for SupportedHdfFormat {
+    fn from(value: GenericHdf) -> Self {
+        Self::Generic(value)
+    }
+}
+
+impl From<ExportedLog> for SupportedHdfFormat {
+    fn from(value: ExportedLog) -> Self {
+        Self::Exported(value)
+    }
+}
+
 impl Plotable for SupportedHdfFormat {
     fn raw_plots(&self) -> &[RawPlot] {
         match self {
             Self::BifrostLoopCurrent(hdf) => hdf.raw_plots(),
+            Self::BifrostStream(hdf) => hdf.raw_plots(),
+            Self::Generic(hdf) => hdf.raw_plots(),
+            Self::Exported(hdf) => hdf.raw_plots(),
         }
     }
 
     fn first_timestamp(&self) -> DateTime<Utc> {
         match self {
             Self::BifrostLoopCurrent(hdf) => hdf.first_timestamp(),
+            Self::BifrostStream(hdf) => hdf.first_timestamp(),
+            Self::Generic(hdf) => hdf.first_timestamp(),
+            Self::Exported(hdf) => hdf.first_timestamp(),
         }
     }
 
     fn descriptive_name(&self) -> &str {
         match self {
             Self::BifrostLoopCurrent(hdf) => hdf.descriptive_name(),
+            Self::BifrostStream(hdf) => hdf.descriptive_name(),
+            Self::Generic(hdf) => hdf.descriptive_name(),
+            Self::Exported(hdf) => hdf.descriptive_name(),
         }
     }
 
     fn labels(&self) -> Option<&[PlotLabels]> {
         match self {
             Self::BifrostLoopCurrent(hdf) => hdf.labels(),
+            Self::BifrostStream(hdf) => hdf.labels(),
+            Self::Generic(hdf) => hdf.labels(),
+            Self::Exported(hdf) => hdf.labels(),
         }
     }
 
     fn metadata(&self) -> Option<Vec<(String, String)>> {
         match self {
             Self::BifrostLoopCurrent(hdf) => hdf.metadata(),
+            Self::BifrostStream(hdf) => hdf.metadata(),
+            Self::Generic(hdf) => hdf.metadata(),
+            Self::Exported(hdf) => hdf.metadata(),
         }
     }
 }
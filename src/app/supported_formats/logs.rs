@@ -1,104 +1,89 @@
 use chrono::{DateTime, Utc};
 use log_if::prelude::*;
-use parse_info::ParseInfo;
-use serde::{Deserialize, Serialize};
+use parse_info::{ParseInfo, ParsedBytes, TotalBytes};
 use skytem_logs::{
     generator::GeneratorLog,
-    mbed_motor_control::{pid::pidlog::PidLog, status::statuslog::StatusLog},
+    mbed_motor_control::{
+        pid::pidlog::PidLog,
+        schema::{bundled_schemas, MbedSchemaFormat},
+        status::statuslog::StatusLog,
+    },
     navsys::NavSysSps,
 };
+use std::fmt;
 
 pub(crate) mod parse_info;
 
-/// Represents a supported log format, which can be any of the supported log format types.
-///
-/// This simply serves to encapsulate all the supported log format in a single type
-#[derive(Debug, Clone, Deserialize, Serialize)]
-pub enum SupportedLog {
-    MbedPid(PidLog, ParseInfo),
-    MbedStatus(StatusLog, ParseInfo),
-    Generator(GeneratorLog, ParseInfo),
-    NavSysSps(NavSysSps, ParseInfo),
+/// Builds a [`FormatRegistry`] with all the log formats that are supported out of the box
+pub(crate) fn builtin_registry() -> FormatRegistry {
+    let registry = FormatRegistry::new()
+        .with_parseable::<PidLog>("PID controller log from the Mbed motor control")
+        .with_parseable::<StatusLog>("Status log from the Mbed motor control")
+        .with_parseable::<GeneratorLog>("Generator log with power, voltages and currents")
+        .with_parseable::<NavSysSps>("Navsys log with altimeter, GPS and tilt data")
+        .with(columnar::ParquetFormat)
+        .with(columnar::ArrowIpcFormat);
+    bundled_schemas()
+        .into_iter()
+        .map(MbedSchemaFormat::new)
+        .fold(registry, FormatRegistry::with_bundled)
 }
 
-impl SupportedLog {
-    pub(crate) fn parse_info(&self) -> ParseInfo {
-        match self {
-            Self::MbedPid(_, parse_info)
-            | Self::MbedStatus(_, parse_info)
-            | Self::NavSysSps(_, parse_info)
-            | Self::Generator(_, parse_info) => *parse_info,
-        }
-    }
+/// A log that was parsed by one of the formats in a [`FormatRegistry`]
+pub struct SupportedLog {
+    log: Box<dyn Plotable>,
+    parse_info: ParseInfo,
 }
 
-impl From<(PidLog, ParseInfo)> for SupportedLog {
-    fn from(value: (PidLog, ParseInfo)) -> Self {
-        Self::MbedPid(value.0, value.1)
-    }
-}
-
-impl From<(StatusLog, ParseInfo)> for SupportedLog {
-    fn from(value: (StatusLog, ParseInfo)) -> Self {
-        Self::MbedStatus(value.0, value.1)
+impl SupportedLog {
+    pub(crate) fn parse_info(&self) -> &ParseInfo {
+        &self.parse_info
     }
 }
 
-impl From<(GeneratorLog, ParseInfo)> for SupportedLog {
-    fn from(value: (GeneratorLog, ParseInfo)) -> Self {
-        Self::Generator(value.0, value.1)
+impl fmt::Debug for SupportedLog {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SupportedLog")
+            .field("log", &self.log.descriptive_name())
+            .field("parse_info", &self.parse_info)
+            .finish()
     }
 }
 
-impl From<(NavSysSps, ParseInfo)> for SupportedLog {
-    fn from(value: (NavSysSps, ParseInfo)) -> Self {
-        Self::NavSysSps(value.0, value.1)
+impl From<ParsedLog> for SupportedLog {
+    fn from(value: ParsedLog) -> Self {
+        let ParsedLog {
+            log,
+            parsed_bytes,
+            total_bytes,
+            diagnostics,
+        } = value;
+        Self {
+            log,
+            parse_info: ParseInfo::new(ParsedBytes(parsed_bytes), TotalBytes(total_bytes))
+                .with_diagnostics(diagnostics),
+        }
     }
 }
 
 impl Plotable for SupportedLog {
     fn raw_plots(&self) -> &[RawPlot] {
-        match self {
-            Self::MbedPid(l, _) => l.raw_plots(),
-            Self::MbedStatus(l, _) => l.raw_plots(),
-            Self::Generator(l, _) => l.raw_plots(),
-            Self::NavSysSps(l, _) => l.raw_plots(),
-        }
+        self.log.raw_plots()
     }
 
     fn first_timestamp(&self) -> DateTime<Utc> {
-        match self {
-            Self::MbedPid(l, _) => l.first_timestamp(),
-            Self::MbedStatus(l, _) => l.first_timestamp(),
-            Self::Generator(l, _) => l.first_timestamp(),
-            Self::NavSysSps(l, _) => l.first_timestamp(),
-        }
+        self.log.first_timestamp()
     }
 
     fn descriptive_name(&self) -> &str {
-        match self {
-            Self::MbedPid(l, _) => l.descriptive_name(),
-            Self::MbedStatus(l, _) => l.descriptive_name(),
-            Self::Generator(l, _) => l.descriptive_name(),
-            Self::NavSysSps(l, _) => l.descriptive_name(),
-        }
+        self.log.descriptive_name()
     }
 
     fn labels(&self) -> Option<&[PlotLabels]> {
-        match self {
-            Self::MbedPid(l, _) => l.labels(),
-            Self::MbedStatus(l, _) => l.labels(),
-            Self::Generator(l, _) => l.labels(),
-            Self::NavSysSps(l, _) => l.labels(),
-        }
+        self.log.labels()
     }
 
     fn metadata(&self) -> Option<Vec<(String, String)>> {
-        match self {
-            Self::MbedPid(l, _) => l.metadata(),
-            Self::MbedStatus(l, _) => l.metadata(),
-            Self::Generator(l, _) => l.metadata(),
-            Self::NavSysSps(l, _) => l.metadata(),
-        }
+        self.log.metadata()
     }
 }
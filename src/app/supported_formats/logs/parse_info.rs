@@ -1,3 +1,4 @@
+use log_if::prelude::ParseDiagnostic;
 use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
@@ -6,10 +7,12 @@ pub struct ParsedBytes(pub usize);
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
 pub struct TotalBytes(pub usize);
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
 pub struct ParseInfo {
     parsed_bytes: ParsedBytes,
     total_bytes: TotalBytes,
+    #[serde(default)]
+    diagnostics: Vec<ParseDiagnostic>,
 }
 
 impl ParseInfo {
@@ -24,9 +27,16 @@ impl ParseInfo {
         Self {
             parsed_bytes,
             total_bytes,
+            diagnostics: vec![],
         }
     }
 
+    /// Attach the diagnostics collected while parsing, i.e. regions of the input that failed to parse
+    pub fn with_diagnostics(mut self, diagnostics: Vec<ParseDiagnostic>) -> Self {
+        self.diagnostics = diagnostics;
+        self
+    }
+
     pub fn parsed_bytes(&self) -> usize {
         self.parsed_bytes.0
     }
@@ -38,4 +48,8 @@ impl ParseInfo {
     pub fn remainder_bytes(&self) -> usize {
         self.total_bytes.0 - self.parsed_bytes.0
     }
+
+    pub fn diagnostics(&self) -> &[ParseDiagnostic] {
+        &self.diagnostics
+    }
 }
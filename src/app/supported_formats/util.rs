@@ -13,3 +13,27 @@ pub fn path_has_hdf_extension(path: &std::path::Path) -> bool {
     }
     false
 }
+
+/// Returns true if the path has an HDF5 file extension, or on native if the file has the HDF5 signature
+pub fn path_is_hdf(path: &std::path::Path) -> bool {
+    if path_has_hdf_extension(path) {
+        return true;
+    }
+    #[cfg(not(target_arch = "wasm32"))]
+    return skytem_hdf::path_has_hdf5_signature(path).unwrap_or(false);
+    #[cfg(target_arch = "wasm32")]
+    false
+}
+
+/// Returns true if the path has the extension of a user format definition file
+pub fn path_is_format_definition(path: &std::path::Path) -> bool {
+    path.extension().is_some_and(|ext| {
+        ext.eq_ignore_ascii_case(skytem_logs::user_format::FORMAT_DEFINITION_EXTENSION)
+    })
+}
+
+/// The directory that user format definitions are loaded from at startup
+#[cfg(not(target_arch = "wasm32"))]
+pub fn user_formats_dir() -> Option<std::path::PathBuf> {
+    eframe::storage_dir("plotinator3000").map(|dir| dir.join("formats"))
+}
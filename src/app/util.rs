@@ -83,5 +83,9 @@ fn list_supported_hdf_formats(ui: &mut egui::Ui) {
             "https://github.com/luftkode/bifrost-app",
         ));
         ui.end_row();
+
+        ui.label(RichText::new("Other HDF5 files").strong());
+        ui.label("Browse the groups, datasets and attributes and choose which datasets to plot");
+        ui.end_row();
     }
 }
@@ -0,0 +1,252 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use egui::{Color32, ColorImage, RichText, TextureHandle, TextureOptions};
+use egui_phosphor::regular;
+use egui_plot::{Line, Plot, PlotImage, PlotPoint, PlotPoints};
+use skytem_hdf::bifrost::{PeriodStats, Waveform};
+
+/// At most this many periods are drawn in the overlay, if more periods are selected only every n'th period is drawn.
+const MAX_OVERLAY_LINES: usize = 200;
+
+const FIRST_PERIOD_COLOR: Color32 = Color32::from_rgb(30, 90, 230);
+const LAST_PERIOD_COLOR: Color32 = Color32::from_rgb(230, 40, 30);
+
+/// Extracts a statistic from the [`PeriodStats`] of a period, if the statistic could be determined
+type PeriodStat = fn(&PeriodStats) -> Option<f64>;
+
+#[derive(Debug, strum_macros::Display, Clone, Copy, PartialEq, Eq)]
+enum WaveformViewMode {
+    Overlay,
+    Waterfall,
+}
+
+/// The waterfall image of the selected periods of a channel, kept until the selection changes.
+struct WaterfallTexture {
+    channel: usize,
+    periods: (usize, usize),
+    texture: TextureHandle,
+}
+
+/// A window that shows the periods of a [`Waveform`], either overlaid on top of each other or as a waterfall
+/// over time, along with statistics of the selected periods.
+#[allow(
+    missing_debug_implementations,
+    reason = "egui's TextureHandle does not implement Debug"
+)]
+pub(crate) struct WaveformViewer {
+    id: egui::Id,
+    title: String,
+    waveform: Waveform,
+    channel: usize,
+    first_period: usize,
+    last_period: usize,
+    mode: WaveformViewMode,
+    waterfall: Option<WaterfallTexture>,
+    open: bool,
+}
+
+impl WaveformViewer {
+    pub(crate) fn new(source_name: &str, waveform: Waveform) -> Self {
+        static NEXT_ID: AtomicUsize = AtomicUsize::new(0);
+        Self {
+            id: egui::Id::new(("waveform_viewer", NEXT_ID.fetch_add(1, Ordering::Relaxed))),
+            title: format!("{} {source_name}: {}", regular::WAVE_SINE, waveform.name()),
+            channel: 0,
+            first_period: 0,
+            last_period: waveform.period_count().saturating_sub(1),
+            mode: WaveformViewMode::Overlay,
+            waterfall: None,
+            waveform,
+            open: true,
+        }
+    }
+
+    pub(crate) fn is_open(&self) -> bool {
+        self.open
+    }
+
+    pub(crate) fn show(&mut self, ctx: &egui::Context) {
+        let mut open = self.open;
+        egui::Window::new(&self.title)
+            .id(self.id)
+            .open(&mut open)
+            .default_size([600.0, 400.0])
+            .show(ctx, |ui| {
+                if self.waveform.period_count() == 0 || self.waveform.channels().is_empty() {
+                    ui.label("The waveform has no periods");
+                    return;
+                }
+                self.show_controls(ui);
+                self.show_stats(ui);
+                ui.separator();
+                match self.mode {
+                    WaveformViewMode::Overlay => self.show_overlay(ui),
+                    WaveformViewMode::Waterfall => self.show_waterfall(ui),
+                }
+            });
+        self.open = open;
+    }
+
+    fn show_controls(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            let channels = self.waveform.channels();
+            egui::ComboBox::from_id_salt((self.id, "channel"))
+                .selected_text(channels[self.channel].name())
+                .show_ui(ui, |ui| {
+                    for (idx, channel) in channels.iter().enumerate() {
+                        ui.selectable_value(&mut self.channel, idx, channel.name());
+                    }
+                });
+            for mode in [WaveformViewMode::Overlay, WaveformViewMode::Waterfall] {
+                ui.selectable_value(&mut self.mode, mode, mode.to_string());
+            }
+        });
+        ui.horizontal(|ui| {
+            let last = self.waveform.period_count() - 1;
+            ui.label("Periods");
+            ui.add(egui::DragValue::new(&mut self.first_period).range(0..=self.last_period));
+            ui.label("to");
+            ui.add(egui::DragValue::new(&mut self.last_period).range(self.first_period..=last));
+            ui.label(RichText::new(format!("of {}", last + 1)).weak());
+        });
+    }
+
+    fn show_stats(&self, ui: &mut egui::Ui) {
+        let stats =
+            &self.waveform.channels()[self.channel].stats()[self.first_period..=self.last_period];
+        let unit = self.waveform.unit();
+        egui::Grid::new((self.id, "stats"))
+            .striped(true)
+            .show(ui, |ui| {
+                for heading in ["", "Min", "Mean", "Max"] {
+                    ui.label(RichText::new(heading).strong());
+                }
+                ui.end_row();
+                let rows: [(String, PeriodStat); 3] = [
+                    (format!("Peak [{unit}]"), |s| Some(s.peak())),
+                    ("Rise time [µs]".to_owned(), |s| {
+                        s.rise_time_ns().map(|ns| ns / 1e3)
+                    }),
+                    ("Fall time [µs]".to_owned(), |s| {
+                        s.fall_time_ns().map(|ns| ns / 1e3)
+                    }),
+                ];
+                for (label, stat) in rows {
+                    ui.label(label);
+                    match min_mean_max(stats.iter().filter_map(stat)) {
+                        Some(min_mean_max) => {
+                            for value in min_mean_max {
+                                ui.label(format!("{value:.3}"));
+                            }
+                        }
+                        None => {
+                            ui.label("-");
+                        }
+                    }
+                    ui.end_row();
+                }
+            });
+    }
+
+    fn show_overlay(&self, ui: &mut egui::Ui) {
+        let period_count = self.last_period - self.first_period + 1;
+        let stride = period_count.div_ceil(MAX_OVERLAY_LINES);
+        let unit = self.waveform.unit();
+        Plot::new((self.id, "overlay"))
+            .x_axis_label("Time since period start [µs]")
+            .y_axis_label(unit.as_str())
+            .show(ui, |plot_ui| {
+                for period in (self.first_period..=self.last_period).step_by(stride) {
+                    let points: Vec<[f64; 2]> = self
+                        .waveform
+                        .sample_offsets_ns(period)
+                        .zip(self.waveform.period(self.channel, period))
+                        .map(|(offset_ns, sample)| [offset_ns / 1e3, *sample])
+                        .collect();
+                    let progress = (period - self.first_period) as f32 / period_count as f32;
+                    plot_ui.line(
+                        Line::new(PlotPoints::new(points))
+                            .color(FIRST_PERIOD_COLOR.lerp_to_gamma(LAST_PERIOD_COLOR, progress)),
+                    );
+                }
+            });
+        ui.label(
+            RichText::new(format!(
+                "Showing every {stride}. period, earliest in blue and latest in red"
+            ))
+            .weak(),
+        );
+    }
+
+    fn show_waterfall(&mut self, ui: &mut egui::Ui) {
+        let periods = (self.first_period, self.last_period);
+        if !self
+            .waterfall
+            .as_ref()
+            .is_some_and(|w| w.channel == self.channel && w.periods == periods)
+        {
+            let image = waterfall_image(&self.waveform, self.channel, periods);
+            self.waterfall = Some(WaterfallTexture {
+                channel: self.channel,
+                periods,
+                texture: ui.ctx().load_texture(
+                    format!("{}_waterfall", self.title),
+                    image,
+                    TextureOptions::NEAREST,
+                ),
+            });
+        }
+        let Some(waterfall) = &self.waterfall else {
+            return;
+        };
+        let period_us = self.waveform.period_durations_ns()[self.first_period] / 1e3;
+        let period_count = (self.last_period - self.first_period + 1) as f64;
+        Plot::new((self.id, "waterfall"))
+            .x_axis_label("Time since period start [µs]")
+            .y_axis_label("Period")
+            .show(ui, |plot_ui| {
+                plot_ui.image(PlotImage::new(
+                    &waterfall.texture,
+                    PlotPoint::new(
+                        period_us / 2.0,
+                        self.first_period as f64 + period_count / 2.0,
+                    ),
+                    [period_us as f32, period_count as f32],
+                ));
+            });
+        ui.label(RichText::new("Lowest value in blue and highest value in red").weak());
+    }
+}
+
+/// An image with a row per period, the latest period at the top, and a column per sample of the period
+fn waterfall_image(waveform: &Waveform, channel: usize, periods: (usize, usize)) -> ColorImage {
+    let (first, last) = periods;
+    let [min, _, max] = min_mean_max(
+        (first..=last)
+            .flat_map(|period| waveform.period(channel, period))
+            .copied(),
+    )
+    .unwrap_or_default();
+    let range = if max > min { max - min } else { 1.0 };
+    let pixels = (first..=last)
+        .rev()
+        .flat_map(|period| waveform.period(channel, period))
+        .map(|sample| {
+            let normalized = ((sample - min) / range) as f32;
+            FIRST_PERIOD_COLOR.lerp_to_gamma(LAST_PERIOD_COLOR, normalized)
+        })
+        .collect();
+    ColorImage {
+        size: [waveform.samples_per_period(), last - first + 1],
+        pixels,
+    }
+}
+
+/// The minimum, mean and maximum of the finite `values`, or [`None`] if there are none
+fn min_mean_max(values: impl Iterator<Item = f64>) -> Option<[f64; 3]> {
+    let (count, sum, min, max) = values.filter(|v| v.is_finite()).fold(
+        (0_usize, 0.0, f64::INFINITY, f64::NEG_INFINITY),
+        |(count, sum, min, max), v| (count + 1, sum + v, min.min(v), max.max(v)),
+    );
+    (count > 0).then(|| [min, sum / count as f64, max])
+}
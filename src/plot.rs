@@ -8,12 +8,18 @@ use serde::{Deserialize, Serialize};
 use axis_config::AxisConfig;
 use egui::{Id, Response};
 use egui_plot::Legend;
+use pid_analysis_panel::PidAnalysisPanel;
+use spectrogram_panel::SpectrogramPanel;
+use spectrum_panel::SpectrumPanel;
 
 use crate::app::supported_formats::SupportedFormat;
 mod axis_config;
+mod pid_analysis_panel;
 mod plot_graphics;
 mod plot_settings;
 mod plot_ui;
+mod spectrogram_panel;
+mod spectrum_panel;
 mod util;
 
 #[derive(Debug, strum_macros::Display, Copy, Clone, PartialEq, Eq)]
@@ -36,6 +42,12 @@ pub struct LogPlotUi {
     plot_settings: PlotSettings,
     x_min_max: Option<(f64, f64)>,
     link_group: Option<Id>,
+    #[serde(default)]
+    spectrum: SpectrumPanel,
+    #[serde(default)]
+    spectrogram: SpectrogramPanel,
+    #[serde(default)]
+    pid_analysis: PidAnalysisPanel,
 }
 
 impl Default for LogPlotUi {
@@ -48,6 +60,9 @@ impl Default for LogPlotUi {
             plot_settings: PlotSettings::default(),
             x_min_max: None,
             link_group: None,
+            spectrum: SpectrumPanel::default(),
+            spectrogram: SpectrogramPanel::default(),
+            pid_analysis: PidAnalysisPanel::default(),
         }
     }
 }
@@ -73,6 +88,9 @@ impl LogPlotUi {
             plot_settings,
             x_min_max,
             link_group,
+            spectrum,
+            spectrogram,
+            pid_analysis,
         } = self;
         if link_group.is_none() {
             link_group.replace(ui.id().with("linked_plots"));
@@ -85,7 +103,13 @@ impl LogPlotUi {
 
         plots.calc_all_plot_x_min_max(x_min_max);
 
-        plot_ui::show_settings_grid(ui, line_width, axis_config, plot_settings);
+        plot_ui::show_settings_grid(
+            ui,
+            line_width,
+            axis_config,
+            (plot_settings, plots),
+            (spectrum, spectrogram, pid_analysis),
+        );
 
         for log in logs {
             util::add_plot_data_to_plot_collections(plots, log, plot_settings);
@@ -102,17 +126,166 @@ impl LogPlotUi {
 
         plot_settings.refresh(plots);
 
+        let link_group = link_group.expect("uninitialized link group id");
         ui.vertical(|ui| {
-            plot_graphics::paint_plots(
+            let visible_x_range = plot_graphics::paint_plots(
                 ui,
                 plots,
                 plot_settings,
                 legend_cfg,
                 axis_config,
-                link_group.expect("uninitialized link group id"),
+                link_group,
                 *line_width,
+                toasts,
             );
+            spectrum.show(ui.ctx(), plots, visible_x_range);
+            spectrogram.show(ui.ctx(), plots, link_group);
+            pid_analysis.show(ui.ctx(), plots, plot_settings);
         })
         .response
     }
+
+    /// Writes the loaded logs to an HDF5 file, with the timestamps of the plots as currently offset by any
+    /// changes to the start date of the logs.
+    #[cfg(feature = "hdf")]
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn export_hdf(&self, path: &std::path::Path) -> std::io::Result<()> {
+        let logs = self.exportable_logs(skytem_hdf::export::ExportedLog::new);
+        skytem_hdf::export::write_logs(path, &logs)
+    }
+
+    /// Writes each loaded log to a Parquet file in `dir`, with the timestamps of the plots as currently offset
+    /// by any changes to the start date of the logs. Returns the number of written files.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn export_parquet(&self, dir: &std::path::Path) -> std::io::Result<usize> {
+        use log_if::prelude::Plotable as _;
+
+        let logs = self.exportable_logs(columnar::ColumnarLog::new);
+        let index_width = logs.len().to_string().len();
+        for (idx, log) in logs.iter().enumerate() {
+            let file_name: String = log
+                .descriptive_name()
+                .chars()
+                .map(|c| {
+                    if c.is_alphanumeric() || c == '-' {
+                        c
+                    } else {
+                        '_'
+                    }
+                })
+                .collect();
+            let file = std::fs::File::create(
+                dir.join(format!("{idx:0index_width$}_{file_name}.parquet")),
+            )?;
+            columnar::write_parquet(std::io::BufWriter::new(file), log)?;
+        }
+        Ok(logs.len())
+    }
+
+    /// Collects each loaded log with its recorded plots, as currently offset in time, by passing its descriptive
+    /// name, start date, plots and metadata to `new_log`.
+    #[cfg(not(target_arch = "wasm32"))]
+    fn exportable_logs<T>(
+        &self,
+        new_log: impl Fn(
+            String,
+            chrono::DateTime<chrono::Utc>,
+            Vec<log_if::prelude::RawPlot>,
+            Vec<(String, String)>,
+        ) -> T,
+    ) -> Vec<T> {
+        use log_if::prelude::{ExpectedPlotRange, RawPlot};
+
+        let plot_data_by_range = [
+            (self.plots.percentage(), ExpectedPlotRange::Percentage),
+            (
+                self.plots.one_to_hundred(),
+                ExpectedPlotRange::OneToOneHundred,
+            ),
+            (self.plots.thousands(), ExpectedPlotRange::Thousands),
+        ];
+        self.plot_settings
+            .loaded_logs()
+            .iter()
+            .map(|settings| {
+                let raw_plots = plot_data_by_range
+                    .iter()
+                    .flat_map(|(plot_data, expected_range)| {
+                        plot_data
+                            .plots()
+                            .iter()
+                            // Plots computed from the recorded plots, e.g. filtered, are not data of the log
+                            .filter(|plot| {
+                                plot.log_id() == settings.log_id() && plot.derivation().is_none()
+                            })
+                            .map(|plot| {
+                                RawPlot::new(
+                                    plot.name().to_owned(),
+                                    plot.points().collect(),
+                                    *expected_range,
+                                )
+                            })
+                    })
+                    .collect();
+                new_log(
+                    settings.descriptive_name().to_owned(),
+                    settings.start_date(),
+                    raw_plots,
+                    settings.metadata_pairs(),
+                )
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+#[cfg(not(target_arch = "wasm32"))]
+mod tests {
+    use std::path::Path;
+
+    use log_if::prelude::Plotable as _;
+    use plot_util::{derived::Operation, filters::SignalFilter};
+    use testresult::TestResult;
+
+    use super::*;
+    use crate::app::supported_formats::LoadedFiles;
+
+    #[test]
+    fn test_export_leaves_out_computed_plots() -> TestResult {
+        let mut loaded_files = LoadedFiles::default();
+        loaded_files.parse_path(Path::new("test_data/generator/20230124_134738_Gen.log"))?;
+        let mut plot_ui = LogPlotUi::default();
+        for log in loaded_files.take_loaded_files() {
+            util::add_plot_data_to_plot_collections(
+                &mut plot_ui.plots,
+                &log,
+                &mut plot_ui.plot_settings,
+            );
+        }
+        let recorded_plots = plot_ui.plot_count();
+        let source = plot_ui.plots.iter().next().expect("no plots");
+        let source_label = source.label().to_owned();
+        let companion = source.derived(Operation::Filter(SignalFilter::MovingAverage {
+            samples: 3,
+        }))?;
+        let companion_name = companion.name().to_owned();
+        plot_ui
+            .plots
+            .plot_data_mut()
+            .into_iter()
+            .find(|plot_data| plot_data.contains_plot(&source_label))
+            .expect("no plot data with the source plot")
+            .add_derived_plot(companion);
+        assert_eq!(plot_ui.plot_count(), recorded_plots + 1);
+
+        let logs = plot_ui.exportable_logs(columnar::ColumnarLog::new);
+
+        assert_eq!(logs.len(), 1);
+        assert_eq!(logs[0].raw_plots().len(), recorded_plots);
+        assert!(logs[0]
+            .raw_plots()
+            .iter()
+            .all(|raw_plot| raw_plot.name() != companion_name));
+        Ok(())
+    }
 }
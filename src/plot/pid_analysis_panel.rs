@@ -0,0 +1,364 @@
+use chrono::{DateTime, Utc};
+use egui::{Color32, RichText};
+use egui_phosphor::regular;
+use plot_util::{
+    step_response::{self, StepResponse, StepSummary},
+    PlotValues, Plots,
+};
+use serde::{Deserialize, Serialize};
+
+use super::plot_settings::{date_settings::LoadedLogSettings, PlotSettings};
+
+const PID_LOG_NAME: &str = "Mbed PID";
+const STATUS_LOG_NAME: &str = "Mbed Status";
+/// A status log whose time range is unknown or doesn't overlap a PID log is only from the same run if they started
+/// at most this many seconds apart
+const MAX_START_DIFFERENCE_S: i64 = 30;
+/// The PID controller gains as they are named in the metadata of a v2 PID log
+const GAIN_NAMES: [&str; 3] = ["Kp", "Ki", "Kd"];
+
+/// What the cached analysis of a PID log was computed from, it's computed again when any of it changes
+#[derive(Debug, Clone, PartialEq)]
+struct AnalysisKey {
+    pid_log_id: usize,
+    status_log_id: Option<usize>,
+    /// The time offsets of the RPM and setpoint plots
+    x_offsets: (f64, f64),
+    min_step: f64,
+    settling_band_percent: f64,
+}
+
+/// The step responses of the RPM of a PID log
+#[derive(Debug, Clone, PartialEq)]
+struct PidAnalysis {
+    log_label: String,
+    /// Kp, Ki and Kd, [`None`] if the log has no config (v1)
+    gains: Option<[String; 3]>,
+    status_log_label: Option<String>,
+    steps: Vec<StepResponse>,
+    summary: StepSummary,
+}
+
+/// A window with the step responses of the RPM in the loaded Mbed PID logs, with the setpoint from the status log
+/// of the same run and the controller gains of each log, to compare tuning iterations.
+#[derive(Debug, PartialEq, Deserialize, Serialize)]
+pub struct PidAnalysisPanel {
+    open: bool,
+    /// Setpoint changes smaller than this (RPM) are not steps
+    min_step: f64,
+    /// The response has settled when it stays within this percentage of the step around the setpoint
+    settling_band_percent: f64,
+    #[serde(skip)]
+    cached: Vec<(AnalysisKey, PidAnalysis)>,
+}
+
+impl Default for PidAnalysisPanel {
+    fn default() -> Self {
+        Self {
+            open: false,
+            min_step: 10.0,
+            settling_band_percent: 5.0,
+            cached: vec![],
+        }
+    }
+}
+
+impl PidAnalysisPanel {
+    pub fn title() -> String {
+        format!("{} PID analysis", regular::GAUGE)
+    }
+
+    /// Shows the toggle button if any Mbed PID logs are loaded
+    pub fn toggle_ui(&mut self, ui: &mut egui::Ui, plot_settings: &PlotSettings) {
+        if pid_logs(plot_settings).next().is_some() {
+            ui.toggle_value(&mut self.open, Self::title())
+                .on_hover_text("Show the rise time, overshoot, settling time and steady-state error of the RPM for each setpoint change");
+        }
+    }
+
+    /// Shows the analysis window if it is open
+    pub fn show(&mut self, ctx: &egui::Context, plots: &Plots, plot_settings: &PlotSettings) {
+        if !self.open {
+            return;
+        }
+        let mut open = self.open;
+        egui::Window::new(Self::title())
+            .open(&mut open)
+            .default_size([700.0, 400.0])
+            .show(ctx, |ui| {
+                self.show_controls(ui);
+                self.update_analyses(plots, plot_settings);
+                if self.cached.is_empty() {
+                    ui.label("No Mbed PID logs are loaded");
+                }
+                egui::ScrollArea::vertical().show(ui, |ui| {
+                    for (key, analysis) in &self.cached {
+                        show_analysis(ui, key.pid_log_id, analysis);
+                    }
+                });
+            });
+        self.open = open;
+    }
+
+    fn show_controls(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal_wrapped(|ui| {
+            ui.label("Minimum step");
+            ui.add(
+                egui::DragValue::new(&mut self.min_step)
+                    .range(1.0..=100_000.0)
+                    .suffix(" RPM"),
+            )
+            .on_hover_text("Smaller setpoint changes are not analyzed as steps");
+            ui.label("Settling band");
+            ui.add(
+                egui::DragValue::new(&mut self.settling_band_percent)
+                    .speed(0.1)
+                    .range(0.1..=50.0)
+                    .suffix(" %"),
+            )
+            .on_hover_text("The RPM has settled when it stays within this percentage of the step around the setpoint");
+        });
+    }
+
+    fn update_analyses(&mut self, plots: &Plots, plot_settings: &PlotSettings) {
+        let mut analyses = Vec::with_capacity(self.cached.len());
+        for pid_log in pid_logs(plot_settings) {
+            let rpm = plots.find_by_label(&PlotValues::label_of("RPM", pid_log.log_id()));
+            let status_log = paired_status_log(plots, plot_settings, pid_log, rpm);
+            let setpoint = status_log.and_then(|status_log| setpoint_plot(plots, status_log));
+            let key = AnalysisKey {
+                pid_log_id: pid_log.log_id(),
+                status_log_id: status_log.map(LoadedLogSettings::log_id),
+                x_offsets: (
+                    rpm.map_or(0.0, PlotValues::x_offset),
+                    setpoint.map_or(0.0, PlotValues::x_offset),
+                ),
+                min_step: self.min_step,
+                settling_band_percent: self.settling_band_percent,
+            };
+            if let Some(idx) = self.cached.iter().position(|(cached, _)| *cached == key) {
+                analyses.push(self.cached.swap_remove(idx));
+                continue;
+            }
+            let steps = match (rpm, setpoint) {
+                (Some(rpm), Some(setpoint)) => step_response::analyze_steps(
+                    &setpoint.points().collect::<Vec<_>>(),
+                    &rpm.points().collect::<Vec<_>>(),
+                    self.min_step,
+                    self.settling_band_percent / 100.0,
+                ),
+                _ => vec![],
+            };
+            let analysis = PidAnalysis {
+                log_label: pid_log.log_label(),
+                gains: gains(&pid_log.metadata_pairs()),
+                status_log_label: status_log.map(LoadedLogSettings::log_label),
+                summary: StepSummary::of(&steps),
+                steps,
+            };
+            analyses.push((key, analysis));
+        }
+        self.cached = analyses;
+    }
+}
+
+fn pid_logs(plot_settings: &PlotSettings) -> impl Iterator<Item = &LoadedLogSettings> {
+    plot_settings
+        .loaded_logs()
+        .iter()
+        .filter(|log| log.descriptive_name().starts_with(PID_LOG_NAME))
+}
+
+/// The status log recorded alongside the `pid_log`, the one of the same run that started closest in time
+fn paired_status_log<'s>(
+    plots: &Plots,
+    plot_settings: &'s PlotSettings,
+    pid_log: &LoadedLogSettings,
+    rpm: Option<&PlotValues>,
+) -> Option<&'s LoadedLogSettings> {
+    let pid_run = Run {
+        start: pid_log.start_date(),
+        time_range_ns: rpm.and_then(time_range_ns),
+    };
+    plot_settings
+        .loaded_logs()
+        .iter()
+        .filter(|log| log.descriptive_name().starts_with(STATUS_LOG_NAME))
+        .filter(|log| {
+            pid_run.is_same_run(&Run {
+                start: log.start_date(),
+                time_range_ns: setpoint_plot(plots, log).and_then(time_range_ns),
+            })
+        })
+        .min_by_key(|log| (log.start_date() - pid_log.start_date()).abs())
+}
+
+fn setpoint_plot<'p>(plots: &'p Plots, status_log: &LoadedLogSettings) -> Option<&'p PlotValues> {
+    plots.find_by_label(&PlotValues::label_of("Setpoint", status_log.log_id()))
+}
+
+/// The first and last timestamp of a plot
+fn time_range_ns(plot: &PlotValues) -> Option<(f64, f64)> {
+    let raw = plot.get_raw();
+    let ([first, _], [last, _]) = (raw.first()?, raw.last()?);
+    Some((first + plot.x_offset(), last + plot.x_offset()))
+}
+
+/// When a log was recorded
+struct Run {
+    start: DateTime<Utc>,
+    /// [`None`] if the log has no points
+    time_range_ns: Option<(f64, f64)>,
+}
+
+impl Run {
+    /// Logs are from the same run if their time ranges overlap, or if they started at most
+    /// [`MAX_START_DIFFERENCE_S`] apart
+    fn is_same_run(&self, other: &Self) -> bool {
+        let overlaps = match (self.time_range_ns, other.time_range_ns) {
+            (Some((first, last)), Some((other_first, other_last))) => {
+                first <= other_last && other_first <= last
+            }
+            _ => false,
+        };
+        overlaps || (self.start - other.start).abs().num_seconds() <= MAX_START_DIFFERENCE_S
+    }
+}
+
+/// Kp, Ki and Kd from the config values in the metadata of a log
+fn gains(metadata: &[(String, String)]) -> Option<[String; 3]> {
+    let gain = |name: &str| {
+        metadata
+            .iter()
+            .find(|(description, _)| description == name)
+            .map(|(_, value)| value.clone())
+    };
+    let [kp, ki, kd] = GAIN_NAMES.map(gain);
+    Some([kp?, ki?, kd?])
+}
+
+fn show_analysis(ui: &mut egui::Ui, pid_log_id: usize, analysis: &PidAnalysis) {
+    egui::CollapsingHeader::new(RichText::new(&analysis.log_label).strong())
+        .id_salt(("pid_analysis", pid_log_id))
+        .default_open(true)
+        .show(ui, |ui| {
+            match &analysis.gains {
+                Some([kp, ki, kd]) => ui.label(format!("Kp {kp}   Ki {ki}   Kd {kd}")),
+                None => ui.label(RichText::new("No controller gains in the log (v1)").weak()),
+            };
+            let Some(status_log_label) = &analysis.status_log_label else {
+                ui.label(
+                    RichText::new(
+                        "No status log for this run is loaded, the setpoint is in the status log",
+                    )
+                    .color(Color32::RED),
+                );
+                return;
+            };
+            ui.label(format!("Setpoint from {status_log_label}"));
+            if analysis.steps.is_empty() {
+                ui.label(RichText::new("No setpoint changes").weak());
+                return;
+            }
+            show_summary(ui, &analysis.summary);
+            show_steps(ui, pid_log_id, &analysis.steps);
+        });
+}
+
+fn show_summary(ui: &mut egui::Ui, summary: &StepSummary) {
+    ui.label(format!(
+        "{} steps, mean rise time {}, overshoot {}, settling time {}, |steady-state error| {}",
+        summary.steps,
+        format_seconds(summary.mean_rise_time),
+        format_percent(summary.mean_overshoot_percent),
+        format_seconds(summary.mean_settling_time),
+        format_rpm(summary.mean_abs_steady_state_error),
+    ));
+}
+
+fn show_steps(ui: &mut egui::Ui, pid_log_id: usize, steps: &[StepResponse]) {
+    egui::Grid::new(("pid_analysis_steps", pid_log_id))
+        .striped(true)
+        .show(ui, |ui| {
+            for header in [
+                "Time",
+                "Setpoint [RPM]",
+                "Duration",
+                "Rise time",
+                "Overshoot",
+                "Settling time",
+                "Steady-state error",
+            ] {
+                ui.label(RichText::new(header).strong());
+            }
+            ui.end_row();
+            for step in steps {
+                ui.label(
+                    DateTime::from_timestamp_nanos(step.start() as i64)
+                        .format("%H:%M:%S%.3f")
+                        .to_string(),
+                );
+                ui.label(format!("{:.0} → {:.0}", step.from(), step.to()));
+                ui.label(format_seconds(Some(step.duration())));
+                ui.label(format_seconds(step.rise_time()));
+                ui.label(format_percent(Some(step.overshoot_percent())));
+                ui.label(format_seconds(step.settling_time()));
+                ui.label(format_rpm(step.steady_state_error()));
+                ui.end_row();
+            }
+        });
+}
+
+fn format_seconds(seconds: Option<f64>) -> String {
+    seconds.map_or_else(|| "N/A".to_owned(), |s| format!("{s:.3} s"))
+}
+
+fn format_percent(percent: Option<f64>) -> String {
+    percent.map_or_else(|| "N/A".to_owned(), |p| format!("{p:.1} %"))
+}
+
+fn format_rpm(rpm: Option<f64>) -> String {
+    rpm.map_or_else(|| "N/A".to_owned(), |rpm| format!("{rpm:.1} RPM"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SECOND_NS: f64 = 1_000_000_000.0;
+
+    fn run(start_s: i64, duration_s: Option<i64>) -> Run {
+        let start = DateTime::from_timestamp(start_s, 0).unwrap();
+        Run {
+            start,
+            time_range_ns: duration_s.map(|duration_s| {
+                let first = start_s as f64 * SECOND_NS;
+                (first, first + duration_s as f64 * SECOND_NS)
+            }),
+        }
+    }
+
+    #[test]
+    fn test_logs_with_overlapping_time_ranges_are_paired() {
+        let pid_log = run(1000, Some(600));
+        // The status log was started late but recorded during the run
+        assert!(pid_log.is_same_run(&run(1300, Some(600))));
+        assert!(run(1300, Some(600)).is_same_run(&pid_log));
+    }
+
+    #[test]
+    fn test_logs_that_start_close_are_paired() {
+        let pid_log = run(1000, None);
+        assert!(pid_log.is_same_run(&run(1000 + MAX_START_DIFFERENCE_S, None)));
+        assert!(pid_log.is_same_run(&run(1000 - MAX_START_DIFFERENCE_S, Some(1))));
+    }
+
+    #[test]
+    fn test_logs_of_other_runs_are_not_paired() {
+        let pid_log = run(1000, Some(600));
+        // A status log of the next day is still the closest when it's the only one loaded
+        assert!(!pid_log.is_same_run(&run(1000 + 24 * 3600, Some(600))));
+        assert!(!pid_log.is_same_run(&run(1000 + 601, Some(600))));
+        assert!(!run(1000, None).is_same_run(&run(1000 + MAX_START_DIFFERENCE_S + 1, None)));
+    }
+}
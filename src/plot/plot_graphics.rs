@@ -1,5 +1,8 @@
+use std::time::Duration;
+
+use egui_notify::Toasts;
 use egui_plot::{AxisHints, HPlacement, Legend, Plot};
-use plot_util::{PlotData, Plots};
+use plot_util::{derived::Operation, PlotData, Plots};
 
 use super::{axis_config::AxisConfig, plot_settings::PlotSettings, PlotType};
 
@@ -14,6 +17,13 @@ use super::{axis_config::AxisConfig, plot_settings::PlotSettings, PlotType};
 /// * `axis_cfg` - For axis customization.
 /// * `link_group` - An [`egui::Id`] for linking plots.
 /// * `line_width` - The width of plot lines.
+/// * `toasts` - For notifying about plots that could not be added from the context menu.
+///
+/// Returns the x range (time) that is visible in the first painted plot, if any.
+#[allow(
+    clippy::too_many_arguments,
+    reason = "The settings are owned by different parts of the plot UI and are only passed on"
+)]
 pub fn paint_plots(
     ui: &mut egui::Ui,
     plots: &mut Plots,
@@ -22,7 +32,8 @@ pub fn paint_plots(
     axis_cfg: &mut AxisConfig,
     link_group: egui::Id,
     line_width: f32,
-) {
+    toasts: &mut Toasts,
+) -> Option<(f64, f64)> {
     let plot_height = ui.available_height() / (plot_settings.total_plot_count() as f32);
 
     let x_axes = vec![AxisHints::new_x().formatter(crate::util::format_time)];
@@ -80,7 +91,8 @@ pub fn paint_plots(
         axis_cfg,
         line_width,
         plot_settings,
-    );
+        toasts,
+    )
 }
 
 /// Iterates through and fills/paints all plots with their respective data.
@@ -92,15 +104,20 @@ pub fn paint_plots(
 /// * `axis_config` - For axis customization.
 /// * `line_width` - The width of plot lines.
 /// * `plot_settings` - Controls which plots to display.
+/// * `toasts` - For notifying about plots that could not be added from the context menu.
+///
+/// Returns the x range that is visible in the first plot, if any.
 fn fill_plots(
     gui: &mut egui::Ui,
     plot_components: Vec<(Plot<'_>, &mut PlotData, PlotType)>,
     axis_config: &mut AxisConfig,
     line_width: f32,
     plot_settings: &PlotSettings,
-) {
+    toasts: &mut Toasts,
+) -> Option<(f64, f64)> {
+    let mut visible_x_range = None;
     for (ui, plot, ptype) in plot_components {
-        ui.show(gui, |plot_ui| {
+        let response = ui.show(gui, |plot_ui| {
             fill_plot(
                 plot_ui,
                 (plot, ptype),
@@ -109,7 +126,13 @@ fn fill_plots(
                 plot_settings,
             );
         });
+        response
+            .response
+            .context_menu(|ui| derived_plots_menu(ui, plot, plot_settings, toasts));
+        let bounds = response.transform.bounds();
+        visible_x_range.get_or_insert((bounds.min()[0], bounds.max()[0]));
     }
+    visible_x_range
 }
 
 /// Fills and paints a single plot with its data.
@@ -130,12 +153,20 @@ fn fill_plot(
 ) {
     let (plot_data, plot_type) = plot;
 
+    if let Some(x_center) = plot_settings.x_jump() {
+        let mut bounds = plot_ui.plot_bounds();
+        bounds.set_x_center_width(x_center, bounds.width());
+        plot_ui.set_plot_bounds(bounds);
+    }
+
     plot_util::plot_lines(
         plot_ui,
         plot_settings.apply_filters(plot_data.plots()),
         line_width,
         plot_settings.mipmap_cfg(),
+        plot_settings.downsampling_method(),
         plot_ui.ctx().used_size().x as usize,
+        plot_settings.shade_gaps(),
     );
 
     plot_util::plot_labels(plot_ui, plot_data, &plot_settings.log_id_filter());
@@ -143,6 +174,107 @@ fn fill_plot(
     axis_config.handle_y_axis_lock(plot_ui, plot_type, |_| {});
 }
 
+/// What to do with a plot after picking it in the [`derived_plots_menu`]
+enum DerivedPlotAction {
+    Add {
+        source: String,
+        operation: Operation,
+    },
+    Remove(String),
+}
+
+/// The context menu of a plot area, with a submenu per shown plot for adding plots computed from it, such as its
+/// derivative, or removing it if it was computed that way.
+///
+/// Plots that can't be computed, or are already shown, are reported with a toast.
+fn derived_plots_menu(
+    ui: &mut egui::Ui,
+    plot_data: &mut PlotData,
+    plot_settings: &PlotSettings,
+    toasts: &mut Toasts,
+) {
+    let smoothing_id = egui::Id::new("derivative_smoothing_samples");
+    let mut smoothing_samples: usize = ui.data(|d| d.get_temp(smoothing_id)).unwrap_or(1);
+    ui.horizontal(|ui| {
+        ui.label("Derivative smoothing");
+        ui.add(
+            egui::DragValue::new(&mut smoothing_samples)
+                .range(1..=1001)
+                .suffix(" samples"),
+        );
+    });
+    ui.data_mut(|d| d.insert_temp(smoothing_id, smoothing_samples));
+    ui.separator();
+
+    let mut action = None;
+    for plot in plot_settings.apply_filters(plot_data.plots()) {
+        ui.menu_button(plot.label(), |ui| {
+            let operations = [
+                (
+                    "d/dt Derivative",
+                    Operation::Derivative { smoothing_samples },
+                ),
+                ("∫ Integral", Operation::Integral),
+            ];
+            for (text, operation) in operations {
+                if ui.button(text).clicked() {
+                    action = Some(DerivedPlotAction::Add {
+                        source: plot.label().to_owned(),
+                        operation,
+                    });
+                    ui.close_menu();
+                }
+            }
+            if let Some(derivation) = plot.derivation() {
+                ui.separator();
+                match derivation.subtrahend() {
+                    Some(subtrahend) => ui.label(format!("{} − {subtrahend}", derivation.source())),
+                    None => ui.label(format!(
+                        "The {} of {}",
+                        derivation.operation(),
+                        derivation.source()
+                    )),
+                };
+                if matches!(derivation.operation(), Operation::Filter(_)) {
+                    ui.label("Remove it in the signal filters");
+                } else if derivation.operation() == Operation::Difference {
+                    ui.label("Remove it in compare runs");
+                } else if ui.button("Remove").clicked() {
+                    action = Some(DerivedPlotAction::Remove(plot.label().to_owned()));
+                    ui.close_menu();
+                }
+            }
+        });
+    }
+
+    match action {
+        Some(DerivedPlotAction::Add { source, operation }) => {
+            let derived = plot_data
+                .find_plot(&source)
+                .map(|source| source.derived(operation));
+            match derived {
+                Some(Ok(derived)) if plot_data.contains_plot(derived.label()) => {
+                    toasts
+                        .info(format!("{} is already shown", derived.label()))
+                        .duration(Some(Duration::from_secs(5)));
+                }
+                Some(Ok(derived)) => plot_data.add_derived_plot(derived),
+                Some(Err(e)) => {
+                    log::warn!("Failed computing the {operation} of {source}: {e}");
+                    toasts
+                        .error(format!("Failed computing the {operation} of {source}: {e}"))
+                        .duration(Some(Duration::from_secs(10)));
+                }
+                None => (),
+            }
+        }
+        Some(DerivedPlotAction::Remove(label)) => {
+            plot_data.remove_plot(&label);
+        }
+        None => (),
+    }
+}
+
 /// Builds and configures a Plot UI (layout) with the specified settings.
 ///
 /// # Arguments
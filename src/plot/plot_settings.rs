@@ -1,23 +1,35 @@
+use compare_runs::CompareRuns;
 use date_settings::LoadedLogSettings;
 use egui::{Color32, Key, Response, RichText};
 use egui_phosphor::regular;
+use firmware::FirmwareFilter;
+use metadata_diff::MetadataDiff;
 use mipmap_settings::MipMapSettings;
 use plot_filter::{PlotNameFilter, PlotNameShow};
-use plot_util::{MipMapConfiguration, PlotValues, Plots};
+use plot_util::{DownsamplingMethod, MipMapConfiguration, PlotValues, Plots};
 use plot_visibility_config::PlotVisibilityConfig;
 use serde::{Deserialize, Serialize};
+use signal_filters::SignalFilters;
 
+mod compare_runs;
 pub mod date_settings;
+mod firmware;
 mod loaded_logs;
+mod metadata_diff;
 pub mod mipmap_settings;
 mod plot_filter;
 mod plot_visibility_config;
+mod signal_filters;
 
 #[derive(PartialEq, Deserialize, Serialize)]
 struct PlotSettingsUi {
     show_loaded_logs: bool,
     show_filter_settings: bool,
     filter_settings_text: String,
+    #[serde(default)]
+    group_by_firmware: bool,
+    #[serde(default)]
+    firmware_filter: FirmwareFilter,
 }
 
 impl Default for PlotSettingsUi {
@@ -26,6 +38,8 @@ impl Default for PlotSettingsUi {
             show_loaded_logs: Default::default(),
             show_filter_settings: Default::default(),
             filter_settings_text: format!("{} Filter", regular::FUNNEL),
+            group_by_firmware: false,
+            firmware_filter: FirmwareFilter::default(),
         }
     }
 }
@@ -55,16 +69,37 @@ pub struct PlotSettings {
     ps_ui: PlotSettingsUi,
     log_start_date_settings: Vec<LoadedLogSettings>,
     mipmap_settings: MipMapSettings,
+    /// A point in time (nanoseconds) the plots should be centered on in the current frame
+    #[serde(skip)]
+    x_jump: Option<f64>,
+    /// Whether the gaps detected in the plots are shaded
+    #[serde(default)]
+    shade_gaps: bool,
+    #[serde(default)]
+    signal_filters: SignalFilters,
+    #[serde(default)]
+    compare_runs: CompareRuns,
+    #[serde(default)]
+    metadata_diff: MetadataDiff,
 }
 
 impl PlotSettings {
-    pub fn show(&mut self, ui: &mut egui::Ui) {
+    pub fn show(&mut self, ui: &mut egui::Ui, plots: &Plots) {
         if self.log_start_date_settings.is_empty() {
             ui.label(RichText::new("No Files Loaded").color(Color32::RED));
         } else {
             self.show_loaded_files(ui);
             self.ui_plot_filter_settings(ui);
             self.mipmap_settings.show(ui);
+            ui.toggle_value(
+                &mut self.shade_gaps,
+                RichText::new(format!("{} Gaps", regular::SELECTION_SLASH)),
+            )
+            .on_hover_text("Shade the gaps where plots have no samples, gaps are configured in the settings of each log");
+            self.signal_filters.show(ui, plots);
+            self.compare_runs
+                .show(ui, &self.log_start_date_settings, plots);
+            self.metadata_diff.show(ui, &self.log_start_date_settings);
         }
         self.visibility.toggle_visibility_ui(ui);
     }
@@ -97,6 +132,21 @@ impl PlotSettings {
             &mut self.ps_ui.show_loaded_logs,
             show_loaded_logs_text.text(),
         );
+        let firmware_warnings: Vec<String> = self
+            .log_start_date_settings
+            .iter()
+            .filter_map(|settings| Some((settings.log_id(), settings.firmware()?)))
+            .flat_map(|(log_id, firmware)| {
+                firmware
+                    .warnings()
+                    .into_iter()
+                    .map(move |warning| format!("#{log_id} {firmware}: {warning}"))
+            })
+            .collect();
+        if !firmware_warnings.is_empty() {
+            ui.label(RichText::new(regular::WARNING).color(Color32::YELLOW))
+                .on_hover_text(firmware_warnings.join("\n"));
+        }
         if self.ps_ui.show_loaded_logs {
             // Only react on Escape input if no settings are currently open
             if ui.ctx().input(|i| i.key_pressed(Key::Escape))
@@ -107,12 +157,12 @@ impl PlotSettings {
             egui::Window::new(show_loaded_logs_text)
                 .open(&mut self.ps_ui.show_loaded_logs)
                 .show(ui.ctx(), |ui| {
-                    egui::Grid::new("log_settings_grid").show(ui, |ui| {
-                        for settings in &mut self.log_start_date_settings {
-                            loaded_logs::log_date_settings_ui(ui, settings);
-                            ui.end_row();
-                        }
-                    });
+                    loaded_logs::loaded_logs_ui(
+                        ui,
+                        &mut self.log_start_date_settings,
+                        &mut self.ps_ui.group_by_firmware,
+                        &mut self.ps_ui.firmware_filter,
+                    );
                 });
         }
     }
@@ -120,8 +170,23 @@ impl PlotSettings {
     /// Needs to be called once (and only once!) per frame before querying for plot ui settings, such as
     /// how many plots to paint and more.
     pub fn refresh(&mut self, plots: &mut Plots) {
+        self.compare_runs
+            .align(&mut self.log_start_date_settings, plots);
         self.update_plot_dates(plots);
+        for settings in &mut self.log_start_date_settings {
+            date_settings::update_plot_gaps(plots, settings);
+        }
+        self.signal_filters.update_companion_plots(plots);
+        self.compare_runs.update_plots(plots);
+        // Plots computed from other plots are added after the logs are loaded
+        for plot in plots.iter().filter(|plot| plot.derivation().is_some()) {
+            self.add_plot_name_if_not_exists(plot.name());
+        }
         self.calc_plot_display_settings(plots);
+        self.x_jump = self
+            .log_start_date_settings
+            .iter_mut()
+            .find_map(LoadedLogSettings::take_jump_request);
         // If true then we set it to false such that it is only true for one frame
         if self.cached_plots_invalidated() {
             self.invalidate_plot = false;
@@ -202,6 +267,11 @@ impl PlotSettings {
         self.log_start_date_settings.len() as u16
     }
 
+    /// The settings of each loaded log, in the order the logs were loaded
+    pub fn loaded_logs(&self) -> &[LoadedLogSettings] {
+        &self.log_start_date_settings
+    }
+
     pub fn add_log_setting(&mut self, log_settings: LoadedLogSettings) {
         self.log_start_date_settings.push(log_settings);
     }
@@ -229,8 +299,23 @@ impl PlotSettings {
         self.invalidate_plot
     }
 
+    /// Returns the point in time (nanoseconds) that the plots should be centered on in the current frame, if any
+    pub fn x_jump(&self) -> Option<f64> {
+        self.x_jump
+    }
+
+    /// Whether the gaps detected in the plots should be shaded
+    pub fn shade_gaps(&self) -> bool {
+        self.shade_gaps
+    }
+
     /// Returns the current `MipMap` settings as a [`MipMapConfiguration`]
     pub fn mipmap_cfg(&self) -> MipMapConfiguration {
         self.mipmap_settings.configuration()
     }
+
+    /// Returns the [`DownsamplingMethod`] selected in the `MipMap` settings
+    pub fn downsampling_method(&self) -> DownsamplingMethod {
+        self.mipmap_settings.method()
+    }
 }
@@ -0,0 +1,388 @@
+use std::{fmt, mem};
+
+use chrono::TimeDelta;
+use egui::{Color32, ComboBox, Key, RichText};
+use egui_phosphor::regular;
+use plot_util::{derived::Operation, PlotValues, Plots};
+use serde::{Deserialize, Serialize};
+use skytem_logs::mbed_motor_control::status::entry::MotorState;
+
+use super::date_settings::LoadedLogSettings;
+
+/// The name of the plot of the motor state in the Mbed status logs
+const MOTOR_STATE_PLOT_NAME: &str = "Motor State";
+
+/// The point in time at which the runs are aligned
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+enum Alignment {
+    /// The first sample of the run
+    #[default]
+    Start,
+    /// The first time the motor state changes to [`MotorState::RUNNING`]
+    FirstRunning,
+}
+
+impl Alignment {
+    const ALL: [Self; 2] = [Self::Start, Self::FirstRunning];
+}
+
+impl fmt::Display for Alignment {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Start => write!(f, "Start"),
+            Self::FirstRunning => write!(f, "First {}", MotorState::RUNNING),
+        }
+    }
+}
+
+/// Which run a log belongs to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Run {
+    A,
+    B,
+    Neither,
+}
+
+/// What a difference plot was computed from, it's computed again when any of it changes
+#[derive(Debug, Clone, PartialEq)]
+struct DifferenceKey {
+    /// The label of the difference plot
+    label: String,
+    minuend: String,
+    subtrahend: String,
+    x_offsets: (f64, f64),
+}
+
+/// Compares two runs, each made of one or more logs: The logs of run B are moved in time so they line up with run A,
+/// the plots of run B are drawn dashed, and the difference (A − B) of plots with the same name can be added.
+#[derive(Debug, Default, PartialEq, Deserialize, Serialize)]
+pub struct CompareRuns {
+    show: bool,
+    /// Log IDs of the logs in each run
+    run_a: Vec<usize>,
+    run_b: Vec<usize>,
+    alignment: Alignment,
+    /// Names of the plots that have a difference plot
+    differences: Vec<String>,
+    new_difference: Option<String>,
+    #[serde(skip)]
+    align_requested: bool,
+    /// Why the runs couldn't be aligned
+    #[serde(skip)]
+    error: Option<String>,
+    #[serde(skip)]
+    computed: Vec<DifferenceKey>,
+}
+
+impl CompareRuns {
+    fn title() -> String {
+        format!("{} Compare runs", regular::GIT_DIFF)
+    }
+
+    fn comparing(&self) -> bool {
+        !self.run_a.is_empty() && !self.run_b.is_empty()
+    }
+
+    fn run_of(&self, log_id: usize) -> Run {
+        if self.run_a.contains(&log_id) {
+            Run::A
+        } else if self.run_b.contains(&log_id) {
+            Run::B
+        } else {
+            Run::Neither
+        }
+    }
+
+    /// Shows the toggle button of the window, and the window if it's open
+    pub fn show(&mut self, ui: &mut egui::Ui, logs: &[LoadedLogSettings], plots: &Plots) {
+        ui.toggle_value(&mut self.show, Self::title())
+            .on_hover_text("Align two runs in time, and compare their plots");
+        if !self.show {
+            return;
+        }
+        let mut open = self.show;
+        egui::Window::new(Self::title())
+            .open(&mut open)
+            .show(ui.ctx(), |ui| {
+                self.show_runs(ui, logs);
+                ui.separator();
+                self.show_alignment(ui);
+                ui.separator();
+                self.show_differences(ui, plots);
+            });
+        self.show = open && !ui.ctx().input(|i| i.key_pressed(Key::Escape));
+    }
+
+    fn show_runs(&mut self, ui: &mut egui::Ui, logs: &[LoadedLogSettings]) {
+        egui::Grid::new("compare_runs_grid")
+            .striped(true)
+            .show(ui, |ui| {
+                for log in logs {
+                    let mut run = self.run_of(log.log_id());
+                    ui.label(log.log_label());
+                    ui.radio_value(&mut run, Run::A, "A");
+                    ui.radio_value(&mut run, Run::B, "B (dashed)");
+                    ui.radio_value(&mut run, Run::Neither, "Neither");
+                    ui.end_row();
+                    if run != self.run_of(log.log_id()) {
+                        self.run_a.retain(|&id| id != log.log_id());
+                        self.run_b.retain(|&id| id != log.log_id());
+                        match run {
+                            Run::A => self.run_a.push(log.log_id()),
+                            Run::B => self.run_b.push(log.log_id()),
+                            Run::Neither => (),
+                        }
+                    }
+                }
+            });
+    }
+
+    fn show_alignment(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal_wrapped(|ui| {
+            ComboBox::from_label("Align at")
+                .selected_text(self.alignment.to_string())
+                .show_ui(ui, |ui| {
+                    for alignment in Alignment::ALL {
+                        ui.selectable_value(&mut self.alignment, alignment, alignment.to_string());
+                    }
+                });
+            if ui
+                .add_enabled(self.comparing(), egui::Button::new("Align B to A"))
+                .on_hover_text("Move the logs of run B in time, so both runs start or change to RUNNING at the same time")
+                .clicked()
+            {
+                self.align_requested = true;
+            }
+        });
+        if let Some(error) = &self.error {
+            ui.label(RichText::new(error).color(Color32::RED));
+        }
+    }
+
+    fn show_differences(&mut self, ui: &mut egui::Ui, plots: &Plots) {
+        let mut remove = None;
+        for (idx, name) in self.differences.iter().enumerate() {
+            ui.horizontal(|ui| {
+                ui.label(format!("{name}: A − B"));
+                if ui
+                    .button(regular::TRASH)
+                    .on_hover_text("Remove the difference plot")
+                    .clicked()
+                {
+                    remove = Some(idx);
+                }
+            });
+        }
+        if let Some(idx) = remove {
+            self.differences.remove(idx);
+        }
+        let common_names = self.common_plot_names(plots);
+        ui.horizontal_wrapped(|ui| {
+            ComboBox::from_id_salt("compare_runs_difference")
+                .selected_text(self.new_difference.as_deref().unwrap_or("Select a plot"))
+                .width(220.0)
+                .show_ui(ui, |ui| {
+                    for name in &common_names {
+                        ui.selectable_value(&mut self.new_difference, Some(name.clone()), name);
+                    }
+                });
+            let addable = self
+                .new_difference
+                .as_ref()
+                .filter(|name| common_names.contains(name) && !self.differences.contains(name));
+            if ui
+                .add_enabled(
+                    addable.is_some(),
+                    egui::Button::new(format!("{} Add A − B", regular::PLUS)),
+                )
+                .on_hover_text(
+                    "Add the plot of run A minus the plot of run B, on the timestamps of run A",
+                )
+                .clicked()
+            {
+                if let Some(name) = addable {
+                    self.differences.push(name.clone());
+                }
+            }
+        });
+    }
+
+    /// The names of the loaded plots that are in both runs
+    fn common_plot_names(&self, plots: &Plots) -> Vec<String> {
+        let mut names: Vec<String> = plots
+            .iter()
+            .filter(|plot| plot.derivation().is_none() && self.run_a.contains(&plot.log_id()))
+            .map(|plot| plot.name().to_owned())
+            .filter(|name| self.find_plot(plots, Run::B, name).is_some())
+            .collect();
+        names.sort();
+        names.dedup();
+        names
+    }
+
+    /// The loaded plot named `name` of the first log of the `run` that has it
+    fn find_plot<'p>(&self, plots: &'p Plots, run: Run, name: &str) -> Option<&'p PlotValues> {
+        let log_ids = match run {
+            Run::A => &self.run_a,
+            Run::B => &self.run_b,
+            Run::Neither => return None,
+        };
+        log_ids.iter().find_map(|&log_id| {
+            plots
+                .find_by_label(&PlotValues::label_of(name, log_id))
+                .filter(|plot| plot.derivation().is_none())
+        })
+    }
+
+    /// Moves the logs of run B so that it lines up with run A, if it was requested since the last call.
+    /// Must be called before the dates of the plots are updated.
+    pub fn align(&mut self, logs: &mut [LoadedLogSettings], plots: &Plots) {
+        if !mem::take(&mut self.align_requested) {
+            return;
+        }
+        let anchors = self
+            .anchor(plots, Run::A)
+            .and_then(|a| self.anchor(plots, Run::B).map(|b| (a, b)));
+        match anchors {
+            Ok((a, b)) => {
+                let shift = TimeDelta::nanoseconds((a - b) as i64);
+                for log in logs
+                    .iter_mut()
+                    .filter(|log| self.run_b.contains(&log.log_id()))
+                {
+                    log.shift_start_date(shift);
+                }
+                self.error = None;
+            }
+            Err(e) => self.error = Some(e),
+        }
+    }
+
+    /// The time (ns) to align the `run` at, as it is currently drawn
+    fn anchor(&self, plots: &Plots, run: Run) -> Result<f64, String> {
+        let in_run = |plot: &&PlotValues| self.run_of(plot.log_id()) == run;
+        let anchor = match self.alignment {
+            Alignment::Start => plots
+                .iter()
+                .filter(in_run)
+                .filter_map(|plot| plot.points().next().map(|[x, _]| x))
+                .reduce(f64::min),
+            Alignment::FirstRunning => plots
+                .iter()
+                .filter(in_run)
+                .filter(|plot| plot.name() == MOTOR_STATE_PLOT_NAME)
+                .filter_map(first_running_transition)
+                .reduce(f64::min),
+        };
+        anchor.ok_or_else(|| match self.alignment {
+            Alignment::Start => format!("Run {run:?} has no samples"),
+            Alignment::FirstRunning => format!(
+                "The motor state never changes to {} in run {run:?}",
+                MotorState::RUNNING
+            ),
+        })
+    }
+
+    /// Draws the plots of run B dashed, and keeps the difference plots up to date with the plots they're computed
+    /// from, e.g. when a run is moved in time.
+    pub fn update_plots(&mut self, plots: &mut Plots) {
+        let comparing = self.comparing();
+        for plot_data in plots.plot_data_mut() {
+            for plot in plot_data.plots_as_mut() {
+                let dashed = comparing && self.run_b.contains(&plot.log_id());
+                if plot.is_dashed() != dashed {
+                    plot.set_dashed(dashed);
+                }
+            }
+        }
+
+        let mut wanted = vec![];
+        for name in &self.differences {
+            if let (Some(a), Some(b)) = (
+                self.find_plot(plots, Run::A, name),
+                self.find_plot(plots, Run::B, name),
+            ) {
+                let key = DifferenceKey {
+                    label: PlotValues::label_of(
+                        &Operation::Difference.derived_name(name),
+                        a.log_id(),
+                    ),
+                    minuend: a.label().to_owned(),
+                    subtrahend: b.label().to_owned(),
+                    x_offsets: (a.x_offset(), b.x_offset()),
+                };
+                wanted.push(key);
+            }
+        }
+        let previous = mem::take(&mut self.computed);
+        for outdated in previous
+            .iter()
+            .filter(|computed| !wanted.contains(computed))
+        {
+            for plot_data in plots.plot_data_mut() {
+                plot_data.remove_plot(&outdated.label);
+            }
+        }
+        for key in wanted {
+            if !previous.contains(&key) {
+                add_difference(plots, &key);
+            }
+            self.computed.push(key);
+        }
+    }
+}
+
+/// The time (ns) of the first change of the `motor_state` plot to [`MotorState::RUNNING`]
+fn first_running_transition(motor_state: &PlotValues) -> Option<f64> {
+    let running = f64::from(MotorState::RUNNING as u8);
+    let points: Vec<[f64; 2]> = motor_state.points().collect();
+    points
+        .windows(2)
+        .find(|pair| pair[0][1] != running && pair[1][1] == running)
+        .map(|pair| pair[1][0])
+}
+
+/// Adds the difference plot of the `key` next to its minuend, unless it's already there
+fn add_difference(plots: &mut Plots, key: &DifferenceKey) {
+    let difference = match (
+        plots.find_by_label(&key.minuend),
+        plots.find_by_label(&key.subtrahend),
+    ) {
+        (Some(minuend), Some(subtrahend)) => minuend.difference(subtrahend),
+        _ => return,
+    };
+    for plot_data in plots.plot_data_mut() {
+        if plot_data.contains_plot(&key.minuend) {
+            plot_data.add_derived_plot(difference);
+            return;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_first_running_transition() {
+        let running = f64::from(MotorState::RUNNING as u8);
+        let standby = f64::from(MotorState::STANDBY_READY as u8);
+        let motor_state = PlotValues::new(
+            vec![
+                [0.0, running],
+                [1.0, standby],
+                [2.0, standby],
+                [3.0, running],
+                [4.0, standby],
+                [5.0, running],
+            ],
+            MOTOR_STATE_PLOT_NAME.to_owned(),
+            1,
+        );
+        assert_eq!(first_running_transition(&motor_state), Some(3.0));
+
+        let never_running =
+            PlotValues::new(vec![[0.0, standby], [1.0, standby]], "State".to_owned(), 2);
+        assert_eq!(first_running_transition(&never_running), None);
+    }
+}
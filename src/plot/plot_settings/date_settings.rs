@@ -1,10 +1,15 @@
-use chrono::{DateTime, NaiveDateTime, Utc};
+use chrono::{DateTime, NaiveDateTime, TimeDelta, Utc};
 use egui::RichText;
-use plot_util::{PlotData, Plots};
+use plot_util::{
+    gaps::{Gap, DEFAULT_GAP_FACTOR},
+    PlotData, Plots,
+};
 use serde::{Deserialize, Serialize};
 
 use crate::app::supported_formats::logs::parse_info::ParseInfo;
 
+use super::firmware::FirmwareInfo;
+
 #[derive(PartialEq, Eq, Deserialize, Serialize)]
 pub struct LoadedLogMetadata {
     description: String,
@@ -42,7 +47,31 @@ impl LoadedLogMetadata {
     }
 }
 
-#[derive(PartialEq, Eq, Deserialize, Serialize)]
+/// How gaps are detected in the plots of a log
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize, Serialize)]
+pub struct GapSettings {
+    pub enabled: bool,
+    /// A gap is where consecutive samples are more than this many median sample intervals apart
+    pub factor: f64,
+}
+
+impl Default for GapSettings {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            factor: DEFAULT_GAP_FACTOR,
+        }
+    }
+}
+
+/// A gap detected in a plot of a log
+#[derive(Debug, Clone, PartialEq)]
+pub struct DetectedGap {
+    pub plot_name: String,
+    pub gap: Gap,
+}
+
+#[derive(PartialEq, Deserialize, Serialize)]
 pub struct LoadedLogSettings {
     log_id: usize,
     log_descriptive_name: String,
@@ -56,6 +85,17 @@ pub struct LoadedLogSettings {
     show: bool,
     log_metadata: Option<Vec<LoadedLogMetadata>>,
     parse_info: Option<ParseInfo>,
+    /// Set when the user requests the plot view to be centered on a point in time (nanoseconds) of this log
+    #[serde(skip)]
+    jump_to_time_ns: Option<f64>,
+    #[serde(default)]
+    gap_settings: GapSettings,
+    /// The gap settings that the gaps of the plots were detected with, [`None`] if they have not been detected
+    #[serde(skip)]
+    applied_gap_settings: Option<GapSettings>,
+    /// The gaps of the plots of this log, [`None`] if they need to be collected (again)
+    #[serde(skip)]
+    detected_gaps: Option<Vec<DetectedGap>>,
 }
 
 impl LoadedLogSettings {
@@ -84,6 +124,10 @@ impl LoadedLogSettings {
             show: true,
             log_metadata,
             parse_info,
+            jump_to_time_ns: None,
+            gap_settings: GapSettings::default(),
+            applied_gap_settings: None,
+            detected_gaps: None,
         }
     }
 
@@ -95,6 +139,13 @@ impl LoadedLogSettings {
         self.start_date = new_start_date;
     }
 
+    /// Moves the log and its plots by `shift` in time, as if its start date was edited
+    pub fn shift_start_date(&mut self, shift: TimeDelta) {
+        self.start_date += shift;
+        self.tmp_date_buf.clear();
+        self.date_changed = true;
+    }
+
     pub fn log_label(&self) -> String {
         format!(
             "#{log_id} {descriptive_name} [{start_date}]",
@@ -108,6 +159,10 @@ impl LoadedLogSettings {
         self.log_id
     }
 
+    pub fn descriptive_name(&self) -> &str {
+        &self.log_descriptive_name
+    }
+
     pub fn show_log(&self) -> bool {
         self.show
     }
@@ -120,8 +175,61 @@ impl LoadedLogSettings {
         self.log_metadata.as_deref_mut()
     }
 
-    pub fn parse_info(&self) -> Option<ParseInfo> {
-        self.parse_info
+    /// The metadata of the log as key/value pairs
+    pub fn metadata_pairs(&self) -> Vec<(String, String)> {
+        self.log_metadata
+            .iter()
+            .flatten()
+            .map(|m| (m.description.clone(), m.value.clone()))
+            .collect()
+    }
+
+    /// The firmware that produced the log, from the git info in its metadata
+    pub fn firmware(&self) -> Option<FirmwareInfo> {
+        let metadata: Vec<(&str, &str)> = self
+            .log_metadata
+            .iter()
+            .flatten()
+            .map(|m| (m.description.as_str(), m.value.as_str()))
+            .collect();
+        FirmwareInfo::from_metadata(&metadata)
+    }
+
+    pub fn parse_info(&self) -> Option<&ParseInfo> {
+        self.parse_info.as_ref()
+    }
+
+    /// Request the plot view to be centered on a timestamp from the log as it was originally parsed.
+    ///
+    /// The timestamp is adjusted for any offset applied to the start date of the log.
+    pub fn request_jump_to_original_time(&mut self, timestamp_ns: f64) {
+        let offset_ns = (self.start_date - self.original_start_date)
+            .num_nanoseconds()
+            .unwrap_or_default();
+        self.jump_to_time_ns = Some(timestamp_ns + offset_ns as f64);
+    }
+
+    /// Request the plot view to be centered on a timestamp of the plots as they are currently shown
+    pub fn request_jump_to_time(&mut self, timestamp_ns: f64) {
+        self.jump_to_time_ns = Some(timestamp_ns);
+    }
+
+    pub fn take_jump_request(&mut self) -> Option<f64> {
+        self.jump_to_time_ns.take()
+    }
+
+    pub fn gap_settings(&self) -> GapSettings {
+        self.gap_settings
+    }
+
+    /// Sets new gap settings, the gaps are detected again before the next frame if they changed
+    pub fn set_gap_settings(&mut self, gap_settings: GapSettings) {
+        self.gap_settings = gap_settings;
+    }
+
+    /// The gaps detected in the plots of this log, sorted by time
+    pub fn detected_gaps(&self) -> &[DetectedGap] {
+        self.detected_gaps.as_deref().unwrap_or_default()
     }
 }
 
@@ -150,6 +258,43 @@ pub fn update_plot_dates(
         apply_offsets(plots.thousands_mut());
 
         settings.date_changed = false;
+        // The gaps moved along with the plots
+        settings.detected_gaps = None;
         *invalidate_plot = true;
     }
 }
+
+/// Detects the gaps in the plots of the log if the gap settings changed, or if they have not been detected yet,
+/// and collects the gaps of the plots if they were detected again or moved.
+pub fn update_plot_gaps(plots: &mut Plots, settings: &mut LoadedLogSettings) {
+    let detect_again = settings.applied_gap_settings != Some(settings.gap_settings);
+    if settings.detected_gaps.is_some() && !detect_again {
+        return;
+    }
+    let factor = settings
+        .gap_settings
+        .enabled
+        .then_some(settings.gap_settings.factor);
+    let Plots {
+        percentage,
+        one_to_hundred,
+        thousands,
+    } = plots;
+    let mut detected_gaps = vec![];
+    for plot_data in [percentage, one_to_hundred, thousands] {
+        for pd in plot_data.plots_as_mut() {
+            if settings.log_id == pd.log_id() {
+                if detect_again {
+                    pd.set_gap_factor(factor);
+                }
+                detected_gaps.extend(pd.gaps().map(|gap| DetectedGap {
+                    plot_name: pd.name().to_owned(),
+                    gap,
+                }));
+            }
+        }
+    }
+    detected_gaps.sort_by(|a, b| a.gap.start().total_cmp(&b.gap.start()));
+    settings.detected_gaps = Some(detected_gaps);
+    settings.applied_gap_settings = Some(settings.gap_settings);
+}
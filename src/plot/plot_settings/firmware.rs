@@ -0,0 +1,161 @@
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+/// Metadata values that mean the log doesn't have the value
+const NOT_AVAILABLE: [&str; 2] = ["", "N/A"];
+/// Repo statuses of firmware built from committed sources
+const CLEAN_REPO_STATUSES: [&str; 1] = ["Clean"];
+/// Branches that released firmware is built from
+const MAIN_BRANCHES: [&str; 2] = ["main", "master"];
+
+/// The firmware a log was produced by, from the git info in the metadata of the log (see `GitMetadata`)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FirmwareInfo {
+    version: Option<String>,
+    short_sha: Option<String>,
+    branch: Option<String>,
+    repo_status: Option<String>,
+}
+
+impl FirmwareInfo {
+    /// Finds the git info in the `metadata` of a log, [`None`] if it has none
+    pub fn from_metadata(metadata: &[(&str, &str)]) -> Option<Self> {
+        let value = |description: &str| {
+            metadata
+                .iter()
+                .find(|(d, _)| *d == description)
+                .map(|(_, value)| value.trim())
+                .filter(|value| !NOT_AVAILABLE.contains(value))
+                .map(str::to_owned)
+        };
+        let info = Self {
+            version: value("Project Version"),
+            short_sha: value("Git Short SHA"),
+            branch: value("Git Branch"),
+            repo_status: value("Git Repo Status"),
+        };
+        (info.version.is_some() || info.short_sha.is_some()).then_some(info)
+    }
+
+    /// The firmware version, [`None`] if only the commit is known
+    pub fn version(&self) -> Option<&str> {
+        self.version.as_deref()
+    }
+
+    /// Why the firmware might not be a released version: It was built with uncommitted changes, or from another
+    /// branch than the main branch
+    pub fn warnings(&self) -> Vec<String> {
+        let mut warnings = vec![];
+        if let Some(status) = self
+            .repo_status
+            .as_deref()
+            .filter(|status| !CLEAN_REPO_STATUSES.contains(status))
+        {
+            warnings.push(format!(
+                "Built with uncommitted changes (repo status: {status})"
+            ));
+        }
+        if let Some(branch) = self
+            .branch
+            .as_deref()
+            .filter(|branch| !MAIN_BRANCHES.contains(branch))
+        {
+            warnings.push(format!("Built from the branch '{branch}'"));
+        }
+        warnings
+    }
+}
+
+impl fmt::Display for FirmwareInfo {
+    /// The version and the commit, e.g. `v2.3.2 (a1b2c3d)`
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (&self.version, &self.short_sha) {
+            (Some(version), Some(sha)) => write!(f, "v{version} ({sha})"),
+            (Some(version), None) => write!(f, "v{version}"),
+            (None, Some(sha)) => write!(f, "{sha}"),
+            (None, None) => write!(f, "unknown"),
+        }
+    }
+}
+
+/// Which firmware versions are listed in the loaded files window
+#[derive(Debug, Default, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub enum FirmwareFilter {
+    #[default]
+    All,
+    Version(String),
+    /// Logs without a firmware version
+    Unknown,
+}
+
+impl FirmwareFilter {
+    pub fn matches(&self, version: Option<&str>) -> bool {
+        match self {
+            Self::All => true,
+            Self::Version(filtered) => version == Some(filtered),
+            Self::Unknown => version.is_none(),
+        }
+    }
+}
+
+impl fmt::Display for FirmwareFilter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::All => write!(f, "All versions"),
+            Self::Version(version) => write!(f, "v{version}"),
+            Self::Unknown => write!(f, "Unknown version"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn metadata(pairs: &[(&'static str, &'static str)]) -> Option<FirmwareInfo> {
+        FirmwareInfo::from_metadata(pairs)
+    }
+
+    #[test]
+    fn test_released_firmware() {
+        let info = metadata(&[
+            ("Project Version", "2.3.2"),
+            ("Git Branch", "main"),
+            ("Git Repo Status", "Clean"),
+            ("Git Short SHA", "a1b2c3d"),
+            ("Kp", "0.5"),
+        ])
+        .expect("no firmware info");
+        assert_eq!(info.version(), Some("2.3.2"));
+        assert_eq!(info.to_string(), "v2.3.2 (a1b2c3d)");
+        assert!(info.warnings().is_empty());
+    }
+
+    #[test]
+    fn test_warns_about_dirty_repo_and_other_branches() {
+        let info = metadata(&[
+            ("Project Version", "2.4.0"),
+            ("Git Branch", "feature/new-pid"),
+            ("Git Repo Status", "dirty"),
+            ("Git Short SHA", "N/A"),
+        ])
+        .expect("no firmware info");
+        assert_eq!(info.to_string(), "v2.4.0");
+        assert_eq!(
+            info.warnings(),
+            [
+                "Built with uncommitted changes (repo status: dirty)",
+                "Built from the branch 'feature/new-pid'"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_unknown_branch_is_not_a_warning() {
+        let info = metadata(&[("Project Version", "2.3.2"), ("Git Branch", "N/A")])
+            .expect("no firmware info");
+        assert!(info.warnings().is_empty());
+        assert_eq!(metadata(&[("Kp", "0.5"), ("Project Version", "N/A")]), None);
+    }
+}
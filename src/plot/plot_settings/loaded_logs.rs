@@ -1,13 +1,89 @@
-use chrono::NaiveDateTime;
+use chrono::{DateTime, NaiveDateTime, Utc};
 use egui::{Color32, Key, RichText, TextEdit};
 use egui_phosphor::regular;
+use log_if::prelude::ParseDiagnostic;
 
 use crate::{
     app::{supported_formats::logs::parse_info::ParseInfo, WARN_ON_UNPARSED_BYTES_THRESHOLD},
-    util::format_data_size,
+    util::{format_data_size, format_ms_timestamp},
 };
 
-use super::date_settings::LoadedLogSettings;
+use super::{
+    date_settings::{DetectedGap, GapSettings, LoadedLogSettings},
+    firmware::{FirmwareFilter, FirmwareInfo},
+};
+
+/// At most this many of the detected gaps of a log are listed
+const MAX_LISTED_GAPS: usize = 1000;
+const FIRMWARE_BADGE_COLOR: Color32 = Color32::from_rgb(40, 70, 110);
+
+/// Lists the loaded logs with their settings, optionally only the logs of one firmware version and grouped by
+/// firmware version
+pub fn loaded_logs_ui(
+    ui: &mut egui::Ui,
+    logs: &mut [LoadedLogSettings],
+    group_by_firmware: &mut bool,
+    firmware_filter: &mut FirmwareFilter,
+) {
+    let versions: Vec<Option<String>> = logs
+        .iter()
+        .map(|log| log.firmware().and_then(|f| f.version().map(str::to_owned)))
+        .collect();
+    ui.horizontal_wrapped(|ui| {
+        ui.checkbox(group_by_firmware, "Group by firmware version");
+        let mut filters = vec![FirmwareFilter::All];
+        let mut known: Vec<&String> = versions.iter().flatten().collect();
+        known.sort();
+        known.dedup();
+        filters.extend(known.into_iter().cloned().map(FirmwareFilter::Version));
+        if versions.iter().any(Option::is_none) {
+            filters.push(FirmwareFilter::Unknown);
+        }
+        egui::ComboBox::from_id_salt("firmware_filter")
+            .selected_text(firmware_filter.to_string())
+            .show_ui(ui, |ui| {
+                for filter in filters {
+                    let text = filter.to_string();
+                    ui.selectable_value(firmware_filter, filter, text);
+                }
+            });
+        if *firmware_filter != FirmwareFilter::All
+            && ui
+                .button("Plot only these")
+                .on_hover_text(
+                    "Show the plots of the listed logs and hide the plots of the other logs",
+                )
+                .clicked()
+        {
+            for (log, version) in logs.iter_mut().zip(&versions) {
+                *log.show_log_mut() = firmware_filter.matches(version.as_deref());
+            }
+        }
+    });
+
+    let mut listed: Vec<usize> = (0..logs.len())
+        .filter(|&idx| firmware_filter.matches(versions[idx].as_deref()))
+        .collect();
+    if *group_by_firmware {
+        // Logs without a version last
+        listed.sort_by_key(|&idx| (versions[idx].is_none(), versions[idx].clone()));
+    }
+    egui::Grid::new("log_settings_grid").show(ui, |ui| {
+        let mut group = None;
+        for idx in listed {
+            if *group_by_firmware && group != Some(&versions[idx]) {
+                group = Some(&versions[idx]);
+                let heading = versions[idx]
+                    .as_deref()
+                    .map_or_else(|| "Unknown version".to_owned(), |v| format!("v{v}"));
+                ui.label(RichText::new(heading).strong().underline());
+                ui.end_row();
+            }
+            log_date_settings_ui(ui, &mut logs[idx]);
+            ui.end_row();
+        }
+    });
+}
 
 pub fn log_date_settings_ui(ui: &mut egui::Ui, settings: &mut LoadedLogSettings) {
     let log_name_date = settings.log_label();
@@ -30,6 +106,12 @@ pub fn log_date_settings_ui(ui: &mut egui::Ui, settings: &mut LoadedLogSettings)
     if button_resp.hovered() {
         button_resp.on_hover_text("Click to modify log settings");
     }
+    match settings.firmware() {
+        Some(firmware) => firmware_badge(ui, &firmware),
+        None => {
+            ui.label("");
+        }
+    }
 
     if settings.tmp_date_buf.is_empty() {
         settings.tmp_date_buf = settings
@@ -56,6 +138,17 @@ fn log_settings_window(ui: &egui::Ui, settings: &mut LoadedLogSettings, log_name
                     show_parse_info(ui, parse_info);
                 }
             });
+            if let Some(jump_to_ts) = settings
+                .parse_info()
+                .and_then(|parse_info| show_parse_diagnostics(ui, parse_info.diagnostics()))
+            {
+                settings.request_jump_to_original_time(jump_to_ts);
+            }
+            let mut gap_settings = settings.gap_settings();
+            if let Some(jump_to_ts) = show_gaps(ui, &mut gap_settings, settings.detected_gaps()) {
+                settings.request_jump_to_time(jump_to_ts);
+            }
+            settings.set_gap_settings(gap_settings);
             if let Some(log_metadata) = settings.log_metadata() {
                 egui::Grid::new("metadata").show(ui, |ui| {
                     for log_metadata in log_metadata {
@@ -106,7 +199,24 @@ fn log_settings_window(ui: &egui::Ui, settings: &mut LoadedLogSettings, log_name
     }
 }
 
-fn show_parse_info(ui: &mut egui::Ui, parse_info: ParseInfo) {
+/// The firmware version of a log, with a warning if it may not be a released version
+fn firmware_badge(ui: &mut egui::Ui, firmware: &FirmwareInfo) {
+    ui.horizontal(|ui| {
+        ui.label(
+            RichText::new(format!(" {} {firmware} ", regular::CPU))
+                .color(Color32::WHITE)
+                .background_color(FIRMWARE_BADGE_COLOR),
+        )
+        .on_hover_text("Firmware version");
+        let warnings = firmware.warnings();
+        if !warnings.is_empty() {
+            ui.label(RichText::new(regular::WARNING).color(Color32::YELLOW))
+                .on_hover_text(warnings.join("\n"));
+        }
+    });
+}
+
+fn show_parse_info(ui: &mut egui::Ui, parse_info: &ParseInfo) {
     let parse_info_str = format!(
         "Parsed {parsed}/{total}",
         parsed = format_data_size(parse_info.parsed_bytes()),
@@ -125,3 +235,126 @@ fn show_parse_info(ui: &mut egui::Ui, parse_info: ParseInfo) {
         ui.label(RichText::new(unparsed_text));
     }
 }
+
+/// Shows a table of the regions that failed to parse, returns the timestamp to jump to if one was clicked
+fn show_parse_diagnostics(ui: &mut egui::Ui, diagnostics: &[ParseDiagnostic]) -> Option<f64> {
+    if diagnostics.is_empty() {
+        return None;
+    }
+    let mut jump_to_ts = None;
+    egui::CollapsingHeader::new(
+        RichText::new(format!("⚠ {} parse diagnostics", diagnostics.len())).color(Color32::YELLOW),
+    )
+    .show(ui, |ui| {
+        egui::ScrollArea::vertical()
+            .max_height(200.0)
+            .show(ui, |ui| {
+                egui::Grid::new("parse_diagnostics")
+                    .striped(true)
+                    .show(ui, |ui| {
+                        for header in ["Offset", "Line", "Kind", "Skipped", "Message", ""] {
+                            ui.label(RichText::new(header).strong());
+                        }
+                        ui.end_row();
+                        for diagnostic in diagnostics {
+                            ui.label(diagnostic.byte_offset().to_string());
+                            ui.label(
+                                diagnostic
+                                    .line()
+                                    .map_or_else(String::new, |l| l.to_string()),
+                            );
+                            ui.label(diagnostic.kind().to_string());
+                            ui.label(format!(
+                                "{} ({} entries)",
+                                format_data_size(diagnostic.skipped_bytes()),
+                                diagnostic.failed_attempts()
+                            ));
+                            ui.label(diagnostic.message());
+                            if let Some(ts) = diagnostic.timestamp_ns() {
+                                if ui
+                                    .button(format!("{} Jump", regular::CROSSHAIR))
+                                    .on_hover_text("Center the plots on the time of the failure")
+                                    .clicked()
+                                {
+                                    jump_to_ts = Some(ts);
+                                }
+                            }
+                            ui.end_row();
+                        }
+                    });
+            });
+    });
+    jump_to_ts
+}
+
+/// Shows the gap settings and a table of the detected gaps, returns the timestamp to jump to if one was clicked
+fn show_gaps(
+    ui: &mut egui::Ui,
+    gap_settings: &mut GapSettings,
+    detected_gaps: &[DetectedGap],
+) -> Option<f64> {
+    let mut jump_to_ts = None;
+    egui::CollapsingHeader::new(format!(
+        "{} {} gaps detected",
+        regular::SELECTION_SLASH,
+        detected_gaps.len()
+    ))
+    .show(ui, |ui| {
+        ui.horizontal(|ui| {
+            ui.checkbox(&mut gap_settings.enabled, "Detect gaps")
+                .on_hover_text("Break the lines of the plots where samples are missing");
+            ui.add_enabled(
+                gap_settings.enabled,
+                egui::DragValue::new(&mut gap_settings.factor)
+                    .speed(0.1)
+                    .range(1.5..=1000.0)
+                    .suffix("× median sample interval"),
+            )
+            .on_hover_text("A gap is where consecutive samples are further apart than this");
+        });
+        if detected_gaps.is_empty() {
+            return;
+        }
+        egui::ScrollArea::vertical()
+            .max_height(200.0)
+            .show(ui, |ui| {
+                egui::Grid::new("detected_gaps")
+                    .striped(true)
+                    .show(ui, |ui| {
+                        for header in ["Plot", "Start", "End", "Duration", ""] {
+                            ui.label(RichText::new(header).strong());
+                        }
+                        ui.end_row();
+                        for DetectedGap { plot_name, gap } in
+                            detected_gaps.iter().take(MAX_LISTED_GAPS)
+                        {
+                            ui.label(plot_name);
+                            ui.label(format_gap_time(gap.start()));
+                            ui.label(format_gap_time(gap.end()));
+                            ui.label(format_ms_timestamp(gap.duration() / 1_000_000.0));
+                            if ui
+                                .button(format!("{} Jump", regular::CROSSHAIR))
+                                .on_hover_text("Center the plots on the gap")
+                                .clicked()
+                            {
+                                jump_to_ts = Some(gap.center());
+                            }
+                            ui.end_row();
+                        }
+                    });
+                if detected_gaps.len() > MAX_LISTED_GAPS {
+                    ui.label(format!(
+                        "... and {} more",
+                        detected_gaps.len() - MAX_LISTED_GAPS
+                    ));
+                }
+            });
+    });
+    jump_to_ts
+}
+
+fn format_gap_time(timestamp_ns: f64) -> String {
+    DateTime::<Utc>::from_timestamp_nanos(timestamp_ns as i64)
+        .format("%Y-%m-%d %H:%M:%S%.3f")
+        .to_string()
+}
@@ -0,0 +1,151 @@
+use egui::{Color32, Key, RichText};
+use egui_phosphor::regular;
+use serde::{Deserialize, Serialize};
+
+use super::date_settings::LoadedLogSettings;
+
+const DIFFERS_COLOR: Color32 = Color32::from_rgb(255, 165, 0);
+const MISSING: &str = "-";
+
+/// A metadata entry with the value of each log, [`None`] for logs that don't have it
+#[derive(Debug, Clone, PartialEq)]
+struct MetadataRow {
+    description: String,
+    values: Vec<Option<String>>,
+}
+
+impl MetadataRow {
+    /// Whether the logs don't all have the same value, a missing value differs from any value
+    fn differs(&self) -> bool {
+        self.values.windows(2).any(|pair| pair[0] != pair[1])
+    }
+}
+
+/// The metadata of each log as rows with a value per log, in the order the entries first appear
+fn metadata_rows(metadata: &[Vec<(String, String)>]) -> Vec<MetadataRow> {
+    let mut rows: Vec<MetadataRow> = vec![];
+    for (log_idx, pairs) in metadata.iter().enumerate() {
+        for (description, value) in pairs {
+            let idx = rows
+                .iter()
+                .position(|row| row.description == *description)
+                .unwrap_or_else(|| {
+                    rows.push(MetadataRow {
+                        description: description.clone(),
+                        values: vec![None; metadata.len()],
+                    });
+                    rows.len() - 1
+                });
+            // The first value of a description in a log is the one shown
+            rows[idx].values[log_idx].get_or_insert_with(|| value.clone());
+        }
+    }
+    rows
+}
+
+/// A window with the metadata of all loaded logs side by side, such as the firmware version and the Mbed config
+/// values, where the entries that differ between the logs are highlighted.
+#[derive(Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
+pub struct MetadataDiff {
+    show: bool,
+    only_differing: bool,
+}
+
+impl MetadataDiff {
+    fn title() -> String {
+        format!("{} Compare metadata", regular::COLUMNS)
+    }
+
+    /// Shows the toggle button of the window, and the window if it's open
+    pub fn show(&mut self, ui: &mut egui::Ui, logs: &[LoadedLogSettings]) {
+        ui.toggle_value(&mut self.show, Self::title())
+            .on_hover_text("Show the metadata of the loaded logs side by side, highlighting the values that differ");
+        if !self.show {
+            return;
+        }
+        let mut open = self.show;
+        egui::Window::new(Self::title())
+            .open(&mut open)
+            .default_size([700.0, 500.0])
+            .show(ui.ctx(), |ui| {
+                ui.checkbox(&mut self.only_differing, "Only differing values");
+                let metadata: Vec<Vec<(String, String)>> =
+                    logs.iter().map(LoadedLogSettings::metadata_pairs).collect();
+                let rows = metadata_rows(&metadata);
+                if rows.is_empty() {
+                    ui.label(RichText::new("The loaded logs have no metadata").weak());
+                    return;
+                }
+                egui::ScrollArea::both().show(ui, |ui| {
+                    self.show_rows(ui, logs, &rows);
+                });
+            });
+        self.show = open && !ui.ctx().input(|i| i.key_pressed(Key::Escape));
+    }
+
+    fn show_rows(&self, ui: &mut egui::Ui, logs: &[LoadedLogSettings], rows: &[MetadataRow]) {
+        egui::Grid::new("metadata_diff_grid")
+            .striped(true)
+            .show(ui, |ui| {
+                ui.label("");
+                for log in logs {
+                    ui.label(RichText::new(log.log_label()).strong());
+                }
+                ui.end_row();
+                for row in rows {
+                    let differs = row.differs();
+                    if self.only_differing && !differs {
+                        continue;
+                    }
+                    let highlight = |text: &str| {
+                        let text = RichText::new(text);
+                        if differs {
+                            text.color(DIFFERS_COLOR)
+                        } else {
+                            text
+                        }
+                    };
+                    ui.label(highlight(&row.description).strong());
+                    for value in &row.values {
+                        ui.label(highlight(value.as_deref().unwrap_or(MISSING)));
+                    }
+                    ui.end_row();
+                }
+            });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pairs(pairs: &[(&str, &str)]) -> Vec<(String, String)> {
+        pairs
+            .iter()
+            .map(|&(description, value)| (description.to_owned(), value.to_owned()))
+            .collect()
+    }
+
+    #[test]
+    fn test_metadata_rows_mark_differing_and_missing_values() {
+        let metadata = [
+            pairs(&[("Project Version", "1.2.0"), ("Kp", "0.5"), ("Ki", "0.1")]),
+            pairs(&[("Project Version", "1.3.0"), ("Kp", "0.5")]),
+        ];
+        let rows = metadata_rows(&metadata);
+
+        let descriptions: Vec<&str> = rows.iter().map(|r| r.description.as_str()).collect();
+        assert_eq!(descriptions, ["Project Version", "Kp", "Ki"]);
+        let differs: Vec<bool> = rows.iter().map(MetadataRow::differs).collect();
+        assert_eq!(differs, [true, false, true]);
+        assert_eq!(rows[2].values, [Some("0.1".to_owned()), None]);
+    }
+
+    #[test]
+    fn test_metadata_rows_keep_the_first_of_repeated_descriptions() {
+        let rows = metadata_rows(&[pairs(&[("Kp", "0.5"), ("Kp", "0.7")])]);
+        assert_eq!(rows.len(), 1);
+        assert_eq!(rows[0].values, [Some("0.5".to_owned())]);
+        assert!(!rows[0].differs());
+    }
+}
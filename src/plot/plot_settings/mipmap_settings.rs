@@ -1,6 +1,6 @@
 use egui::{Response, RichText};
 use egui_phosphor::regular;
-use plot_util::MipMapConfiguration;
+use plot_util::{DownsamplingMethod, MipMapConfiguration};
 use serde::{Deserialize, Serialize};
 
 #[derive(PartialEq, Eq, Deserialize, Serialize)]
@@ -8,6 +8,8 @@ pub struct MipMapSettings {
     enabled: bool,
     auto_set: bool,
     level: usize,
+    #[serde(default)]
+    method: DownsamplingMethod,
 }
 
 impl Default for MipMapSettings {
@@ -16,6 +18,7 @@ impl Default for MipMapSettings {
             enabled: true,
             auto_set: true,
             level: 0,
+            method: DownsamplingMethod::default(),
         }
     }
 }
@@ -25,8 +28,20 @@ impl MipMapSettings {
     pub fn show(&mut self, ui: &mut egui::Ui) -> Response {
         egui::Grid::new("mipmap settings").show(ui, |ui| {
 
-            ui.toggle_value(&mut self.enabled, RichText::new(format!("{} Downsampling", regular::EQUALIZER))).on_hover_text("Enable downsampling with min/max mipmaps. Will show 2 plots per logical plot, one that displays the minimum values and one with the maximum values");
+            ui.toggle_value(&mut self.enabled, RichText::new(format!("{} Downsampling", regular::EQUALIZER))).on_hover_text("Enable downsampling with precomputed levels (mipmaps) of each plot");
             ui.add_enabled_ui(self.enabled, |ui| {
+                egui::ComboBox::from_id_salt("downsampling method")
+                    .selected_text(self.method.to_string())
+                    .width(70.0)
+                    .show_ui(ui, |ui| {
+                        ui.selectable_value(&mut self.method, DownsamplingMethod::MinMax, DownsamplingMethod::MinMax.to_string())
+                            .on_hover_text("Show 2 plots per logical plot, one that displays the minimum values and one with the maximum values");
+                        ui.selectable_value(&mut self.method, DownsamplingMethod::Envelope, DownsamplingMethod::Envelope.to_string())
+                            .on_hover_text("Show 1 plot per logical plot that fills the area between the minimum and maximum values, like an oscilloscope");
+                        ui.selectable_value(&mut self.method, DownsamplingMethod::Lttb, DownsamplingMethod::Lttb.to_string())
+                            .on_hover_text("Show 1 plot per logical plot with the Largest-Triangle-Three-Buckets algorithm, which keeps peaks and the overall shape");
+                    });
+
                 ui.checkbox(&mut self.auto_set, "auto")
                 .on_hover_text("Toggle auto-scaling mipmap'ing (downsampling)");
 
@@ -61,4 +76,9 @@ impl MipMapSettings {
             MipMapConfiguration::Disabled
         }
     }
+
+    /// The selected [`DownsamplingMethod`], used when the configuration is not [`MipMapConfiguration::Disabled`]
+    pub fn method(&self) -> DownsamplingMethod {
+        self.method
+    }
 }
@@ -0,0 +1,264 @@
+use std::mem;
+
+use egui::{Color32, ComboBox, Key, RichText};
+use egui_phosphor::regular;
+use plot_util::{
+    derived::Operation,
+    filters::{SignalFilter, MAX_ORDER},
+    PlotValues, Plots,
+};
+use serde::{Deserialize, Serialize};
+
+/// The kinds of filters that can be added, with their default parameters
+const FILTER_KINDS: [SignalFilter; 5] = [
+    SignalFilter::MovingAverage { samples: 5 },
+    SignalFilter::Median { samples: 5 },
+    SignalFilter::LowPass {
+        cutoff_hz: 10.0,
+        order: 4,
+    },
+    SignalFilter::HighPass {
+        cutoff_hz: 1.0,
+        order: 2,
+    },
+    SignalFilter::BandPass {
+        low_hz: 1.0,
+        high_hz: 10.0,
+        order: 4,
+    },
+];
+
+fn kind_name(filter: &SignalFilter) -> &'static str {
+    match filter {
+        SignalFilter::MovingAverage { .. } => "Moving average",
+        SignalFilter::Median { .. } => "Median",
+        SignalFilter::LowPass { .. } => "Low-pass",
+        SignalFilter::HighPass { .. } => "High-pass",
+        SignalFilter::BandPass { .. } => "Band-pass",
+    }
+}
+
+/// A filter of a plot, the filtered samples are shown as a companion plot
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+struct ConfiguredFilter {
+    /// The label of the filtered plot, see [`PlotValues::label`]
+    source: String,
+    filter: SignalFilter,
+    /// Why the companion plot couldn't be made
+    #[serde(skip)]
+    error: Option<String>,
+}
+
+impl ConfiguredFilter {
+    /// The label of the companion plot, if the filtered plot is one of the `plots`
+    fn companion_label(&self, plots: &Plots) -> Option<String> {
+        plots.find_by_label(&self.source).map(|source| {
+            PlotValues::label_of(
+                &Operation::Filter(self.filter).derived_name(source.name()),
+                source.log_id(),
+            )
+        })
+    }
+}
+
+/// Filters of plots, such as moving averages and Butterworth filters, that each add a companion plot with the
+/// filtered samples, leaving the original plot as it is.
+#[derive(Debug, PartialEq, Deserialize, Serialize)]
+pub struct SignalFilters {
+    show: bool,
+    filters: Vec<ConfiguredFilter>,
+    /// The plot and filter that are added when clicking `Add`
+    new_source: Option<String>,
+    new_filter: SignalFilter,
+    /// Removed filters whose companion plots are removed in the next [`SignalFilters::update_companion_plots`]
+    #[serde(skip)]
+    removed: Vec<ConfiguredFilter>,
+}
+
+impl Default for SignalFilters {
+    fn default() -> Self {
+        Self {
+            show: false,
+            filters: vec![],
+            new_source: None,
+            new_filter: FILTER_KINDS[0],
+            removed: vec![],
+        }
+    }
+}
+
+impl SignalFilters {
+    fn title() -> String {
+        format!("{} Signal filters", regular::WAVE_SAWTOOTH)
+    }
+
+    /// Shows the toggle button of the window, and the window if it's open
+    pub fn show(&mut self, ui: &mut egui::Ui, plots: &Plots) {
+        ui.toggle_value(&mut self.show, Self::title())
+            .on_hover_text(
+                "Add filtered companions of plots, e.g. a moving average or a low-pass filter",
+            );
+        if !self.show {
+            return;
+        }
+        let mut open = self.show;
+        egui::Window::new(Self::title())
+            .open(&mut open)
+            .show(ui.ctx(), |ui| {
+                self.show_filters(ui);
+                ui.separator();
+                self.show_new_filter(ui, plots);
+            });
+        self.show = open && !ui.ctx().input(|i| i.key_pressed(Key::Escape));
+    }
+
+    fn show_filters(&mut self, ui: &mut egui::Ui) {
+        if self.filters.is_empty() {
+            ui.label(RichText::new("No filters").weak());
+            return;
+        }
+        let mut remove = None;
+        egui::Grid::new("signal_filters_grid")
+            .striped(true)
+            .show(ui, |ui| {
+                for (idx, configured) in self.filters.iter().enumerate() {
+                    ui.label(&configured.source);
+                    ui.label(configured.filter.to_string());
+                    if ui
+                        .button(regular::TRASH)
+                        .on_hover_text("Remove the filter and its companion plot")
+                        .clicked()
+                    {
+                        remove = Some(idx);
+                    }
+                    if let Some(error) = &configured.error {
+                        ui.label(RichText::new(error).color(Color32::RED));
+                    }
+                    ui.end_row();
+                }
+            });
+        if let Some(idx) = remove {
+            self.removed.push(self.filters.remove(idx));
+        }
+    }
+
+    fn show_new_filter(&mut self, ui: &mut egui::Ui, plots: &Plots) {
+        ui.horizontal_wrapped(|ui| {
+            ComboBox::from_id_salt("signal_filter_source")
+                .selected_text(self.new_source.as_deref().unwrap_or("Select a plot"))
+                .width(220.0)
+                .show_ui(ui, |ui| {
+                    for plot in plots.iter() {
+                        ui.selectable_value(
+                            &mut self.new_source,
+                            Some(plot.label().to_owned()),
+                            plot.label(),
+                        );
+                    }
+                });
+            ComboBox::from_id_salt("signal_filter_kind")
+                .selected_text(kind_name(&self.new_filter))
+                .show_ui(ui, |ui| {
+                    for kind in FILTER_KINDS {
+                        let selected =
+                            mem::discriminant(&kind) == mem::discriminant(&self.new_filter);
+                        if ui.selectable_label(selected, kind_name(&kind)).clicked() && !selected {
+                            self.new_filter = kind;
+                        }
+                    }
+                });
+            show_parameters(ui, &mut self.new_filter);
+            if let Some(source) = &self.new_source {
+                let new = ConfiguredFilter {
+                    source: source.clone(),
+                    filter: self.new_filter,
+                    error: None,
+                };
+                let exists = self
+                    .filters
+                    .iter()
+                    .any(|f| f.source == new.source && f.filter == new.filter);
+                if ui
+                    .add_enabled(!exists, egui::Button::new(format!("{} Add", regular::PLUS)))
+                    .clicked()
+                {
+                    self.filters.push(new);
+                }
+            }
+        });
+    }
+
+    /// Removes the companion plots of removed filters and adds the missing companion plots of the filters
+    pub fn update_companion_plots(&mut self, plots: &mut Plots) {
+        for removed in mem::take(&mut self.removed) {
+            if let Some(companion) = removed.companion_label(plots) {
+                for plot_data in plots.plot_data_mut() {
+                    plot_data.remove_plot(&companion);
+                }
+            }
+        }
+        for configured in &mut self.filters {
+            if configured.error.is_some() {
+                continue;
+            }
+            let Some(companion) = configured.companion_label(plots) else {
+                continue;
+            };
+            for plot_data in plots.plot_data_mut() {
+                let Some(source) = plot_data.find_plot(&configured.source) else {
+                    continue;
+                };
+                if plot_data.contains_plot(&companion) {
+                    continue;
+                }
+                match source.derived(Operation::Filter(configured.filter)) {
+                    Ok(filtered) => plot_data.add_derived_plot(filtered),
+                    Err(e) => configured.error = Some(e.to_string()),
+                }
+            }
+        }
+    }
+}
+
+fn show_parameters(ui: &mut egui::Ui, filter: &mut SignalFilter) {
+    match filter {
+        SignalFilter::MovingAverage { samples } | SignalFilter::Median { samples } => {
+            ui.label("Samples");
+            ui.add(egui::DragValue::new(samples).range(1..=10_001));
+        }
+        SignalFilter::LowPass { cutoff_hz, order }
+        | SignalFilter::HighPass { cutoff_hz, order } => {
+            ui.label("Cutoff");
+            ui.add(cutoff_value(cutoff_hz));
+            show_order(ui, order);
+        }
+        SignalFilter::BandPass {
+            low_hz,
+            high_hz,
+            order,
+        } => {
+            ui.label("From");
+            ui.add(cutoff_value(low_hz));
+            ui.label("to");
+            ui.add(cutoff_value(high_hz));
+            show_order(ui, order);
+        }
+    }
+}
+
+fn cutoff_value(value: &mut f64) -> egui::DragValue<'_> {
+    egui::DragValue::new(value)
+        .speed(0.1)
+        .range(0.001..=1_000_000.0)
+        .suffix(" Hz")
+}
+
+fn show_order(ui: &mut egui::Ui, order: &mut usize) {
+    ComboBox::from_label("Order")
+        .selected_text(order.to_string())
+        .show_ui(ui, |ui| {
+            for value in (2..=MAX_ORDER).step_by(2) {
+                ui.selectable_value(order, value, value.to_string());
+            }
+        });
+}
@@ -1,17 +1,26 @@
 use egui::{Key, RichText};
 use egui_phosphor::regular;
+use plot_util::Plots;
 
-use super::{axis_config::AxisConfig, plot_settings::PlotSettings};
+use super::{
+    axis_config::AxisConfig, pid_analysis_panel::PidAnalysisPanel, plot_settings::PlotSettings,
+    spectrogram_panel::SpectrogramPanel, spectrum_panel::SpectrumPanel,
+};
 
 // filter settings should be refactored out to be a standalone thing, maybe together with loaded_logs_ui
 pub fn show_settings_grid(
     ui: &mut egui::Ui,
     line_width: &mut f32,
     axis_cfg: &mut AxisConfig,
-    plot_settings: &mut PlotSettings,
+    (plot_settings, plots): (&mut PlotSettings, &Plots),
+    (spectrum, spectrogram, pid_analysis): (
+        &mut SpectrumPanel,
+        &mut SpectrogramPanel,
+        &mut PidAnalysisPanel,
+    ),
 ) {
     ui.horizontal_wrapped(|ui| {
-        plot_settings.show(ui);
+        plot_settings.show(ui, plots);
         ui.label("|");
         let axis_cfg_str = RichText::new(format!("{} Axis config", regular::GEAR));
         if ui.button(axis_cfg_str.clone()).clicked() {
@@ -29,6 +38,9 @@ pub fn show_settings_grid(
         if ui.ctx().input(|i| i.key_pressed(Key::Escape)) {
             axis_cfg.ui_visible = false;
         }
+        spectrum.toggle_ui(ui);
+        spectrogram.toggle_ui(ui);
+        pid_analysis.toggle_ui(ui, plot_settings);
         ui.label("Line width");
         ui.add(
             egui::DragValue::new(line_width)
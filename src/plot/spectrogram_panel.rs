@@ -0,0 +1,255 @@
+use egui::{Color32, ColorImage, ComboBox, RichText, TextureHandle, TextureOptions};
+use egui_phosphor::regular;
+use egui_plot::{GridMark, Plot, PlotImage, PlotPoint};
+use plot_util::{
+    spectrum::{Spectrogram, WindowFunction, MIN_SEGMENT_LEN},
+    Plots,
+};
+use serde::{Deserialize, Serialize};
+
+const SEGMENT_LENGTHS: [usize; 7] = [128, 256, 512, 1024, 2048, 4096, 8192];
+/// The spectrogram of a whole recording is at most this many pixels wide
+const MAX_COLUMNS: usize = 2048;
+
+/// Low to high power, a perceptually uniform dark purple to light yellow gradient
+const COLORMAP: [Color32; 5] = [
+    Color32::from_rgb(0, 0, 4),
+    Color32::from_rgb(87, 16, 110),
+    Color32::from_rgb(188, 55, 84),
+    Color32::from_rgb(249, 142, 9),
+    Color32::from_rgb(252, 255, 164),
+];
+
+/// What the cached spectrogram was computed from, it's computed again when any of it changes
+#[derive(Debug, Clone, PartialEq)]
+struct SpectrogramKey {
+    label: String,
+    window: WindowFunction,
+    segment_len: usize,
+}
+
+/// The image of a spectrogram, kept until the spectrogram or the dynamic range changes
+#[derive(PartialEq)]
+struct SpectrogramTexture {
+    dynamic_range_db: f64,
+    texture: TextureHandle,
+}
+
+/// A window with the spectrogram of a series over the whole recording, with its time axis linked to the plots so
+/// changes in the spectrum can be matched to events in the other plots.
+#[allow(
+    missing_debug_implementations,
+    reason = "egui's TextureHandle does not implement Debug"
+)]
+#[derive(PartialEq, Deserialize, Serialize)]
+pub struct SpectrogramPanel {
+    open: bool,
+    /// The label of the selected series, see [`plot_util::PlotValues::label`]
+    selected: Option<String>,
+    window: WindowFunction,
+    segment_len: usize,
+    /// Power this far (dB) below the highest power is drawn with the lowest color
+    dynamic_range_db: f64,
+    #[serde(skip)]
+    cached: Option<(SpectrogramKey, Option<Spectrogram>)>,
+    #[serde(skip)]
+    texture: Option<SpectrogramTexture>,
+}
+
+impl Default for SpectrogramPanel {
+    fn default() -> Self {
+        Self {
+            open: false,
+            selected: None,
+            window: WindowFunction::default(),
+            segment_len: 1024,
+            dynamic_range_db: 80.0,
+            cached: None,
+            texture: None,
+        }
+    }
+}
+
+impl SpectrogramPanel {
+    pub fn title() -> String {
+        format!("{} Spectrogram", regular::CHART_BAR_HORIZONTAL)
+    }
+
+    pub fn toggle_ui(&mut self, ui: &mut egui::Ui) {
+        ui.toggle_value(&mut self.open, Self::title())
+            .on_hover_text("Show how the spectrum of a plot changes over the whole recording");
+    }
+
+    /// Shows the spectrogram window if it is open, its time axis is linked to the plots of the `link_group`
+    pub fn show(&mut self, ctx: &egui::Context, plots: &Plots, link_group: egui::Id) {
+        let mut open = self.open;
+        egui::Window::new(Self::title())
+            .open(&mut open)
+            .default_size([800.0, 300.0])
+            .show(ctx, |ui| {
+                self.show_controls(ui, plots);
+                self.update_spectrogram(plots);
+                self.show_spectrogram(ui, plots, link_group);
+            });
+        self.open = open;
+    }
+
+    fn show_controls(&mut self, ui: &mut egui::Ui, plots: &Plots) {
+        ui.horizontal_wrapped(|ui| {
+            ComboBox::from_id_salt("spectrogram_series")
+                .selected_text(self.selected.as_deref().unwrap_or("Select a plot"))
+                .width(220.0)
+                .show_ui(ui, |ui| {
+                    for plot in plots.iter() {
+                        ui.selectable_value(
+                            &mut self.selected,
+                            Some(plot.label().to_owned()),
+                            plot.label(),
+                        );
+                    }
+                });
+            ComboBox::from_label("Window")
+                .selected_text(self.window.to_string())
+                .show_ui(ui, |ui| {
+                    for window in WindowFunction::ALL {
+                        ui.selectable_value(&mut self.window, window, window.to_string());
+                    }
+                });
+            ComboBox::from_label("Segment")
+                .selected_text(self.segment_len.to_string())
+                .show_ui(ui, |ui| {
+                    for len in SEGMENT_LENGTHS {
+                        ui.selectable_value(&mut self.segment_len, len, len.to_string());
+                    }
+                })
+                .response
+                .on_hover_text("Samples per FFT segment, longer segments resolve finer frequencies but blur changes over time");
+            ui.label("Dynamic range");
+            ui.add(
+                egui::DragValue::new(&mut self.dynamic_range_db)
+                    .range(10.0..=200.0)
+                    .suffix(" dB"),
+            );
+        });
+    }
+
+    fn update_spectrogram(&mut self, plots: &Plots) {
+        let Some(plot) = self
+            .selected
+            .as_deref()
+            .and_then(|label| plots.find_by_label(label))
+        else {
+            self.cached = None;
+            self.texture = None;
+            return;
+        };
+        let key = SpectrogramKey {
+            label: plot.label().to_owned(),
+            window: self.window,
+            segment_len: self.segment_len,
+        };
+        if self
+            .cached
+            .as_ref()
+            .is_some_and(|(cached, _)| *cached == key)
+        {
+            return;
+        }
+        let spectrogram = plot.spectrogram(self.window, self.segment_len, MAX_COLUMNS);
+        self.cached = Some((key, spectrogram));
+        self.texture = None;
+    }
+
+    fn show_spectrogram(&mut self, ui: &mut egui::Ui, plots: &Plots, link_group: egui::Id) {
+        let (label, spectrogram) = match &self.cached {
+            None => {
+                ui.label("Select a plot to show its spectrogram");
+                return;
+            }
+            Some((_, None)) => {
+                ui.label(
+                    RichText::new(format!(
+                        "Too few samples, at least {MIN_SEGMENT_LEN} are needed"
+                    ))
+                    .color(Color32::RED),
+                );
+                return;
+            }
+            Some((key, Some(spectrogram))) => (&key.label, spectrogram),
+        };
+        if !self
+            .texture
+            .as_ref()
+            .is_some_and(|texture| texture.dynamic_range_db == self.dynamic_range_db)
+        {
+            self.texture = Some(SpectrogramTexture {
+                dynamic_range_db: self.dynamic_range_db,
+                texture: ui.ctx().load_texture(
+                    "spectrogram",
+                    spectrogram_image(spectrogram, self.dynamic_range_db),
+                    TextureOptions::NEAREST,
+                ),
+            });
+        }
+        let Some(texture) = &self.texture else {
+            return;
+        };
+        // The plot may have been moved in time since the spectrogram was computed
+        let x_offset = plots
+            .find_by_label(label)
+            .map_or(0.0, |plot| plot.x_offset());
+        let (x_min, x_max) = spectrogram.x_range();
+        let frequency_span = spectrogram.frequency_bins() as f64 * spectrogram.resolution();
+        let max_frequency = frequency_span - spectrogram.resolution();
+
+        ui.label(format!(
+            "{} columns, resolution {:.4} Hz, up to {max_frequency:.2} Hz",
+            spectrogram.columns(),
+            spectrogram.resolution(),
+        ));
+        Plot::new("spectrogram_plot")
+            .link_axis(link_group, true, false)
+            .x_axis_formatter(crate::util::format_time)
+            .y_axis_formatter(|mark: GridMark, _range| format!("{:.1} Hz", mark.value))
+            .label_formatter(|_name, point: &PlotPoint| {
+                crate::util::format_label_ns("Frequency [Hz]", point)
+            })
+            .show(ui, |plot_ui| {
+                plot_ui.image(PlotImage::new(
+                    &texture.texture,
+                    PlotPoint::new((x_min + x_max) / 2.0 + x_offset, max_frequency / 2.0),
+                    [(x_max - x_min) as f32, frequency_span as f32],
+                ));
+            });
+    }
+}
+
+/// An image with a column of pixels per column of the spectrogram, the highest frequency at the top. The power is in
+/// dB, from `dynamic_range_db` below the highest power to the highest power, columns without samples are transparent.
+fn spectrogram_image(spectrogram: &Spectrogram, dynamic_range_db: f64) -> ColorImage {
+    let size = [spectrogram.columns(), spectrogram.frequency_bins()];
+    let [columns, rows] = size;
+    let max_db = spectrogram
+        .power_range()
+        .map_or(0.0, |(_, max)| 10.0 * max.log10());
+    let min_db = max_db - dynamic_range_db;
+    let mut pixels = vec![Color32::TRANSPARENT; columns * rows];
+    for column in 0..columns {
+        for (bin, power) in spectrogram.column(column).iter().enumerate() {
+            if power.is_nan() {
+                continue;
+            }
+            let db = 10.0 * power.max(f64::MIN_POSITIVE).log10();
+            let normalized = ((db - min_db) / dynamic_range_db).clamp(0.0, 1.0);
+            pixels[(rows - 1 - bin) * columns + column] = colormap(normalized as f32);
+        }
+    }
+    ColorImage { size, pixels }
+}
+
+/// The color of a value between 0 and 1 in the [`COLORMAP`]
+fn colormap(value: f32) -> Color32 {
+    let scaled = value * (COLORMAP.len() - 1) as f32;
+    let idx = (scaled as usize).min(COLORMAP.len() - 2);
+    COLORMAP[idx].lerp_to_gamma(COLORMAP[idx + 1], scaled - idx as f32)
+}
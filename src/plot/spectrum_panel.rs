@@ -0,0 +1,238 @@
+use egui::{Color32, ComboBox, RichText};
+use egui_phosphor::regular;
+use egui_plot::{GridMark, Line, MarkerShape, Plot, PlotPoint, PlotPoints, Points, Text};
+use plot_util::{
+    spectrum::{Spectrum, WindowFunction, MIN_SEGMENT_LEN},
+    Plots,
+};
+use serde::{Deserialize, Serialize};
+
+const SEGMENT_LENGTHS: [usize; 8] = [256, 512, 1024, 2048, 4096, 8192, 16384, 32768];
+const MAX_PEAKS: usize = 20;
+const PEAK_COLOR: Color32 = Color32::from_rgb(230, 40, 30);
+
+/// What the cached spectrum was computed from, it's computed again when any of it changes
+#[derive(Debug, Clone, PartialEq)]
+struct SpectrumKey {
+    label: String,
+    x_range: (f64, f64),
+    x_offset: f64,
+    window: WindowFunction,
+    segment_len: usize,
+}
+
+/// A window with the power spectral density of a series over the time range that is visible in the plots, which
+/// follows along when the plots are panned or zoomed.
+#[derive(Debug, PartialEq, Deserialize, Serialize)]
+pub struct SpectrumPanel {
+    open: bool,
+    /// The label of the selected series, see [`plot_util::PlotValues::label`]
+    selected: Option<String>,
+    window: WindowFunction,
+    segment_len: usize,
+    log_frequency: bool,
+    /// Show the power in decibels
+    log_power: bool,
+    peak_count: usize,
+    #[serde(skip)]
+    cached: Option<(SpectrumKey, Option<Spectrum>)>,
+}
+
+impl Default for SpectrumPanel {
+    fn default() -> Self {
+        Self {
+            open: false,
+            selected: None,
+            window: WindowFunction::default(),
+            segment_len: 4096,
+            log_frequency: false,
+            log_power: true,
+            peak_count: 3,
+            cached: None,
+        }
+    }
+}
+
+impl SpectrumPanel {
+    pub fn title() -> String {
+        format!("{} Spectrum", regular::WAVEFORM)
+    }
+
+    pub fn toggle_ui(&mut self, ui: &mut egui::Ui) {
+        ui.toggle_value(&mut self.open, Self::title())
+            .on_hover_text("Show the power spectral density of a plot over the visible time range");
+    }
+
+    /// Shows the spectrum window if it is open. `x_range` is the time range visible in the plots, [`None`] if no
+    /// plots are shown.
+    pub fn show(&mut self, ctx: &egui::Context, plots: &Plots, x_range: Option<(f64, f64)>) {
+        let mut open = self.open;
+        egui::Window::new(Self::title())
+            .open(&mut open)
+            .default_size([600.0, 350.0])
+            .show(ctx, |ui| {
+                self.show_controls(ui, plots);
+                let Some(x_range) = x_range else {
+                    ui.label("No plots are shown");
+                    return;
+                };
+                self.update_spectrum(plots, x_range);
+                self.show_spectrum(ui);
+            });
+        self.open = open;
+    }
+
+    fn show_controls(&mut self, ui: &mut egui::Ui, plots: &Plots) {
+        ui.horizontal_wrapped(|ui| {
+            ComboBox::from_id_salt("spectrum_series")
+                .selected_text(self.selected.as_deref().unwrap_or("Select a plot"))
+                .width(220.0)
+                .show_ui(ui, |ui| {
+                    for plot in plots.iter() {
+                        ui.selectable_value(
+                            &mut self.selected,
+                            Some(plot.label().to_owned()),
+                            plot.label(),
+                        );
+                    }
+                });
+            ComboBox::from_label("Window")
+                .selected_text(self.window.to_string())
+                .show_ui(ui, |ui| {
+                    for window in WindowFunction::ALL {
+                        ui.selectable_value(&mut self.window, window, window.to_string());
+                    }
+                });
+            ComboBox::from_label("Segment")
+                .selected_text(self.segment_len.to_string())
+                .show_ui(ui, |ui| {
+                    for len in SEGMENT_LENGTHS {
+                        ui.selectable_value(&mut self.segment_len, len, len.to_string());
+                    }
+                })
+                .response
+                .on_hover_text("Samples per FFT segment, longer segments resolve finer frequencies but average fewer segments");
+            ui.checkbox(&mut self.log_frequency, "Log frequency");
+            ui.checkbox(&mut self.log_power, "Power in dB");
+            ui.label("Peaks");
+            ui.add(egui::DragValue::new(&mut self.peak_count).range(0..=MAX_PEAKS));
+        });
+    }
+
+    fn update_spectrum(&mut self, plots: &Plots, x_range: (f64, f64)) {
+        let Some(plot) = self
+            .selected
+            .as_deref()
+            .and_then(|label| plots.find_by_label(label))
+        else {
+            self.cached = None;
+            return;
+        };
+        let key = SpectrumKey {
+            label: plot.label().to_owned(),
+            x_range,
+            x_offset: plot.x_offset(),
+            window: self.window,
+            segment_len: self.segment_len,
+        };
+        if self
+            .cached
+            .as_ref()
+            .is_some_and(|(cached, _)| *cached == key)
+        {
+            return;
+        }
+        let spectrum = plot.power_spectral_density(x_range, self.window, self.segment_len);
+        self.cached = Some((key, spectrum));
+    }
+
+    fn show_spectrum(&self, ui: &mut egui::Ui) {
+        let spectrum = match &self.cached {
+            None => {
+                ui.label("Select a plot to show its spectrum");
+                return;
+            }
+            Some((_, None)) => {
+                ui.label(
+                    RichText::new(format!(
+                        "Too few samples in the visible range, at least {MIN_SEGMENT_LEN} are needed"
+                    ))
+                    .color(Color32::RED),
+                );
+                return;
+            }
+            Some((_, Some(spectrum))) => spectrum,
+        };
+        ui.label(format!(
+            "Sample rate {:.2} Hz, resolution {:.4} Hz, {} segments of {} samples",
+            spectrum.sample_rate(),
+            spectrum.resolution(),
+            spectrum.segments(),
+            spectrum.segment_len()
+        ));
+
+        let (log_frequency, log_power) = (self.log_frequency, self.log_power);
+        let to_plot_point = move |[frequency, power]: [f64; 2]| {
+            // The mean of each segment is removed, so 0 Hz carries no information
+            (frequency > 0.0 && (!log_power || power > 0.0)).then(|| {
+                [
+                    if log_frequency {
+                        frequency.log10()
+                    } else {
+                        frequency
+                    },
+                    if log_power {
+                        10.0 * power.log10()
+                    } else {
+                        power
+                    },
+                ]
+            })
+        };
+        let to_frequency = move |x: f64| if log_frequency { 10f64.powf(x) } else { x };
+        let power_unit = if log_power { "dB" } else { "units²/Hz" };
+
+        Plot::new("spectrum_plot")
+            .x_axis_formatter(move |mark: GridMark, _range| {
+                format!("{:.3} Hz", to_frequency(mark.value))
+            })
+            .y_axis_formatter(move |mark: GridMark, _range| {
+                format!("{:.3} {power_unit}", mark.value)
+            })
+            .label_formatter(move |_name, point: &PlotPoint| {
+                format!(
+                    "{:.3} Hz\n{:.4} {power_unit}",
+                    to_frequency(point.x),
+                    point.y
+                )
+            })
+            .show(ui, |plot_ui| {
+                let line: PlotPoints = spectrum
+                    .points()
+                    .iter()
+                    .filter_map(|&point| to_plot_point(point))
+                    .collect();
+                plot_ui.line(Line::new(line).name("PSD"));
+
+                let peaks: Vec<[f64; 2]> = spectrum
+                    .peaks(self.peak_count)
+                    .into_iter()
+                    .filter_map(to_plot_point)
+                    .collect();
+                for &[x, y] in &peaks {
+                    plot_ui.text(
+                        Text::new(PlotPoint::new(x, y), format!("{:.2} Hz", to_frequency(x)))
+                            .color(PEAK_COLOR)
+                            .anchor(egui::Align2::CENTER_BOTTOM),
+                    );
+                }
+                plot_ui.points(
+                    Points::new(peaks)
+                        .name("Peaks")
+                        .shape(MarkerShape::Down)
+                        .radius(4.0)
+                        .color(PEAK_COLOR),
+                );
+            });
+    }
+}
@@ -18,7 +18,7 @@ pub fn add_plot_data_to_plot_collections(
         log.descriptive_name().to_owned(),
         log.first_timestamp(),
         log.metadata(),
-        log.parse_info(),
+        log.parse_info().cloned(),
     ));
     for raw_plot in log.raw_plots() {
         match raw_plot.expected_range() {